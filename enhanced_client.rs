@@ -1,27 +1,16 @@
 use colorgrad::Gradient;
 use image::{ImageBuffer, Rgb, RgbImage};
-use serde::{Deserialize, Serialize};
+use rust_tcp_server::field::{is_missing, mean_present};
+use rust_tcp_server::protocol::{
+    Compression, PolarGrid, RadarClient, RadarConfig, RadarSweep, SampleFormat, SampleUnit,
+    StreamHeader, Subscription, SweepField,
+};
 use std::collections::VecDeque;
 use std::error::Error;
 use std::sync::{Arc, Mutex};
-use tokio::io::AsyncReadExt;
-use tokio::net::TcpStream;
 use tokio::spawn;
 use tokio::time::{sleep, Duration, Instant};
 
-// Import from the server modules
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct RadarSweep {
-    timestamp: u64,
-    sequence_id: u64,
-    azimuth_start: f32,
-    azimuth_end: f32,
-    range_bins: Vec<f32>,
-    data: Vec<Vec<f32>>,
-    overlap_region: Vec<Vec<f32>>,
-    client_id: usize,
-}
-
 // Double buffering structure for efficient data handling
 #[derive(Debug)]
 struct DoubleBuffer {
@@ -108,11 +97,11 @@ impl SlidingWindowProcessor {
         }
     }
 
-    fn try_merge_next_frame(&mut self) -> Option<MergedRadarFrame> {
+    fn try_merge_next_frame(&mut self, radar_config: &RadarConfig) -> Option<MergedRadarFrame> {
         // Find synchronized frames with matching sequence IDs
         if let Some((sweep1, sweep2)) = self.find_synchronized_pair() {
             self.processed_frames += 1;
-            Some(self.merge_sweeps(sweep1, sweep2))
+            Some(self.merge_sweeps(sweep1, sweep2, radar_config))
         } else {
             None
         }
@@ -160,53 +149,84 @@ impl SlidingWindowProcessor {
         None
     }
 
-    fn merge_sweeps(&self, client1: RadarSweep, client2: RadarSweep) -> MergedRadarFrame {
-        let mut complete_data = Vec::new();
-
-        // Client 1: 0-170° (exclude overlap)
-        let client1_main = &client1.data[0..170.min(client1.data.len())];
-        complete_data.extend_from_slice(client1_main);
-
-        // Overlap region: 170-190° (average both clients)
-        let overlap_merged =
-            self.merge_overlap_region(&client1.overlap_region, &client2.overlap_region);
-        complete_data.extend(overlap_merged);
-
-        // Client 2: 190-360° (skip overlap portion)
-        if client2.data.len() > 20 {
-            let client2_main = &client2.data[20..];
-            complete_data.extend_from_slice(client2_main);
-        }
+    fn merge_sweeps(
+        &self,
+        client1: RadarSweep,
+        client2: RadarSweep,
+        radar_config: &RadarConfig,
+    ) -> MergedRadarFrame {
+        let overlap_bins =
+            (radar_config.overlap_degrees / radar_config.azimuth_resolution_deg).round() as usize;
+
+        // Fields both clients received, each stitched together on its own
+        let fields = client1
+            .fields
+            .iter()
+            .filter_map(|field1| {
+                let field2 = client2.field(&field1.name)?;
+                let merged = self.merge_field(
+                    (&field1.data, &field1.overlap_region),
+                    (&field2.data, &field2.overlap_region),
+                    overlap_bins,
+                );
+                Some(SweepField::new(&field1.name, field1.units, merged))
+            })
+            .collect();
 
         MergedRadarFrame {
             sequence_id: client1.sequence_id,
             timestamp: client1.timestamp,
+            volume_id: client1.volume_id,
+            elevation_deg: client1.elevation_deg,
             range_bins: client1.range_bins,
-            complete_data,
-            azimuth_resolution: 1.0, // 1 degree per bin
+            fields,
+            azimuth_resolution: radar_config.azimuth_resolution_deg,
         }
     }
 
-    fn merge_overlap_region(&self, overlap1: &[Vec<f32>], overlap2: &[Vec<f32>]) -> Vec<Vec<f32>> {
-        let mut merged = Vec::new();
-        let max_len = overlap1.len().max(overlap2.len());
+    // One field of both portions as a single 360° grid: (data, overlap region) of each client
+    fn merge_field(
+        &self,
+        client1: (&PolarGrid, &PolarGrid),
+        client2: (&PolarGrid, &PolarGrid),
+        overlap_bins: usize,
+    ) -> PolarGrid {
+        let (data1, overlap1) = client1;
+        let (data2, overlap2) = client2;
+
+        // Client 1: 0-170° (exclude overlap)
+        let client1_bins = data1.azimuth_count().saturating_sub(overlap_bins);
+        let mut complete_data = data1.slice_rows(0..client1_bins);
+
+        // Overlap region: 170-190° (average both clients)
+        let overlap_merged = self.merge_overlap_region(overlap1, overlap2);
+        complete_data.append(&overlap_merged);
+
+        // Client 2: 190-360° (skip overlap portion)
+        complete_data.append(&data2.slice_rows(overlap_bins..data2.azimuth_count()));
+        complete_data
+    }
+
+    fn merge_overlap_region(&self, overlap1: &PolarGrid, overlap2: &PolarGrid) -> PolarGrid {
+        let mut merged = PolarGrid::new(0, overlap1.range_count());
+        let max_len = overlap1.azimuth_count().max(overlap2.azimuth_count());
 
         for i in 0..max_len {
-            let row1 = overlap1.get(i);
-            let row2 = overlap2.get(i);
+            let row1 = overlap1.row(i);
+            let row2 = overlap2.row(i);
 
             match (row1, row2) {
                 (Some(r1), Some(r2)) => {
-                    // Average the overlapping data
+                    // Average the overlapping data, keeping whichever side is present
                     let averaged: Vec<f32> = r1
                         .iter()
                         .zip(r2.iter())
-                        .map(|(a, b)| (a + b) / 2.0)
+                        .map(|(&a, &b)| mean_present([a, b]))
                         .collect();
-                    merged.push(averaged);
+                    merged.push_row(&averaged);
                 }
-                (Some(r1), None) => merged.push(r1.clone()),
-                (None, Some(r2)) => merged.push(r2.clone()),
+                (Some(r1), None) => merged.push_row(r1),
+                (None, Some(r2)) => merged.push_row(r2),
                 (None, None) => break,
             }
         }
@@ -220,15 +240,16 @@ impl SlidingWindowProcessor {
 struct MergedRadarFrame {
     sequence_id: u64,
     timestamp: u64,
+    volume_id: u64,     // Volume scan the tilt belongs to
+    elevation_deg: f32, // Antenna elevation of the tilt
     range_bins: Vec<f32>,
-    complete_data: Vec<Vec<f32>>, // [azimuth][range]
+    fields: Vec<SweepField>, // Complete 360° fields, echo strength first
     azimuth_resolution: f32,
 }
 
 // Image processor for PNG generation
 struct RadarImageProcessor {
     gradient: Gradient,
-    value_range: (f32, f32),
     apply_log_scale: bool,
 }
 
@@ -239,18 +260,13 @@ impl RadarImageProcessor {
 
         Self {
             gradient,
-            value_range: (0.0, 1.0),
             apply_log_scale: true,
         }
     }
 
-    fn process_and_save(
-        &self,
-        frame: &MergedRadarFrame,
-        filename: &str,
-    ) -> Result<(), Box<dyn Error>> {
-        let width = frame.complete_data.len() as u32;
-        let height = frame.complete_data.get(0).map_or(0, |row| row.len()) as u32;
+    fn process_and_save(&self, field: &SweepField, filename: &str) -> Result<(), Box<dyn Error>> {
+        let width = field.data.azimuth_count() as u32;
+        let height = field.data.range_count() as u32;
 
         if width == 0 || height == 0 {
             return Err("Invalid frame dimensions".into());
@@ -262,21 +278,18 @@ impl RadarImageProcessor {
         let mut img: RgbImage = ImageBuffer::new(width, height);
 
         // Find value range for normalization
-        let (min_val, max_val) = self.find_value_range(frame);
+        let (min_val, max_val) = self.find_value_range(field);
 
         // Process each pixel
-        for (x, azimuth_data) in frame.complete_data.iter().enumerate() {
+        for (x, azimuth_data) in field.data.rows().enumerate() {
             for (y, &intensity) in azimuth_data.iter().enumerate() {
+                // Missing samples stay black
+                if is_missing(intensity) {
+                    continue;
+                }
+
                 // Apply mathematical processing
-                let processed_value = if self.apply_log_scale {
-                    if intensity > 0.0 {
-                        (intensity.abs().ln() + 1.0).max(0.0)
-                    } else {
-                        0.0
-                    }
-                } else {
-                    intensity.abs()
-                };
+                let processed_value = self.process_value(intensity, field);
 
                 // Normalize to 0-1 range
                 let normalized = if max_val > min_val {
@@ -306,21 +319,28 @@ impl RadarImageProcessor {
         Ok(())
     }
 
-    fn find_value_range(&self, frame: &MergedRadarFrame) -> (f32, f32) {
+    // dBm and dBZ samples are already logarithmic, velocities are signed
+    fn process_value(&self, intensity: f32, field: &SweepField) -> f32 {
+        if field.units.is_logarithmic() || field.units == SampleUnit::MetersPerSecond {
+            intensity
+        } else if self.apply_log_scale {
+            if intensity > 0.0 {
+                (intensity.abs().ln() + 1.0).max(0.0)
+            } else {
+                0.0
+            }
+        } else {
+            intensity.abs()
+        }
+    }
+
+    fn find_value_range(&self, field: &SweepField) -> (f32, f32) {
         let mut min_val = f32::INFINITY;
         let mut max_val = f32::NEG_INFINITY;
 
-        for azimuth_data in &frame.complete_data {
-            for &intensity in azimuth_data {
-                let processed = if self.apply_log_scale {
-                    if intensity > 0.0 {
-                        (intensity.abs().ln() + 1.0).max(0.0)
-                    } else {
-                        0.0
-                    }
-                } else {
-                    intensity.abs()
-                };
+        for azimuth_data in field.data.rows() {
+            for &intensity in azimuth_data.iter().filter(|&&value| !is_missing(value)) {
+                let processed = self.process_value(intensity, field);
 
                 min_val = min_val.min(processed);
                 max_val = max_val.max(processed);
@@ -333,11 +353,12 @@ impl RadarImageProcessor {
 
 async fn receive_radar_data(
     port: u16,
+    sample_format: SampleFormat,
+    compression: Compression,
     buffer: Arc<Mutex<DoubleBuffer>>,
+    stream_header: Arc<Mutex<Option<StreamHeader>>>,
 ) -> Result<(), Box<dyn Error>> {
-    use tokio::io::AsyncWriteExt;
-
-    let mut stream = TcpStream::connect(format!("127.0.0.1:{}", port)).await?;
+    let mut client = RadarClient::connect("127.0.0.1", port).await?;
     println!("Connected to radar server on port {}", port);
 
     // Determine send delay based on port (0s for 8080, 10s for 8081)
@@ -357,30 +378,36 @@ async fn receive_radar_data(
     sleep(send_delay).await;
 
     // Send SEND_DATA command
-    stream.write_all(b"SEND_DATA").await?;
-    stream.flush().await?;
-    println!("✅ Sent 'SEND_DATA' command to server on port {}", port);
-
-    loop {
-        // Read the size of the incoming data
-        let data_size = stream.read_u64().await?;
+    if compression != Compression::None {
+        client.set_compression(compression).await?;
+    }
 
-        // Read the serialized data
-        let mut data_buffer = vec![0u8; data_size as usize];
-        stream.read_exact(&mut data_buffer).await?;
+    // Quantized sweeps are decoded back to floats by `next_sweep`
+    client
+        .subscribe(Subscription {
+            sample_format: Some(sample_format),
+            ..Subscription::default()
+        })
+        .await?;
+    println!("✅ Sent 'SEND_DATA' command to server on port {}", port);
 
-        // Deserialize the radar sweep
-        let radar_sweep: RadarSweep = bincode::deserialize(&data_buffer)?;
+    while let Some(radar_sweep) = client.next_sweep().await? {
+        // The server's geometry and sample units arrive in the stream header
+        if let Some(header) = client.stream_header() {
+            *stream_header.lock().unwrap() = Some(header.clone());
+        }
 
         println!(
-            "[Port {}] Received sweep {} (Client {}): Az {:.1}°-{:.1}°, {} azimuth bins, {} range bins",
+            "[Port {}] Received sweep {} (Client {}, tilt {} at {:.1}°): Az {:.1}°-{:.1}°, {} azimuth bins, {} range bins",
             port,
             radar_sweep.sequence_id,
             radar_sweep.client_id,
+            radar_sweep.tilt,
+            radar_sweep.elevation_deg,
             radar_sweep.azimuth_start,
             radar_sweep.azimuth_end,
-            radar_sweep.data.len(),
-            radar_sweep.data.get(0).map_or(0, |row| row.len())
+            radar_sweep.azimuth_count(),
+            radar_sweep.range_count()
         );
 
         // Add to double buffer
@@ -389,11 +416,16 @@ async fn receive_radar_data(
             buffer_guard.add_sweep(radar_sweep);
         }
     }
+
+    println!("Radar server on port {} closed the connection", port);
+    Ok(())
 }
 
 async fn process_radar_data(
     client1_buffer: Arc<Mutex<DoubleBuffer>>,
     client2_buffer: Arc<Mutex<DoubleBuffer>>,
+    stream_header: Arc<Mutex<Option<StreamHeader>>>,
+    render_fields: Option<Vec<String>>,
 ) -> Result<(), Box<dyn Error>> {
     let mut processor = SlidingWindowProcessor::new(10); // 10-frame sliding window
     let image_processor = RadarImageProcessor::new();
@@ -433,28 +465,56 @@ async fn process_radar_data(
         }
 
         // Try to merge and process frames
-        while let Some(merged_frame) = processor.try_merge_next_frame() {
+        let config = match &*stream_header.lock().unwrap() {
+            Some(header) => header.radar,
+            None => RadarConfig::default(), // Until the header arrives
+        };
+        while let Some(merged_frame) = processor.try_merge_next_frame(&config) {
             println!(
-                "Merged frame {} at timestamp {} (360° complete, {} range bins)",
+                "Merged frame {} at timestamp {} (volume {} at {:.1}°, 360° complete at {:.1}°/bin, {} range bins)",
                 merged_frame.sequence_id,
                 merged_frame.timestamp,
+                merged_frame.volume_id,
+                merged_frame.elevation_deg,
+                merged_frame.azimuth_resolution,
                 merged_frame.range_bins.len()
             );
+            for field in merged_frame.fields.iter().skip(1) {
+                if let Some((min, max)) = field.value_range() {
+                    println!(
+                        "   🌀 {}: {:.1} to {:.1} {}",
+                        field.name, min, max, field.units
+                    );
+                }
+            }
 
-            // Generate PNG every frame since server runs at 1Hz now
-            if merged_frame.sequence_id % 1 == 0 {
-                let filename = format!("radar_frame_{:06}.png", merged_frame.sequence_id);
+            let save_dir = std::env::current_dir()
+                .unwrap_or_else(|_| std::path::PathBuf::from("."))
+                .join("radar_images");
+            std::fs::create_dir_all(&save_dir)
+                .unwrap_or_else(|_| panic!("Failed to create directory for images"));
+
+            // Generate PNGs every frame since server runs at 1Hz now, one per field
+            for (index, field) in merged_frame.fields.iter().enumerate() {
+                if let Some(names) = &render_fields {
+                    if !names.contains(&field.name) {
+                        continue;
+                    }
+                }
 
-                let current_dir = std::env::current_dir();
-                let save_path = current_dir
-                    .unwrap_or_else(|_| std::path::PathBuf::from("."))
-                    .join("radar_images")
-                    .join(&filename);
-                std::fs::create_dir_all(save_path.parent().unwrap())
-                    .unwrap_or_else(|_| panic!("Failed to create directory for images"));
+                // The echo-strength image keeps its plain name
+                let filename = if index == 0 {
+                    format!("radar_frame_{:06}.png", merged_frame.sequence_id)
+                } else {
+                    format!(
+                        "radar_frame_{:06}_{}.png",
+                        merged_frame.sequence_id, field.name
+                    )
+                };
+                let save_path = save_dir.join(&filename);
 
                 if let Err(e) =
-                    image_processor.process_and_save(&merged_frame, &save_path.to_string_lossy())
+                    image_processor.process_and_save(field, &save_path.to_string_lossy())
                 {
                     eprintln!("Failed to save image {}: {}", filename, e);
                 } else {
@@ -471,30 +531,72 @@ async fn process_radar_data(
 async fn main() -> Result<(), Box<dyn Error>> {
     println!("🎯 Enhanced Radar Client with Double Buffering & Sliding Window Merging");
     println!("📡 Connecting to radar data streams...");
+
+    // Optional stream encoding and fields to render:
+    // enhanced_client [f32|u8|u16|dbz] [none|rle|lz4] [reflectivity,velocity,...]
+    let sample_format: SampleFormat = match std::env::args().nth(1) {
+        Some(format) => format.parse()?,
+        None => SampleFormat::default(),
+    };
+    let compression: Compression = match std::env::args().nth(2) {
+        Some(compression) => compression.parse()?,
+        None => Compression::default(),
+    };
+    let render_fields: Option<Vec<String>> = std::env::args().nth(3).map(|names| {
+        names
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .collect()
+    });
+    println!(
+        "🗜️  Requesting {} samples with {} compression",
+        sample_format, compression
+    );
     println!("⏰ Timing: Client 1 (8080) sends SEND_DATA at 0s, Client 2 (8081) at 10s");
 
     // Create double buffers for each client
     let client1_buffer = Arc::new(Mutex::new(DoubleBuffer::new(20)));
     let client2_buffer = Arc::new(Mutex::new(DoubleBuffer::new(20)));
+    let stream_header = Arc::new(Mutex::new(None)); // Until the header arrives
 
     // Start data receivers for both clients
     let client1_buffer_clone = Arc::clone(&client1_buffer);
+    let header_clone = Arc::clone(&stream_header);
     let receiver1 = spawn(async move {
-        if let Err(e) = receive_radar_data(8080, client1_buffer_clone).await {
+        if let Err(e) = receive_radar_data(
+            8080,
+            sample_format,
+            compression,
+            client1_buffer_clone,
+            header_clone,
+        )
+        .await
+        {
             eprintln!("Client 1 receiver error: {}", e);
         }
     });
 
     let client2_buffer_clone = Arc::clone(&client2_buffer);
+    let header_clone = Arc::clone(&stream_header);
     let receiver2 = spawn(async move {
-        if let Err(e) = receive_radar_data(8081, client2_buffer_clone).await {
+        if let Err(e) = receive_radar_data(
+            8081,
+            sample_format,
+            compression,
+            client2_buffer_clone,
+            header_clone,
+        )
+        .await
+        {
             eprintln!("Client 2 receiver error: {}", e);
         }
     });
 
     // Start data processor
     let processor = spawn(async move {
-        if let Err(e) = process_radar_data(client1_buffer, client2_buffer).await {
+        if let Err(e) =
+            process_radar_data(client1_buffer, client2_buffer, stream_header, render_fields).await
+        {
             eprintln!("Data processor error: {}", e);
         }
     });
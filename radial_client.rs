@@ -0,0 +1,70 @@
+use rust_tcp_server::protocol::RadarClient;
+use rust_tcp_server::{SampleUnit, StreamMode, Subscription};
+use std::error::Error;
+use std::io::{self, Write};
+
+const LINE_WIDTH: usize = 64; // Characters per rendered radial
+const SHADES: &[u8] = b" .:-=+*#%@"; // Weak to strong echo
+
+// Follows the antenna: one line per radial, redrawn in place as it turns
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    // radial_client [port]
+    let port: u16 = match std::env::args().nth(1) {
+        Some(port) => port.parse()?,
+        None => 8080,
+    };
+
+    let mut client = RadarClient::connect("127.0.0.1", port).await?;
+    println!("📡 Connected to server on port {}", port);
+
+    client
+        .subscribe(Subscription {
+            stream_mode: Some(StreamMode::Radial),
+            ..Subscription::default()
+        })
+        .await?;
+    println!("Sent 'SUBSCRIBE mode=radial', following the antenna (Ctrl+C to stop)...");
+
+    let mut rotation = 0;
+    while let Some(radial) = client.next_radial().await? {
+        if radial.sequence_id != rotation {
+            rotation = radial.sequence_id;
+            println!();
+            println!("🔄 Rotation {}", rotation);
+        }
+
+        let unit = client.sample_unit();
+        let peak = radial
+            .samples
+            .iter()
+            .cloned()
+            .fold(f32::NEG_INFINITY, f32::max);
+        print!(
+            "\r{:>6.1}° |{}| peak {:.2} {}",
+            radial.azimuth,
+            render_radial(&radial.samples, unit),
+            peak,
+            unit
+        );
+        io::stdout().flush()?;
+    }
+
+    println!();
+    println!("Server closed the connection");
+    Ok(())
+}
+
+// Strongest sample of each range slice as a shade character, range increasing to the right
+fn render_radial(samples: &[f32], unit: SampleUnit) -> String {
+    let (weakest, strongest) = unit.display_range();
+    let slice_len = samples.len().div_ceil(LINE_WIDTH).max(1);
+    samples
+        .chunks(slice_len)
+        .map(|slice| {
+            let peak = slice.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+            let value = ((peak - weakest) / (strongest - weakest)).clamp(0.0, 1.0);
+            SHADES[(value * (SHADES.len() - 1) as f32).round() as usize] as char
+        })
+        .collect()
+}
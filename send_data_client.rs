@@ -1,20 +1,6 @@
-use serde::{Deserialize, Serialize};
+use rust_tcp_server::protocol::RadarClient;
 use std::error::Error;
 use std::io::{self, Write};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct RadarSweep {
-    timestamp: u64,
-    sequence_id: u64,
-    azimuth_start: f32,
-    azimuth_end: f32,
-    range_bins: Vec<f32>,
-    data: Vec<Vec<f32>>,
-    overlap_region: Vec<Vec<f32>>,
-    client_id: usize,
-}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -28,7 +14,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let port: u16 = input.trim().parse().unwrap_or(8080);
 
     // Connect to server
-    let mut stream = TcpStream::connect(format!("127.0.0.1:{}", port)).await?;
+    let mut client = RadarClient::connect("127.0.0.1", port).await?;
     println!("Connected to server on port {}", port);
 
     // Wait for user input to send SEND_DATA command
@@ -38,25 +24,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
     io::stdin().read_line(&mut input).unwrap();
 
     // Send SEND_DATA command
-    stream.write_all(b"SEND_DATA").await?;
-    stream.flush().await?;
+    client.start_streaming().await?;
     println!("Sent 'SEND_DATA' command to server");
 
     // Now start receiving radar data
     println!("Starting to receive radar data...");
     let mut sweep_count = 0;
 
-    loop {
-        // Read data size
-        let data_size = stream.read_u64().await?;
-
-        // Read the serialized data
-        let mut buffer = vec![0u8; data_size as usize];
-        stream.read_exact(&mut buffer).await?;
-
-        // Deserialize the radar sweep
-        let sweep: RadarSweep = bincode::deserialize(&buffer)?;
-
+    while let Some(sweep) = client.next_sweep().await? {
         sweep_count += 1;
         println!(
             "[{}] Received sweep {} (Az: {:.1}°-{:.1}°, {} range bins, {} data points)",
@@ -65,7 +40,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             sweep.azimuth_start,
             sweep.azimuth_end,
             sweep.range_bins.len(),
-            sweep.data.len()
+            sweep.azimuth_count()
         );
 
         // Stop after receiving 10 sweeps for demo
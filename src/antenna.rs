@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+pub(crate) const SPEED_OF_LIGHT_MS: f32 = 3.0e8;
+
+// Farther sidelobes are lost in the noise
+const SIDELOBE_EXTENT_DEG: f32 = 30.0;
+
+// How the radar sees a point target: the antenna pattern across azimuth and
+// the matched-filter pulse response along range. A wider beam or a longer
+// pulse makes every echo look bigger; strong targets also leak into the
+// sidelobes and show up as faint ghosts either side of the real echo.
+//
+//   antenna = { beamwidth_deg = 1.0, sidelobe_level_db = -25.0, pulse_width_us = 1.0 }
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BeamPattern {
+    pub beamwidth_deg: f32,     // One-way half-power beamwidth
+    pub sidelobe_level_db: f32, // First sidelobe relative to boresight, one way
+    pub pulse_width_us: f32,
+}
+
+impl Default for BeamPattern {
+    fn default() -> Self {
+        Self {
+            beamwidth_deg: 1.0,
+            sidelobe_level_db: -25.0,
+            pulse_width_us: 1.0,
+        }
+    }
+}
+
+impl BeamPattern {
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if !self.beamwidth_deg.is_finite() || self.beamwidth_deg <= 0.0 || self.beamwidth_deg > 90.0
+        {
+            return Err(format!("Invalid beamwidth {}°", self.beamwidth_deg).into());
+        }
+        if !self.sidelobe_level_db.is_finite() || self.sidelobe_level_db >= 0.0 {
+            return Err(format!(
+                "Sidelobe level {} dB must be below the main lobe",
+                self.sidelobe_level_db
+            )
+            .into());
+        }
+        if !self.pulse_width_us.is_finite() || self.pulse_width_us <= 0.0 {
+            return Err(format!("Invalid pulse width {} µs", self.pulse_width_us).into());
+        }
+        Ok(())
+    }
+
+    // One-way power gain `offset_deg` off boresight, 1 on the axis. A
+    // Gaussian main lobe, then sidelobes one beamwidth apart falling off with
+    // the square of the angle.
+    pub fn one_way_gain(&self, offset_deg: f32) -> f32 {
+        let offset = offset_deg.abs();
+        if offset > SIDELOBE_EXTENT_DEG {
+            return 0.0;
+        }
+        let main_lobe =
+            (-4.0 * std::f32::consts::LN_2 * (offset / self.beamwidth_deg).powi(2)).exp();
+
+        let first_sidelobe = 1.5 * self.beamwidth_deg;
+        if offset < first_sidelobe {
+            return main_lobe;
+        }
+        let envelope =
+            10f32.powf(self.sidelobe_level_db / 10.0) * (first_sidelobe / offset).powi(2);
+        let ripple = (std::f32::consts::PI * (offset - first_sidelobe) / self.beamwidth_deg).cos();
+        main_lobe.max(envelope * ripple * ripple)
+    }
+
+    // Transmit and receive through the same antenna
+    pub fn two_way_gain(&self, offset_deg: f32) -> f32 {
+        self.one_way_gain(offset_deg).powi(2)
+    }
+
+    // Range resolution c·τ/2, the half-width of the range response
+    pub fn range_resolution_m(&self) -> f32 {
+        SPEED_OF_LIGHT_MS * self.pulse_width_us * 1.0e-6 / 2.0
+    }
+
+    // Matched-filter response to a point target `offset_m` away along range:
+    // a triangle, 1 on the target and 0 one range resolution away
+    pub fn range_weight(&self, offset_m: f32) -> f32 {
+        (1.0 - offset_m.abs() / self.range_resolution_m()).max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db(ratio: f32) -> f32 {
+        10.0 * ratio.log10()
+    }
+
+    #[test]
+    fn main_lobe_is_3_db_down_at_half_the_beamwidth() {
+        for beamwidth_deg in [0.5, 1.0, 3.0] {
+            let beam = BeamPattern {
+                beamwidth_deg,
+                ..BeamPattern::default()
+            };
+            assert_eq!(beam.one_way_gain(0.0), 1.0);
+            let edge = beamwidth_deg / 2.0;
+            assert!((db(beam.one_way_gain(edge)) + 3.01).abs() < 0.01);
+            assert!((db(beam.two_way_gain(-edge)) + 6.02).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn sidelobes_peak_at_their_level_with_nulls_between() {
+        let beam = BeamPattern::default();
+        // First sidelobe 1.5 beamwidths out
+        assert!((db(beam.one_way_gain(1.5)) - beam.sidelobe_level_db).abs() < 0.01);
+        assert!((db(beam.one_way_gain(-1.5)) - beam.sidelobe_level_db).abs() < 0.01);
+        // Null half a beamwidth later, down to what is left of the main lobe
+        assert!(db(beam.one_way_gain(2.0)) < -45.0);
+        // Next sidelobe lower again
+        assert!(beam.one_way_gain(2.5) < beam.one_way_gain(1.5));
+        assert_eq!(beam.one_way_gain(SIDELOBE_EXTENT_DEG + 0.1), 0.0);
+    }
+
+    #[test]
+    fn range_response_is_a_triangle() {
+        let beam = BeamPattern::default();
+        // 1 µs pulse
+        assert!((beam.range_resolution_m() - 150.0).abs() < 1e-3);
+        assert_eq!(beam.range_weight(0.0), 1.0);
+        assert!((beam.range_weight(75.0) - 0.5).abs() < 1e-6);
+        assert!((beam.range_weight(-75.0) - 0.5).abs() < 1e-6);
+        assert_eq!(beam.range_weight(150.0), 0.0);
+        assert_eq!(beam.range_weight(-400.0), 0.0);
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        assert!(BeamPattern::default().validate().is_ok());
+
+        let invalid = [
+            BeamPattern {
+                beamwidth_deg: 0.0,
+                ..BeamPattern::default()
+            },
+            BeamPattern {
+                beamwidth_deg: 120.0,
+                ..BeamPattern::default()
+            },
+            BeamPattern {
+                sidelobe_level_db: 3.0,
+                ..BeamPattern::default()
+            },
+            BeamPattern {
+                pulse_width_us: f32::NAN,
+                ..BeamPattern::default()
+            },
+        ];
+        for beam in invalid {
+            assert!(beam.validate().is_err(), "{:?} accepted", beam);
+        }
+    }
+}
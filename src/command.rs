@@ -0,0 +1,533 @@
+use crate::compression::Compression;
+use crate::subscription::{AzimuthWindow, Subscription};
+use bytes::BytesMut;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use tokio_util::codec::{Decoder, LinesCodec, LinesCodecError};
+
+// Client -> server commands, one per newline-terminated line:
+//
+//   SUBSCRIBE [az=<start>:<end>] [range=<km>] [decimate=<n>] [rate=<hz>] [format=<f32|u8|u16|dbz>]
+//             [delta=<keyframe interval, 0 = off>] [mode=<sweep|radial>]
+//             [tilts=<i,j,...|all>]    (alias: SEND_DATA)
+//   UNSUBSCRIBE              (alias: STOP)
+//   SET_RATE <hz>
+//   SELECT_SECTOR <index>
+//   COMPRESS <none|rle|lz4>
+//   RESYNC                   (next sweep is a keyframe)
+//   PING [token]
+//   GET_STATUS
+pub const MAX_COMMAND_LINE_BYTES: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Subscribe(Subscription),
+    Unsubscribe,
+    SetRate(f64),
+    SelectSector(usize),
+    Compress(Compression),
+    Resync,
+    Ping(u64),
+    GetStatus,
+}
+
+// Successful command outcome, sent back as `Message::Ack`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Ack {
+    Subscribed(Subscription), // Effective subscription after the update
+    Unsubscribed,
+    RateSet(f64),
+    SectorSelected(usize),
+    CompressionSet(Compression), // Applies to sweeps sent after this ack
+    ResyncScheduled,
+    Pong(u64),
+}
+
+// Failed command outcome, sent back as `Message::Error`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum CommandError {
+    Empty,
+    LineTooLong, // Longer than `MAX_COMMAND_LINE_BYTES`, the rest of the line is skipped
+    Unknown(String),
+    MissingArgument(String),
+    InvalidArgument { command: String, argument: String },
+    Rejected(String),
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Subscribe(subscription) => {
+                write!(f, "SUBSCRIBE")?;
+                if let Some(window) = subscription.azimuth {
+                    write!(f, " az={}:{}", window.azimuth_start, window.azimuth_end)?;
+                }
+                if let Some(max_range_km) = subscription.max_range_km {
+                    write!(f, " range={}", max_range_km)?;
+                }
+                if let Some(factor) = subscription.range_decimation {
+                    write!(f, " decimate={}", factor)?;
+                }
+                if let Some(rate_hz) = subscription.rate_hz {
+                    write!(f, " rate={}", rate_hz)?;
+                }
+                if let Some(format) = subscription.sample_format {
+                    write!(f, " format={}", format)?;
+                }
+                if let Some(interval) = subscription.delta_keyframe_interval {
+                    write!(f, " delta={}", interval)?;
+                }
+                if let Some(mode) = subscription.stream_mode {
+                    write!(f, " mode={}", mode)?;
+                }
+                if let Some(tilts) = &subscription.tilts {
+                    if tilts.is_empty() {
+                        write!(f, " tilts=all")?;
+                    } else {
+                        let tilts: Vec<String> =
+                            tilts.iter().map(|tilt| tilt.to_string()).collect();
+                        write!(f, " tilts={}", tilts.join(","))?;
+                    }
+                }
+                Ok(())
+            }
+            Command::Unsubscribe => write!(f, "UNSUBSCRIBE"),
+            Command::SetRate(hz) => write!(f, "SET_RATE {}", hz),
+            Command::SelectSector(index) => write!(f, "SELECT_SECTOR {}", index),
+            Command::Compress(compression) => write!(f, "COMPRESS {}", compression),
+            Command::Resync => write!(f, "RESYNC"),
+            Command::Ping(token) => write!(f, "PING {}", token),
+            Command::GetStatus => write!(f, "GET_STATUS"),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = CommandError;
+
+    fn from_str(line: &str) -> Result<Self, CommandError> {
+        let mut words = line.split_whitespace();
+        let keyword = words
+            .next()
+            .ok_or(CommandError::Empty)?
+            .to_ascii_uppercase();
+        let arguments: Vec<&str> = words.collect();
+
+        let command = match keyword.as_str() {
+            "SUBSCRIBE" | "SEND_DATA" => {
+                Command::Subscribe(parse_subscription(&keyword, &arguments)?)
+            }
+            "UNSUBSCRIBE" | "STOP" => {
+                no_argument(&keyword, &arguments)?;
+                Command::Unsubscribe
+            }
+            "SET_RATE" => {
+                let argument = single_argument(&keyword, &arguments)?;
+                Command::SetRate(parse_positive(&keyword, argument)?)
+            }
+            "SELECT_SECTOR" => {
+                let argument = single_argument(&keyword, &arguments)?;
+                Command::SelectSector(parse_argument(&keyword, argument)?)
+            }
+            "RESYNC" => {
+                no_argument(&keyword, &arguments)?;
+                Command::Resync
+            }
+            "COMPRESS" => {
+                let argument = single_argument(&keyword, &arguments)?;
+                Command::Compress(parse_argument(&keyword, argument)?)
+            }
+            "PING" => match arguments.as_slice() {
+                [] => Command::Ping(0),
+                [token] => Command::Ping(parse_argument(&keyword, token)?),
+                [_, extra, ..] => return Err(invalid_argument(&keyword, extra)),
+            },
+            "GET_STATUS" => {
+                no_argument(&keyword, &arguments)?;
+                Command::GetStatus
+            }
+            _ => return Err(CommandError::Unknown(line.trim().to_string())),
+        };
+
+        Ok(command)
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Empty => write!(f, "Empty command"),
+            CommandError::LineTooLong => {
+                write!(f, "Command longer than {} bytes", MAX_COMMAND_LINE_BYTES)
+            }
+            CommandError::Unknown(command) => write!(f, "Unknown command: '{}'", command),
+            CommandError::MissingArgument(command) => {
+                write!(f, "Missing argument for {}", command)
+            }
+            CommandError::InvalidArgument { command, argument } => {
+                write!(f, "Invalid argument '{}' for {}", argument, command)
+            }
+            CommandError::Rejected(reason) => write!(f, "Command rejected: {}", reason),
+        }
+    }
+}
+
+impl Error for CommandError {}
+
+fn parse_subscription(keyword: &str, arguments: &[&str]) -> Result<Subscription, CommandError> {
+    let mut subscription = Subscription::default();
+
+    for &argument in arguments {
+        // Errors always name the whole `key=value`, not just the part that failed
+        let (key, value) = argument
+            .split_once('=')
+            .ok_or_else(|| invalid_argument(keyword, argument))?;
+        set_subscription_key(&mut subscription, keyword, key, value)
+            .map_err(|_| invalid_argument(keyword, argument))?;
+    }
+
+    Ok(subscription)
+}
+
+fn set_subscription_key(
+    subscription: &mut Subscription,
+    keyword: &str,
+    key: &str,
+    value: &str,
+) -> Result<(), CommandError> {
+    match key.to_ascii_lowercase().as_str() {
+        "az" => {
+            let (start, end) = value
+                .split_once(':')
+                .ok_or_else(|| invalid_argument(keyword, value))?;
+            let azimuth_start: f32 = parse_argument(keyword, start)?;
+            let azimuth_end: f32 = parse_argument(keyword, end)?;
+            if !azimuth_start.is_finite() || !azimuth_end.is_finite() {
+                return Err(invalid_argument(keyword, value));
+            }
+            subscription.azimuth = Some(AzimuthWindow {
+                azimuth_start: azimuth_start.rem_euclid(360.0),
+                azimuth_end: if azimuth_end == 360.0 {
+                    360.0
+                } else {
+                    azimuth_end.rem_euclid(360.0)
+                },
+            });
+        }
+        "range" => subscription.max_range_km = Some(parse_positive(keyword, value)? as f32),
+        "decimate" => {
+            let factor: usize = parse_argument(keyword, value)?;
+            if factor == 0 {
+                return Err(invalid_argument(keyword, value));
+            }
+            subscription.range_decimation = Some(factor);
+        }
+        "rate" => subscription.rate_hz = Some(parse_positive(keyword, value)?),
+        "format" => subscription.sample_format = Some(parse_argument(keyword, value)?),
+        "delta" => subscription.delta_keyframe_interval = Some(parse_argument(keyword, value)?),
+        "mode" => subscription.stream_mode = Some(parse_argument(keyword, value)?),
+        "tilts" => {
+            subscription.tilts = Some(if value.eq_ignore_ascii_case("all") {
+                Vec::new()
+            } else {
+                value
+                    .split(',')
+                    .map(|tilt| parse_argument(keyword, tilt))
+                    .collect::<Result<_, _>>()?
+            });
+        }
+        _ => return Err(invalid_argument(keyword, key)),
+    }
+
+    Ok(())
+}
+
+fn no_argument(keyword: &str, arguments: &[&str]) -> Result<(), CommandError> {
+    match arguments.first() {
+        Some(extra) => Err(invalid_argument(keyword, extra)),
+        None => Ok(()),
+    }
+}
+
+fn single_argument<'a>(keyword: &str, arguments: &[&'a str]) -> Result<&'a str, CommandError> {
+    match arguments {
+        [] => Err(CommandError::MissingArgument(keyword.to_string())),
+        [argument] => Ok(argument),
+        [_, extra, ..] => Err(invalid_argument(keyword, extra)),
+    }
+}
+
+fn parse_positive(keyword: &str, argument: &str) -> Result<f64, CommandError> {
+    let value: f64 = parse_argument(keyword, argument)?;
+    if !value.is_finite() || value <= 0.0 {
+        return Err(invalid_argument(keyword, argument));
+    }
+    Ok(value)
+}
+
+fn parse_argument<T: FromStr>(keyword: &str, argument: &str) -> Result<T, CommandError> {
+    argument
+        .parse()
+        .map_err(|_| invalid_argument(keyword, argument))
+}
+
+fn invalid_argument(keyword: &str, argument: &str) -> CommandError {
+    CommandError::InvalidArgument {
+        command: keyword.to_string(),
+        argument: argument.to_string(),
+    }
+}
+
+// Splits the incoming byte stream into command lines. A line over
+// `MAX_COMMAND_LINE_BYTES` comes out as `CommandError::LineTooLong` rather
+// than a stream error; the rest of it is skipped and reading carries on.
+#[derive(Debug, Clone)]
+pub struct CommandLineCodec {
+    lines: LinesCodec,
+}
+
+impl CommandLineCodec {
+    pub fn new() -> Self {
+        Self {
+            lines: LinesCodec::new_with_max_length(MAX_COMMAND_LINE_BYTES),
+        }
+    }
+}
+
+impl Default for CommandLineCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for CommandLineCodec {
+    type Item = Result<String, CommandError>;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        command_line(self.lines.decode(src))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        command_line(self.lines.decode_eof(src))
+    }
+}
+
+fn command_line(
+    line: Result<Option<String>, LinesCodecError>,
+) -> io::Result<Option<Result<String, CommandError>>> {
+    match line {
+        Ok(line) => Ok(line.map(Ok)),
+        Err(LinesCodecError::MaxLineLengthExceeded) => Ok(Some(Err(CommandError::LineTooLong))),
+        Err(LinesCodecError::Io(e)) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantize::SampleFormat;
+    use crate::subscription::StreamMode;
+
+    fn parse(line: &str) -> Result<Command, CommandError> {
+        line.parse()
+    }
+
+    fn subscription(line: &str) -> Subscription {
+        match parse(line) {
+            Ok(Command::Subscribe(subscription)) => subscription,
+            other => panic!("'{}' parsed as {:?}", line, other),
+        }
+    }
+
+    fn is_invalid(result: Result<Command, CommandError>, bad_argument: &str) -> bool {
+        matches!(result, Err(CommandError::InvalidArgument { argument, .. }) if argument == bad_argument)
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let commands = [
+            Command::Subscribe(Subscription::default()),
+            Command::Subscribe(Subscription {
+                azimuth: Some(AzimuthWindow {
+                    azimuth_start: 350.0,
+                    azimuth_end: 10.5,
+                }),
+                max_range_km: Some(75.5),
+                range_decimation: Some(4),
+                rate_hz: Some(0.5),
+                sample_format: Some(SampleFormat::Dbz8),
+                delta_keyframe_interval: Some(10),
+                stream_mode: Some(StreamMode::Radial),
+                tilts: Some(vec![0, 2]),
+            }),
+            Command::Subscribe(Subscription {
+                tilts: Some(Vec::new()),
+                ..Subscription::default()
+            }),
+            Command::Unsubscribe,
+            Command::SetRate(2.5),
+            Command::SelectSector(1),
+            Command::Compress(Compression::Lz4),
+            Command::Resync,
+            Command::Ping(12345),
+            Command::GetStatus,
+        ];
+
+        for command in commands {
+            assert_eq!(parse(&command.to_string()), Ok(command));
+        }
+    }
+
+    #[test]
+    fn keywords_are_case_insensitive_and_have_aliases() {
+        assert_eq!(
+            parse("send_data"),
+            Ok(Command::Subscribe(Subscription::default()))
+        );
+        assert_eq!(parse("SEND_DATA rate=2"), parse("subscribe RATE=2"));
+        assert_eq!(parse("STOP"), Ok(Command::Unsubscribe));
+        assert_eq!(parse("stop"), Ok(Command::Unsubscribe));
+        assert_eq!(parse("  PING  "), Ok(Command::Ping(0)));
+    }
+
+    #[test]
+    fn empty_lines_are_rejected() {
+        assert_eq!(parse(""), Err(CommandError::Empty));
+        assert_eq!(parse("   \t "), Err(CommandError::Empty));
+    }
+
+    #[test]
+    fn unknown_commands_are_rejected() {
+        assert_eq!(
+            parse(" JUMP high "),
+            Err(CommandError::Unknown("JUMP high".to_string()))
+        );
+    }
+
+    #[test]
+    fn extra_arguments_are_rejected() {
+        assert!(is_invalid(parse("STOP now"), "now"));
+        assert!(is_invalid(parse("UNSUBSCRIBE 1"), "1"));
+        assert!(is_invalid(parse("RESYNC all"), "all"));
+        assert!(is_invalid(parse("GET_STATUS x"), "x"));
+        assert!(is_invalid(parse("SET_RATE 1 2"), "2"));
+        assert!(is_invalid(parse("SELECT_SECTOR 0 1"), "1"));
+        assert!(is_invalid(parse("PING 1 2"), "2"));
+        assert!(is_invalid(parse("COMPRESS lz4 rle"), "rle"));
+    }
+
+    #[test]
+    fn missing_arguments_are_rejected() {
+        assert_eq!(
+            parse("SET_RATE"),
+            Err(CommandError::MissingArgument("SET_RATE".to_string()))
+        );
+        assert_eq!(
+            parse("select_sector"),
+            Err(CommandError::MissingArgument("SELECT_SECTOR".to_string()))
+        );
+        assert_eq!(
+            parse("COMPRESS"),
+            Err(CommandError::MissingArgument("COMPRESS".to_string()))
+        );
+    }
+
+    #[test]
+    fn rates_must_be_positive_and_finite() {
+        assert_eq!(parse("SET_RATE 0.5"), Ok(Command::SetRate(0.5)));
+        for rate in ["0", "-1", "inf", "NaN", "fast"] {
+            assert!(is_invalid(parse(&format!("SET_RATE {}", rate)), rate));
+        }
+        assert!(is_invalid(parse("SUBSCRIBE rate=0"), "rate=0"));
+        assert!(is_invalid(parse("SUBSCRIBE range=-5"), "range=-5"));
+    }
+
+    #[test]
+    fn sector_and_ping_arguments_are_unsigned() {
+        assert!(is_invalid(parse("SELECT_SECTOR -1"), "-1"));
+        assert!(is_invalid(parse("PING abc"), "abc"));
+        assert_eq!(parse("PING 7"), Ok(Command::Ping(7)));
+    }
+
+    #[test]
+    fn azimuth_windows_wrap_into_one_turn() {
+        let window = subscription("SUBSCRIBE az=-10:370").azimuth.unwrap();
+        assert_eq!(window.azimuth_start, 350.0);
+        assert_eq!(window.azimuth_end, 10.0);
+        assert_eq!(window.width(), 20.0);
+
+        let window = subscription("SUBSCRIBE az=720:90").azimuth.unwrap();
+        assert_eq!((window.azimuth_start, window.azimuth_end), (0.0, 90.0));
+    }
+
+    #[test]
+    fn azimuth_window_may_end_at_360() {
+        let window = subscription("SUBSCRIBE az=270:360").azimuth.unwrap();
+        assert_eq!((window.azimuth_start, window.azimuth_end), (270.0, 360.0));
+        assert_eq!(window.width(), 90.0);
+
+        let window = subscription("SUBSCRIBE az=0:360").azimuth.unwrap();
+        assert_eq!(window.width(), 360.0);
+    }
+
+    #[test]
+    fn malformed_azimuth_windows_are_rejected() {
+        assert!(is_invalid(parse("SUBSCRIBE az=10"), "az=10"));
+        assert!(is_invalid(parse("SUBSCRIBE az=a:10"), "az=a:10"));
+        assert!(is_invalid(parse("SUBSCRIBE az=inf:10"), "az=inf:10"));
+    }
+
+    #[test]
+    fn decimation_must_be_at_least_one() {
+        assert_eq!(
+            subscription("SUBSCRIBE decimate=1").range_decimation,
+            Some(1)
+        );
+        assert!(is_invalid(parse("SUBSCRIBE decimate=0"), "decimate=0"));
+        assert!(is_invalid(parse("SUBSCRIBE decimate=-2"), "decimate=-2"));
+    }
+
+    #[test]
+    fn tilts_are_a_list_or_all() {
+        assert_eq!(subscription("SUBSCRIBE tilts=1,3").tilts, Some(vec![1, 3]));
+        assert_eq!(subscription("SUBSCRIBE tilts=2").tilts, Some(vec![2]));
+        assert_eq!(subscription("SUBSCRIBE tilts=ALL").tilts, Some(Vec::new()));
+        assert_eq!(subscription("SUBSCRIBE").tilts, None);
+        assert!(is_invalid(parse("SUBSCRIBE tilts=1,,2"), "tilts=1,,2"));
+        assert!(is_invalid(parse("SUBSCRIBE tilts=-1"), "tilts=-1"));
+        assert!(is_invalid(parse("SUBSCRIBE tilts="), "tilts="));
+    }
+
+    #[test]
+    fn subscribe_arguments_need_known_keys() {
+        assert!(is_invalid(parse("SUBSCRIBE fast"), "fast"));
+        assert!(is_invalid(parse("SUBSCRIBE speed=2"), "speed=2"));
+        assert!(is_invalid(parse("SUBSCRIBE format=u32"), "format=u32"));
+        assert!(is_invalid(parse("SUBSCRIBE mode=burst"), "mode=burst"));
+    }
+
+    #[test]
+    fn over_long_lines_are_skipped_not_fatal() {
+        let mut codec = CommandLineCodec::new();
+        let mut src = BytesMut::new();
+        src.extend_from_slice(&[b'X'; MAX_COMMAND_LINE_BYTES + 10]);
+        src.extend_from_slice(b"X\nPING 5\n");
+
+        assert_eq!(
+            codec.decode(&mut src).unwrap(),
+            Some(Err(CommandError::LineTooLong))
+        );
+        assert_eq!(
+            codec.decode(&mut src).unwrap(),
+            Some(Ok("PING 5".to_string()))
+        );
+        assert_eq!(codec.decode(&mut src).unwrap(), None);
+        assert!(CommandError::LineTooLong
+            .to_string()
+            .contains(&MAX_COMMAND_LINE_BYTES.to_string()));
+    }
+}
@@ -0,0 +1,302 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+// Payload compression, carried in the low bits of the frame header flags.
+// Chosen per connection with `COMPRESS <none|rle|lz4>`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Compression {
+    #[default]
+    None = 0,
+    Rle = 1, // Zero-run encoding, cheap and good for sparse/quantized data
+    Lz4 = 2, // General-purpose, size-prefixed LZ4 block
+}
+
+pub const COMPRESSION_FLAG_MASK: u8 = 0b0000_0011;
+
+impl Compression {
+    pub const ALL: [Compression; 3] = [Compression::None, Compression::Rle, Compression::Lz4];
+
+    pub fn from_flags(flags: u8) -> Option<Self> {
+        match flags & COMPRESSION_FLAG_MASK {
+            0 => Some(Compression::None),
+            1 => Some(Compression::Rle),
+            2 => Some(Compression::Lz4),
+            _ => None,
+        }
+    }
+
+    pub fn compress(&self, raw: &[u8]) -> Vec<u8> {
+        match self {
+            Compression::None => raw.to_vec(),
+            Compression::Rle => rle_compress(raw),
+            Compression::Lz4 => lz4_flex::block::compress_prepend_size(raw),
+        }
+    }
+
+    // Refuses to produce more than `max_len` bytes, so a corrupt or hostile
+    // frame cannot make the reader allocate without bound
+    pub fn decompress(&self, data: &[u8], max_len: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Rle => rle_decompress(data, max_len),
+            Compression::Lz4 => {
+                if data.len() < 4 {
+                    return Err("LZ4 payload is missing its size prefix".into());
+                }
+                let size = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+                if size > max_len {
+                    return Err(format!("LZ4 payload expands to {} bytes", size).into());
+                }
+                let raw = lz4_flex::block::decompress(&data[4..], size)?;
+                if raw.len() != size {
+                    return Err("LZ4 payload size does not match its prefix".into());
+                }
+                Ok(raw)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::None => write!(f, "none"),
+            Compression::Rle => write!(f, "rle"),
+            Compression::Lz4 => write!(f, "lz4"),
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "none" | "off" => Ok(Compression::None),
+            "rle" => Ok(Compression::Rle),
+            "lz4" => Ok(Compression::Lz4),
+            _ => Err(format!("Unknown compression '{}'", value)),
+        }
+    }
+}
+
+// Every zero byte is followed by the length of its run (1-255);
+// all other bytes are copied as they are
+fn rle_compress(raw: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(raw.len() / 2);
+    let mut i = 0;
+
+    while i < raw.len() {
+        if raw[i] != 0 {
+            out.push(raw[i]);
+            i += 1;
+            continue;
+        }
+        let run = raw[i..]
+            .iter()
+            .take(u8::MAX as usize)
+            .take_while(|&&byte| byte == 0)
+            .count();
+        out.push(0);
+        out.push(run as u8);
+        i += run;
+    }
+
+    out
+}
+
+fn rle_decompress(data: &[u8], max_len: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut out = Vec::with_capacity(data.len().saturating_mul(2).min(max_len));
+    let mut bytes = data.iter();
+
+    while let Some(&byte) = bytes.next() {
+        if byte != 0 {
+            out.push(byte);
+        } else {
+            match bytes.next() {
+                Some(&run) if run > 0 => out.resize(out.len() + run as usize, 0),
+                _ => return Err("RLE zero run is missing its length".into()),
+            }
+        }
+        if out.len() > max_len {
+            return Err(format!("RLE payload expands past {} bytes", max_len).into());
+        }
+    }
+
+    Ok(out)
+}
+
+// Server-wide compression counters for one codec
+#[derive(Debug, Default)]
+pub struct CompressionCounters {
+    pub frames: AtomicU64,
+    pub raw_bytes: AtomicU64,
+    pub compressed_bytes: AtomicU64,
+    pub encode_micros: AtomicU64,
+}
+
+impl CompressionCounters {
+    pub fn record(&self, raw_bytes: usize, compressed_bytes: usize, elapsed: Duration) {
+        self.frames.fetch_add(1, Ordering::Relaxed);
+        self.raw_bytes
+            .fetch_add(raw_bytes as u64, Ordering::Relaxed);
+        self.compressed_bytes
+            .fetch_add(compressed_bytes as u64, Ordering::Relaxed);
+        self.encode_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn report(&self, compression: Compression) -> CompressionReport {
+        CompressionReport {
+            compression,
+            frames: self.frames.load(Ordering::Relaxed),
+            raw_bytes: self.raw_bytes.load(Ordering::Relaxed),
+            compressed_bytes: self.compressed_bytes.load(Ordering::Relaxed),
+            encode_micros: self.encode_micros.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// Snapshot of one codec's counters, sent in `ServerStatus`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CompressionReport {
+    pub compression: Compression,
+    pub frames: u64,
+    pub raw_bytes: u64,        // Serialized payload size before compression
+    pub compressed_bytes: u64, // Payload size on the wire
+    pub encode_micros: u64,    // Time spent serializing and compressing
+}
+
+impl CompressionReport {
+    // Raw size over wire size, above 1.0 means the codec pays off
+    pub fn ratio(&self) -> f64 {
+        if self.compressed_bytes == 0 {
+            return 1.0;
+        }
+        self.raw_bytes as f64 / self.compressed_bytes as f64
+    }
+
+    pub fn average_encode_micros(&self) -> f64 {
+        if self.frames == 0 {
+            return 0.0;
+        }
+        self.encode_micros as f64 / self.frames as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const MAX_LEN: usize = 1 << 20;
+
+    fn round_trip(compression: Compression, raw: &[u8]) -> Vec<u8> {
+        let compressed = compression.compress(raw);
+        compression.decompress(&compressed, MAX_LEN).unwrap()
+    }
+
+    fn random_bytes(len: usize, seed: u64) -> Vec<u8> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..len).map(|_| rng.gen()).collect()
+    }
+
+    // Mostly zeros with short bursts of data, like quantized sparse echoes
+    fn sparse_bytes(len: usize, seed: u64) -> Vec<u8> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..len)
+            .map(|_| if rng.gen_bool(0.1) { rng.gen() } else { 0 })
+            .collect()
+    }
+
+    #[test]
+    fn empty_input_round_trips() {
+        for compression in Compression::ALL {
+            assert!(round_trip(compression, &[]).is_empty());
+        }
+        assert!(Compression::Rle.compress(&[]).is_empty());
+    }
+
+    #[test]
+    fn long_runs_round_trip() {
+        let mut raw = vec![0u8; 100_000];
+        raw.extend(vec![7u8; 50_000]);
+        raw.push(0);
+        for run in [1, 254, 255, 256, 510, 511] {
+            raw.extend(vec![0u8; run]);
+            raw.push(1);
+        }
+
+        for compression in Compression::ALL {
+            assert_eq!(round_trip(compression, &raw), raw);
+        }
+    }
+
+    #[test]
+    fn rle_splits_runs_longer_than_a_length_byte() {
+        assert_eq!(Compression::Rle.compress(&[0; 255]), vec![0, 255]);
+        assert_eq!(Compression::Rle.compress(&[0; 256]), vec![0, 255, 0, 1]);
+        assert_eq!(Compression::Rle.compress(&[3, 0, 0, 4]), vec![3, 0, 2, 4]);
+    }
+
+    #[test]
+    fn random_input_round_trips() {
+        for seed in 0..8 {
+            let raw = random_bytes(10_000 + seed as usize * 37, seed);
+            let sparse = sparse_bytes(10_000, seed);
+            for compression in Compression::ALL {
+                assert_eq!(round_trip(compression, &raw), raw);
+                assert_eq!(round_trip(compression, &sparse), sparse);
+            }
+        }
+    }
+
+    #[test]
+    fn sparse_data_compresses() {
+        let sparse = sparse_bytes(100_000, 1);
+        assert!(Compression::Rle.compress(&sparse).len() < sparse.len() / 2);
+        assert!(Compression::Lz4.compress(&sparse).len() < sparse.len() / 2);
+    }
+
+    #[test]
+    fn truncated_rle_run_is_rejected() {
+        assert!(Compression::Rle.decompress(&[5, 0], MAX_LEN).is_err());
+        assert!(Compression::Rle.decompress(&[0, 0], MAX_LEN).is_err());
+    }
+
+    #[test]
+    fn decompression_respects_the_size_limit() {
+        let raw = vec![0u8; 4096];
+        for compression in [Compression::Rle, Compression::Lz4] {
+            let compressed = compression.compress(&raw);
+            assert!(compression.decompress(&compressed, 4095).is_err());
+            assert_eq!(compression.decompress(&compressed, 4096).unwrap(), raw);
+        }
+    }
+
+    #[test]
+    fn corrupt_lz4_is_rejected() {
+        assert!(Compression::Lz4.decompress(&[1, 0], MAX_LEN).is_err());
+
+        let mut compressed = Compression::Lz4.compress(&random_bytes(1000, 9));
+        compressed[0] = compressed[0].wrapping_add(1); // Size prefix no longer matches
+        assert!(Compression::Lz4.decompress(&compressed, MAX_LEN).is_err());
+    }
+
+    #[test]
+    fn flags_select_the_codec() {
+        for compression in Compression::ALL {
+            assert_eq!(
+                Compression::from_flags(compression as u8),
+                Some(compression)
+            );
+            assert_eq!(compression.to_string().parse(), Ok(compression));
+        }
+        assert_eq!(Compression::from_flags(0b11), None);
+    }
+}
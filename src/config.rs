@@ -0,0 +1,221 @@
+use crate::radar_simulator::{OVERLAP_DEGREES, RANGE_BINS, RANGE_RESOLUTION_M};
+use crate::sector::SectorPlan;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+// Radar geometry read by the simulator, the sector splitter and clients.
+// The server sends it to every client in the stream header.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RadarConfig {
+    pub azimuth_resolution_deg: f32, // Width of one radial, must divide 360°
+    pub range_gate_count: usize,     // Gates per radial
+    pub gate_spacing_m: f32,         // Distance between neighbouring gates
+    pub first_gate_m: f32,           // Range of the first gate
+    pub overlap_degrees: f32,        // Overlap shared by neighbouring sectors
+}
+
+impl Default for RadarConfig {
+    fn default() -> Self {
+        Self {
+            azimuth_resolution_deg: 1.0,
+            range_gate_count: RANGE_BINS,
+            gate_spacing_m: RANGE_RESOLUTION_M,
+            first_gate_m: 0.0,
+            overlap_degrees: OVERLAP_DEGREES,
+        }
+    }
+}
+
+impl RadarConfig {
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let resolution = self.azimuth_resolution_deg;
+        if !resolution.is_finite() || resolution <= 0.0 || resolution > 360.0 {
+            return Err(format!("Invalid azimuth resolution {}°", resolution).into());
+        }
+        let radials = 360.0 / resolution;
+        if (radials - radials.round()).abs() > 1e-3 {
+            return Err(format!("Azimuth resolution {}° does not divide 360°", resolution).into());
+        }
+        if self.range_gate_count == 0 {
+            return Err("Radar needs at least one range gate".into());
+        }
+        if !self.gate_spacing_m.is_finite() || self.gate_spacing_m <= 0.0 {
+            return Err(format!("Invalid gate spacing {} m", self.gate_spacing_m).into());
+        }
+        if !self.first_gate_m.is_finite() || self.first_gate_m < 0.0 {
+            return Err(format!("Invalid first gate range {} m", self.first_gate_m).into());
+        }
+        if !self.overlap_degrees.is_finite()
+            || self.overlap_degrees < 0.0
+            || self.overlap_degrees >= 180.0
+        {
+            return Err(format!("Invalid sector overlap {}°", self.overlap_degrees).into());
+        }
+        Ok(())
+    }
+
+    // Radials in one rotation
+    pub fn azimuth_count(&self) -> usize {
+        (360.0 / self.azimuth_resolution_deg).round() as usize
+    }
+
+    pub fn gate_spacing_km(&self) -> f32 {
+        self.gate_spacing_m / 1000.0
+    }
+
+    pub fn first_gate_km(&self) -> f32 {
+        self.first_gate_m / 1000.0
+    }
+
+    // Far edge of the last gate
+    pub fn max_range_km(&self) -> f32 {
+        self.first_gate_km() + self.range_gate_count as f32 * self.gate_spacing_km()
+    }
+
+    // Range of every gate (km)
+    pub fn range_bins(&self) -> Vec<f32> {
+        (0..self.range_gate_count)
+            .map(|gate| self.first_gate_km() + gate as f32 * self.gate_spacing_km())
+            .collect()
+    }
+
+    // Gate covering `range_km`, `None` before the first or past the last gate
+    pub fn gate_index(&self, range_km: f32) -> Option<usize> {
+        let gate = ((range_km - self.first_gate_km()) / self.gate_spacing_km()).floor();
+        if gate < 0.0 || gate >= self.range_gate_count as f32 {
+            return None;
+        }
+        Some(gate as usize)
+    }
+
+    // Radial covering `azimuth`, wrapping past 360°
+    pub fn azimuth_index(&self, azimuth: f32) -> usize {
+        let index = (azimuth.rem_euclid(360.0) / self.azimuth_resolution_deg) as usize;
+        index % self.azimuth_count().max(1)
+    }
+
+    // Azimuth where radial `index` starts
+    pub fn azimuth_of(&self, index: usize) -> f32 {
+        index as f32 * self.azimuth_resolution_deg
+    }
+
+    // Original two-sector layout with this radar's overlap
+    pub fn sector_plan(&self) -> SectorPlan {
+        SectorPlan::two_way(self.overlap_degrees)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> RadarConfig {
+        RadarConfig {
+            azimuth_resolution_deg: 0.5,
+            range_gate_count: 100,
+            gate_spacing_m: 250.0,
+            first_gate_m: 2000.0,
+            overlap_degrees: 10.0,
+        }
+    }
+
+    #[test]
+    fn default_matches_the_original_radar() {
+        let config = RadarConfig::default();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.azimuth_count(), 360);
+        assert_eq!(config.range_bins().len(), RANGE_BINS);
+        assert_eq!(config.max_range_km(), 50.0);
+    }
+
+    #[test]
+    fn geometry_follows_the_settings() {
+        let config = config();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.azimuth_count(), 720);
+        assert_eq!(config.first_gate_km(), 2.0);
+        assert_eq!(config.gate_spacing_km(), 0.25);
+        assert_eq!(config.max_range_km(), 27.0);
+
+        let range_bins = config.range_bins();
+        assert_eq!(range_bins.len(), 100);
+        assert_eq!(range_bins[0], 2.0);
+        assert_eq!(range_bins[4], 3.0);
+    }
+
+    #[test]
+    fn gate_index_is_none_outside_the_gates() {
+        let config = config();
+        assert_eq!(config.gate_index(0.0), None);
+        assert_eq!(config.gate_index(1.99), None); // Before the first gate
+        assert_eq!(config.gate_index(2.0), Some(0));
+        assert_eq!(config.gate_index(2.3), Some(1));
+        assert_eq!(config.gate_index(26.9), Some(99));
+        assert_eq!(config.gate_index(27.0), None); // Past the last gate
+        assert_eq!(config.gate_index(-5.0), None);
+    }
+
+    #[test]
+    fn azimuth_index_wraps() {
+        let config = config();
+        assert_eq!(config.azimuth_index(0.0), 0);
+        assert_eq!(config.azimuth_index(10.4), 20);
+        assert_eq!(config.azimuth_index(359.9), 719);
+        assert_eq!(config.azimuth_index(360.0), 0);
+        assert_eq!(config.azimuth_index(-0.5), 719);
+        assert_eq!(config.azimuth_of(20), 10.0);
+    }
+
+    #[test]
+    fn resolution_must_divide_the_circle() {
+        for resolution in [0.7, 7.0, 400.0, 0.0, -1.0, f32::NAN] {
+            let config = RadarConfig {
+                azimuth_resolution_deg: resolution,
+                ..config()
+            };
+            assert!(config.validate().is_err(), "{}° accepted", resolution);
+        }
+        for resolution in [0.25, 0.5, 1.0, 1.5, 2.0, 360.0] {
+            let config = RadarConfig {
+                azimuth_resolution_deg: resolution,
+                ..config()
+            };
+            assert!(config.validate().is_ok(), "{}° rejected", resolution);
+        }
+    }
+
+    #[test]
+    fn other_invalid_settings_are_rejected() {
+        let invalid = [
+            RadarConfig {
+                range_gate_count: 0,
+                ..config()
+            },
+            RadarConfig {
+                gate_spacing_m: 0.0,
+                ..config()
+            },
+            RadarConfig {
+                first_gate_m: -1.0,
+                ..config()
+            },
+            RadarConfig {
+                overlap_degrees: 180.0,
+                ..config()
+            },
+        ];
+        for config in invalid {
+            assert!(config.validate().is_err(), "{:?} accepted", config);
+        }
+    }
+
+    #[test]
+    fn sector_plan_is_the_two_way_split() {
+        let config = config();
+        assert_eq!(config.sector_plan(), SectorPlan::two_way(10.0));
+        assert_eq!(
+            RadarConfig::default().sector_plan(),
+            SectorPlan::two_way(OVERLAP_DEGREES)
+        );
+    }
+}
@@ -0,0 +1,527 @@
+use crate::field::{is_missing, MISSING};
+use crate::grid::PolarGrid;
+use crate::quantize::{QuantizedSweep, SampleFormat};
+use crate::radar_simulator::RadarSweep;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+
+const MAX_DELTA_CODE: f32 = i8::MAX as f32;
+const MISSING_CODE: i8 = i8::MIN;
+
+// Per-gate differences against the previous frame, quantized to i8 with a
+// per-frame step: `sample = previous + code * step`. A missing previous
+// sample counts as 0, `i8::MIN` marks a sample that is missing now.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeltaGrid {
+    pub azimuth_count: usize,
+    pub range_count: usize,
+    pub step: f32,
+    pub codes: Vec<i8>,
+}
+
+// Sweep sent as a difference against the frame with `base_sequence_id`,
+// the previous sweep of the same tilt. Range gates, elevation and field units
+// are unchanged from the base, so they are not repeated.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeltaSweep {
+    pub timestamp: u64,
+    pub sequence_id: u64,
+    pub base_sequence_id: u64,
+    pub volume_id: u64,
+    pub tilt: usize,
+    pub azimuth_start: f32,
+    pub azimuth_end: f32,
+    pub fields: Vec<DeltaField>, // Same fields, in the same order, as the base
+    pub client_id: usize,
+}
+
+// Differences of one field against the base frame's field of the same name
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeltaField {
+    pub name: String,
+    pub data: DeltaGrid,
+    pub overlap_region: DeltaGrid,
+}
+
+impl DeltaSweep {
+    // Rebuild the full sweep from the frame this delta was made against
+    pub fn apply(&self, base: &RadarSweep) -> Result<RadarSweep, Box<dyn Error>> {
+        if base.sequence_id != self.base_sequence_id {
+            return Err(format!(
+                "Delta for sweep {} needs base {}, have {}",
+                self.sequence_id, self.base_sequence_id, base.sequence_id
+            )
+            .into());
+        }
+        if base.tilt != self.tilt {
+            return Err(format!(
+                "Delta for tilt {} cannot apply to tilt {}",
+                self.tilt, base.tilt
+            )
+            .into());
+        }
+        if base.fields.len() != self.fields.len() {
+            return Err("Delta fields do not match its base".into());
+        }
+
+        let mut fields = Vec::with_capacity(self.fields.len());
+        for (base_field, delta) in base.fields.iter().zip(&self.fields) {
+            if base_field.name != delta.name {
+                return Err(format!(
+                    "Delta field {} does not match base field {}",
+                    delta.name, base_field.name
+                )
+                .into());
+            }
+            fields.push(base_field.with_grids(
+                apply_grid(&base_field.data, &delta.data)?,
+                apply_grid(&base_field.overlap_region, &delta.overlap_region)?,
+            ));
+        }
+
+        Ok(RadarSweep {
+            timestamp: self.timestamp,
+            sequence_id: self.sequence_id,
+            volume_id: self.volume_id,
+            tilt: self.tilt,
+            elevation_deg: base.elevation_deg,
+            azimuth_start: self.azimuth_start,
+            azimuth_end: self.azimuth_end,
+            range_bins: base.range_bins.clone(),
+            fields,
+            client_id: self.client_id,
+        })
+    }
+}
+
+// Server-side delta state for one client. The encoder tracks exactly what
+// the client reconstructs, so quantization error never accumulates. Each tilt
+// of a volume scan is coded against its own previous sweep, with its own
+// keyframes.
+#[derive(Debug, Clone)]
+pub struct DeltaEncoder {
+    keyframe_interval: u32,
+    references: HashMap<usize, (RadarSweep, u32)>, // Tilt -> the client's current frame, deltas since its keyframe
+}
+
+impl DeltaEncoder {
+    pub fn new(keyframe_interval: u32) -> Self {
+        Self {
+            keyframe_interval: keyframe_interval.max(1),
+            references: HashMap::new(),
+        }
+    }
+
+    pub fn keyframe_interval(&self) -> u32 {
+        self.keyframe_interval
+    }
+
+    // Send a keyframe next on every tilt, e.g. after the client lost a frame
+    pub fn request_keyframe(&mut self) {
+        self.references.clear();
+    }
+
+    // `None` means `sweep` has to go out as a keyframe in `format`,
+    // otherwise the delta to send instead
+    pub fn encode(&mut self, sweep: &RadarSweep, format: SampleFormat) -> Option<DeltaSweep> {
+        let (reference, since_keyframe) = match self.references.remove(&sweep.tilt) {
+            Some((reference, since_keyframe))
+                if since_keyframe + 1 < self.keyframe_interval
+                    && same_layout(&reference, sweep) =>
+            {
+                (reference, since_keyframe)
+            }
+            _ => {
+                // The client will hold the keyframe as decoded from the wire
+                let keyframe = match QuantizedSweep::encode(sweep, format) {
+                    Some(quantized) => quantized.decode().unwrap_or_else(|_| sweep.clone()),
+                    None => sweep.clone(),
+                };
+                self.references.insert(sweep.tilt, (keyframe, 0));
+                return None;
+            }
+        };
+
+        let delta = DeltaSweep {
+            timestamp: sweep.timestamp,
+            sequence_id: sweep.sequence_id,
+            base_sequence_id: reference.sequence_id,
+            volume_id: sweep.volume_id,
+            tilt: sweep.tilt,
+            azimuth_start: sweep.azimuth_start,
+            azimuth_end: sweep.azimuth_end,
+            fields: reference
+                .fields
+                .iter()
+                .zip(&sweep.fields)
+                .map(|(previous, field)| DeltaField {
+                    name: field.name.clone(),
+                    data: diff_grid(&previous.data, &field.data),
+                    overlap_region: diff_grid(&previous.overlap_region, &field.overlap_region),
+                })
+                .collect(),
+            client_id: sweep.client_id,
+        };
+
+        // Same arithmetic as the client, so both sides stay bit-identical
+        if let Ok(applied) = delta.apply(&reference) {
+            self.references
+                .insert(sweep.tilt, (applied, since_keyframe + 1));
+        }
+        Some(delta)
+    }
+}
+
+fn same_layout(reference: &RadarSweep, sweep: &RadarSweep) -> bool {
+    reference.azimuth_start == sweep.azimuth_start
+        && reference.elevation_deg == sweep.elevation_deg
+        && reference.range_bins == sweep.range_bins
+        && reference.azimuth_count() == sweep.azimuth_count()
+        && reference.overlap_azimuth_count() == sweep.overlap_azimuth_count()
+        && reference.field_names() == sweep.field_names()
+}
+
+fn diff_grid(reference: &PolarGrid, current: &PolarGrid) -> DeltaGrid {
+    let max_change = reference
+        .values()
+        .iter()
+        .zip(current.values())
+        .map(|(&previous, sample)| (sample - present_or_zero(previous)).abs())
+        .filter(|change| change.is_finite())
+        .fold(0.0f32, f32::max);
+    let step = max_change / MAX_DELTA_CODE;
+
+    let codes = reference
+        .values()
+        .iter()
+        .zip(current.values())
+        .map(|(&previous, &sample)| {
+            if is_missing(sample) {
+                MISSING_CODE
+            } else if step > 0.0 {
+                ((sample - present_or_zero(previous)) / step)
+                    .round()
+                    .clamp(-MAX_DELTA_CODE, MAX_DELTA_CODE) as i8
+            } else {
+                0
+            }
+        })
+        .collect();
+
+    DeltaGrid {
+        azimuth_count: current.azimuth_count(),
+        range_count: current.range_count(),
+        step,
+        codes,
+    }
+}
+
+fn apply_grid(base: &PolarGrid, delta: &DeltaGrid) -> Result<PolarGrid, Box<dyn Error>> {
+    if base.azimuth_count() != delta.azimuth_count || base.range_count() != delta.range_count {
+        return Err("Delta grid does not match its base".into());
+    }
+
+    let values = base
+        .values()
+        .iter()
+        .zip(&delta.codes)
+        .map(|(&previous, &code)| {
+            if code == MISSING_CODE {
+                MISSING
+            } else {
+                present_or_zero(previous) + code as f32 * delta.step
+            }
+        })
+        .collect();
+    PolarGrid::from_values(delta.azimuth_count, delta.range_count, values)
+}
+
+fn present_or_zero(value: f32) -> f32 {
+    if is_missing(value) {
+        0.0
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::SweepField;
+    use crate::power::SampleUnit;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const AZIMUTHS: usize = 6;
+    const GATES: usize = 8;
+
+    fn sweep(sequence_id: u64, tilt: usize, values: Vec<f32>) -> RadarSweep {
+        RadarSweep {
+            timestamp: sequence_id * 1000,
+            sequence_id,
+            volume_id: sequence_id / 2,
+            tilt,
+            elevation_deg: 0.5 + tilt as f32,
+            azimuth_start: 0.0,
+            azimuth_end: AZIMUTHS as f32,
+            range_bins: (0..GATES).map(|gate| gate as f32 * 0.25).collect(),
+            fields: vec![SweepField {
+                name: "intensity".to_string(),
+                units: SampleUnit::Intensity,
+                data: PolarGrid::from_values(AZIMUTHS, GATES, values).unwrap(),
+                overlap_region: PolarGrid::from_values(1, GATES, vec![0.5; GATES]).unwrap(),
+            }],
+            client_id: 0,
+        }
+    }
+
+    fn random_values(rng: &mut StdRng) -> Vec<f32> {
+        (0..AZIMUTHS * GATES)
+            .map(|_| rng.gen_range(0.0..10.0))
+            .collect()
+    }
+
+    fn values(sweep: &RadarSweep) -> &[f32] {
+        sweep.fields[0].data.values()
+    }
+
+    // Receives what `encoder` sends, the way `RadarClient` does
+    fn receive(
+        encoder: &mut DeltaEncoder,
+        held: &mut HashMap<usize, RadarSweep>,
+        sweep: &RadarSweep,
+        format: SampleFormat,
+    ) -> Option<DeltaSweep> {
+        let delta = encoder.encode(sweep, format);
+        let received = match &delta {
+            Some(delta) => delta.apply(&held[&delta.tilt]).unwrap(),
+            None => match QuantizedSweep::encode(sweep, format) {
+                Some(quantized) => quantized.decode().unwrap(),
+                None => sweep.clone(),
+            },
+        };
+        held.insert(received.tilt, received);
+        delta
+    }
+
+    #[test]
+    fn keyframes_follow_the_interval() {
+        let mut encoder = DeltaEncoder::new(3);
+        let mut rng = StdRng::seed_from_u64(1);
+        let kinds: Vec<bool> = (0..8)
+            .map(|sequence_id| {
+                let current = sweep(sequence_id, 0, random_values(&mut rng));
+                encoder.encode(&current, SampleFormat::Float32).is_some()
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [false, true, true, false, true, true, false, true],
+            "keyframe, two deltas, keyframe, ..."
+        );
+    }
+
+    #[test]
+    fn interval_of_one_sends_only_keyframes() {
+        let mut encoder = DeltaEncoder::new(0); // Clamped to 1
+        assert_eq!(encoder.keyframe_interval(), 1);
+        for sequence_id in 0..3 {
+            let current = sweep(sequence_id, 0, vec![1.0; AZIMUTHS * GATES]);
+            assert!(encoder.encode(&current, SampleFormat::Float32).is_none());
+        }
+    }
+
+    #[test]
+    fn error_does_not_accumulate() {
+        let mut encoder = DeltaEncoder::new(1000);
+        let mut held = HashMap::new();
+        let mut rng = StdRng::seed_from_u64(2);
+
+        for sequence_id in 0..200 {
+            let current = sweep(sequence_id, 0, random_values(&mut rng));
+            let delta = receive(&mut encoder, &mut held, &current, SampleFormat::Float32);
+
+            // Each frame is off by at most half its own step, never more
+            let tolerance = delta.map_or(0.0, |delta| delta.fields[0].data.step / 2.0 + 1e-4);
+            for (&sent, &got) in values(&current).iter().zip(values(&held[&0])) {
+                assert!(
+                    (sent - got).abs() <= tolerance,
+                    "{} received as {}",
+                    sent,
+                    got
+                );
+            }
+            // The encoder's reference is exactly what the client holds
+            assert_eq!(encoder.references[&0].0.fields, held[&0].fields);
+        }
+    }
+
+    #[test]
+    fn quantized_keyframes_are_the_reference() {
+        let mut encoder = DeltaEncoder::new(4);
+        let mut held = HashMap::new();
+        let mut rng = StdRng::seed_from_u64(3);
+
+        for sequence_id in 0..10 {
+            let current = sweep(sequence_id, 0, random_values(&mut rng));
+            receive(&mut encoder, &mut held, &current, SampleFormat::Linear8);
+            assert_eq!(encoder.references[&0].0.fields, held[&0].fields);
+        }
+    }
+
+    #[test]
+    fn large_jumps_saturate_nothing() {
+        let mut encoder = DeltaEncoder::new(10);
+        let mut held = HashMap::new();
+        receive(
+            &mut encoder,
+            &mut held,
+            &sweep(0, 0, vec![0.0; AZIMUTHS * GATES]),
+            SampleFormat::Float32,
+        );
+
+        // One gate jumps far beyond what 127 small steps could reach
+        let mut jumped = vec![0.01; AZIMUTHS * GATES];
+        jumped[5] = 1.0e6;
+        jumped[6] = -1.0e6;
+        let current = sweep(1, 0, jumped);
+        let delta = receive(&mut encoder, &mut held, &current, SampleFormat::Float32).unwrap();
+
+        let grid = &delta.fields[0].data;
+        assert!(grid.codes.iter().all(|&code| code != MISSING_CODE));
+        assert_eq!(grid.codes[5], i8::MAX);
+        assert_eq!(grid.codes[6], -i8::MAX);
+        assert_eq!(values(&held[&0])[5], 1.0e6);
+        assert_eq!(values(&held[&0])[6], -1.0e6);
+    }
+
+    #[test]
+    fn unchanged_sweep_is_all_zero_codes() {
+        let mut encoder = DeltaEncoder::new(10);
+        let values = vec![3.0; AZIMUTHS * GATES];
+        encoder.encode(&sweep(0, 0, values.clone()), SampleFormat::Float32);
+        let delta = encoder
+            .encode(&sweep(1, 0, values), SampleFormat::Float32)
+            .unwrap();
+        assert_eq!(delta.fields[0].data.step, 0.0);
+        assert!(delta.fields[0].data.codes.iter().all(|&code| code == 0));
+    }
+
+    #[test]
+    fn missing_samples_come_and_go() {
+        let mut encoder = DeltaEncoder::new(10);
+        let mut held = HashMap::new();
+
+        let mut first = vec![2.0; AZIMUTHS * GATES];
+        first[0] = MISSING;
+        receive(
+            &mut encoder,
+            &mut held,
+            &sweep(0, 0, first),
+            SampleFormat::Float32,
+        );
+
+        let mut second = vec![2.5; AZIMUTHS * GATES];
+        second[1] = MISSING; // Newly missing, while gate 0 reappears
+        let delta = receive(
+            &mut encoder,
+            &mut held,
+            &sweep(1, 0, second),
+            SampleFormat::Float32,
+        )
+        .unwrap();
+
+        assert_eq!(delta.fields[0].data.codes[1], MISSING_CODE);
+        let received = values(&held[&0]);
+        assert!((received[0] - 2.5).abs() <= delta.fields[0].data.step / 2.0 + 1e-6);
+        assert!(is_missing(received[1]));
+        assert!((received[2] - 2.5).abs() <= delta.fields[0].data.step / 2.0 + 1e-6);
+    }
+
+    #[test]
+    fn tilts_keep_their_own_references() {
+        let mut encoder = DeltaEncoder::new(10);
+        let mut held = HashMap::new();
+        let mut rng = StdRng::seed_from_u64(4);
+
+        // A volume scan alternating two tilts: each tilt starts with its own keyframe
+        let mut deltas = Vec::new();
+        for sequence_id in 0..6 {
+            let tilt = sequence_id as usize % 2;
+            let current = sweep(sequence_id, tilt, random_values(&mut rng));
+            deltas.push(receive(
+                &mut encoder,
+                &mut held,
+                &current,
+                SampleFormat::Float32,
+            ));
+        }
+
+        assert!(deltas[0].is_none() && deltas[1].is_none());
+        for (sequence_id, delta) in deltas.iter().enumerate().skip(2) {
+            let delta = delta.as_ref().expect("delta after each tilt's keyframe");
+            assert_eq!(delta.tilt, sequence_id % 2);
+            assert_eq!(delta.base_sequence_id, sequence_id as u64 - 2);
+        }
+
+        // A delta only applies to the previous sweep of its own tilt
+        let delta = deltas[5].as_ref().unwrap();
+        let mut wrong_tilt = held[&0].clone();
+        wrong_tilt.sequence_id = delta.base_sequence_id;
+        assert!(delta.apply(&wrong_tilt).is_err());
+    }
+
+    #[test]
+    fn resync_sends_a_keyframe_on_every_tilt() {
+        let mut encoder = DeltaEncoder::new(10);
+        let mut rng = StdRng::seed_from_u64(5);
+        for sequence_id in 0..4 {
+            let tilt = sequence_id as usize % 2;
+            encoder.encode(
+                &sweep(sequence_id, tilt, random_values(&mut rng)),
+                SampleFormat::Float32,
+            );
+        }
+
+        encoder.request_keyframe();
+        for sequence_id in 4..6 {
+            let tilt = sequence_id as usize % 2;
+            let current = sweep(sequence_id, tilt, random_values(&mut rng));
+            assert!(encoder.encode(&current, SampleFormat::Float32).is_none());
+        }
+        let current = sweep(6, 0, random_values(&mut rng));
+        assert!(encoder.encode(&current, SampleFormat::Float32).is_some());
+    }
+
+    #[test]
+    fn layout_change_forces_a_keyframe() {
+        let mut encoder = DeltaEncoder::new(10);
+        encoder.encode(
+            &sweep(0, 0, vec![1.0; AZIMUTHS * GATES]),
+            SampleFormat::Float32,
+        );
+
+        let mut moved = sweep(1, 0, vec![1.0; AZIMUTHS * GATES]);
+        moved.azimuth_start = 90.0;
+        assert!(encoder.encode(&moved, SampleFormat::Float32).is_none());
+    }
+
+    #[test]
+    fn delta_needs_its_base_frame() {
+        let mut encoder = DeltaEncoder::new(10);
+        let base = sweep(0, 0, vec![1.0; AZIMUTHS * GATES]);
+        encoder.encode(&base, SampleFormat::Float32);
+        let delta = encoder
+            .encode(
+                &sweep(1, 0, vec![2.0; AZIMUTHS * GATES]),
+                SampleFormat::Float32,
+            )
+            .unwrap();
+
+        assert!(delta.apply(&base).is_ok());
+        let mut stale = base.clone();
+        stale.sequence_id = 7;
+        assert!(delta.apply(&stale).is_err());
+    }
+}
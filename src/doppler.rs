@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::f32::consts::{PI, SQRT_2};
+
+// Names of the Doppler fields of a `RadarSweep`
+pub const VELOCITY: &str = "velocity"; // Mean radial velocity (m/s), positive away from the radar
+pub const SPECTRUM_WIDTH: &str = "spectrum_width"; // Velocity spread within the gate (m/s)
+
+// Share of the noise power that looks coherent to the pulse-pair estimator,
+// so weak echoes get a noisy velocity and a widened spectrum
+const NOISE_COHERENCE: f32 = 0.3;
+
+// Pulse-pair Doppler processing. Velocities are only measured within
+// ±`nyquist_velocity_ms`; faster echoes fold back into that interval.
+//
+//   doppler = { nyquist_velocity_ms = 25.0 }
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DopplerConfig {
+    pub nyquist_velocity_ms: f32, // λ·PRF/4, 25 m/s for an S-band radar at 1 kHz
+}
+
+impl Default for DopplerConfig {
+    fn default() -> Self {
+        Self {
+            nyquist_velocity_ms: 25.0,
+        }
+    }
+}
+
+impl DopplerConfig {
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if !self.nyquist_velocity_ms.is_finite() || self.nyquist_velocity_ms <= 0.0 {
+            return Err(
+                format!("Invalid Nyquist velocity {} m/s", self.nyquist_velocity_ms).into(),
+            );
+        }
+        Ok(())
+    }
+}
+
+// Power and lag-one autocorrelation of one range gate, summed over every
+// echo in it. Sums of several spectra are again a spectrum, and the mean
+// velocity and width are read off the total as a pulse-pair processor would.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DopplerSpectrum {
+    pub power: f32,
+    pub autocorrelation: [f32; 2], // Lag-one autocorrelation (real, imaginary)
+}
+
+impl DopplerSpectrum {
+    // Echo of `power` moving at `velocity_ms` with a Gaussian spread of `width_ms`
+    pub fn echo(config: &DopplerConfig, power: f32, velocity_ms: f32, width_ms: f32) -> Self {
+        let nyquist = config.nyquist_velocity_ms;
+        let coherence = (-(PI * width_ms / nyquist).powi(2) / 8.0).exp();
+        let (sin, cos) = (PI * velocity_ms / nyquist).sin_cos();
+        Self {
+            power,
+            autocorrelation: [power * coherence * cos, power * coherence * sin],
+        }
+    }
+
+    // White receiver noise: flat across the Nyquist interval, with a bit of
+    // coherence at a random `phase` between -1 and 1
+    pub fn noise(power: f32, phase: f32) -> Self {
+        let (sin, cos) = (PI * phase).sin_cos();
+        Self {
+            power,
+            autocorrelation: [power * NOISE_COHERENCE * cos, power * NOISE_COHERENCE * sin],
+        }
+    }
+
+    pub fn add(&mut self, other: &DopplerSpectrum) {
+        self.power += other.power;
+        self.autocorrelation[0] += other.autocorrelation[0];
+        self.autocorrelation[1] += other.autocorrelation[1];
+    }
+
+    // Same spectrum with a total power of `power`
+    pub fn scaled_to(&self, power: f32) -> Self {
+        if self.power <= 0.0 {
+            return Self::default();
+        }
+        let scale = power / self.power;
+        Self {
+            power,
+            autocorrelation: self.autocorrelation.map(|value| value * scale),
+        }
+    }
+
+    // Mean radial velocity, already aliased into ±Nyquist
+    pub fn velocity_ms(&self, config: &DopplerConfig) -> f32 {
+        let [re, im] = self.autocorrelation;
+        config.nyquist_velocity_ms / PI * im.atan2(re)
+    }
+
+    // Spectrum width, at most the Nyquist velocity
+    pub fn width_ms(&self, config: &DopplerConfig) -> f32 {
+        let nyquist = config.nyquist_velocity_ms;
+        let magnitude = self.autocorrelation[0].hypot(self.autocorrelation[1]);
+        if self.power <= 0.0 {
+            return 0.0;
+        }
+        if magnitude <= 0.0 {
+            return nyquist;
+        }
+        let width = 2.0 * SQRT_2 * nyquist / PI * (self.power / magnitude).ln().max(0.0).sqrt();
+        width.min(nyquist)
+    }
+}
@@ -0,0 +1,237 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::error::Error;
+use std::time::Duration;
+
+// Network impairments applied to one connection's outgoing frames.
+// Disabled by default; test harnesses enable it per listener port.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FaultConfig {
+    pub delay: Duration,                      // Fixed latency added to every frame
+    pub jitter: Duration,                     // Extra random latency, uniform in 0..=jitter
+    pub drop_probability: f64,                // Chance (0.0-1.0) a frame is silently dropped
+    pub bandwidth_bytes_per_sec: Option<u64>, // Link speed used to add transmission time
+    pub seed: u64,                            // Same seed + connection = same fault sequence
+}
+
+impl FaultConfig {
+    // The fields are public, so settings are checked before an injector uses them
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if !(0.0..=1.0).contains(&self.drop_probability) {
+            return Err(format!(
+                "Invalid drop probability {} (expected 0.0-1.0)",
+                self.drop_probability
+            )
+            .into());
+        }
+        if self.bandwidth_bytes_per_sec == Some(0) {
+            return Err("Bandwidth must be at least 1 byte per second".into());
+        }
+        Ok(())
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_drop_probability(mut self, drop_probability: f64) -> Self {
+        self.drop_probability = if drop_probability.is_nan() {
+            0.0
+        } else {
+            drop_probability.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    pub fn with_bandwidth(mut self, bytes_per_sec: u64) -> Self {
+        self.bandwidth_bytes_per_sec = Some(bytes_per_sec.max(1));
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+// Injected delay of one frame. Latency is per frame, so frames in flight
+// overlap; transmission time queues up behind earlier frames like on a real link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameDelay {
+    pub latency: Duration,      // Fixed delay plus jitter
+    pub transmission: Duration, // Time on the wire at the capped bandwidth
+}
+
+impl FrameDelay {
+    pub fn latency(latency: Duration) -> Self {
+        Self {
+            latency,
+            transmission: Duration::ZERO,
+        }
+    }
+}
+
+// What to do with one outgoing frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultAction {
+    Deliver(FrameDelay), // Send after this delay (zero means immediately)
+    Drop,
+}
+
+// Per-connection fault state with its own deterministic random stream
+#[derive(Debug, Clone)]
+pub struct FaultInjector {
+    config: FaultConfig,
+    rng: StdRng,
+}
+
+impl FaultInjector {
+    pub fn new(config: FaultConfig, connection_id: u64) -> Result<Self, Box<dyn Error>> {
+        config.validate()?;
+        let rng = StdRng::seed_from_u64(config.seed.wrapping_add(connection_id));
+        Ok(Self { config, rng })
+    }
+
+    pub fn config(&self) -> &FaultConfig {
+        &self.config
+    }
+
+    // Decide the fate of a frame of `frame_len` bytes
+    pub fn next_action(&mut self, frame_len: usize) -> FaultAction {
+        if self.config.drop_probability > 0.0 && self.rng.gen_bool(self.config.drop_probability) {
+            return FaultAction::Drop;
+        }
+
+        let jitter = if self.config.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            self.rng.gen_range(Duration::ZERO..=self.config.jitter)
+        };
+        let transmission = match self.config.bandwidth_bytes_per_sec {
+            Some(bytes_per_sec) => Duration::from_secs_f64(frame_len as f64 / bytes_per_sec as f64),
+            None => Duration::ZERO,
+        };
+
+        FaultAction::Deliver(FrameDelay {
+            latency: self.config.delay + jitter,
+            transmission,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actions(config: &FaultConfig, connection_id: u64, count: usize) -> Vec<FaultAction> {
+        let mut injector = FaultInjector::new(config.clone(), connection_id).unwrap();
+        (0..count).map(|i| injector.next_action(100 + i)).collect()
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_faults() {
+        let config = FaultConfig::default()
+            .with_jitter(Duration::from_millis(50))
+            .with_drop_probability(0.3)
+            .with_seed(42);
+
+        let first = actions(&config, 7, 200);
+        assert_eq!(first, actions(&config, 7, 200));
+        assert!(first.contains(&FaultAction::Drop));
+        assert!(first.iter().any(|action| *action != FaultAction::Drop));
+
+        // Each connection draws its own sequence
+        assert_ne!(first, actions(&config, 8, 200));
+    }
+
+    #[test]
+    fn drop_probability_zero_and_one() {
+        let never = FaultConfig::default().with_drop_probability(0.0);
+        assert!(actions(&never, 0, 100)
+            .iter()
+            .all(|action| *action == FaultAction::Deliver(FrameDelay::default())));
+
+        let always = FaultConfig::default().with_drop_probability(1.0);
+        assert!(actions(&always, 0, 100)
+            .iter()
+            .all(|action| *action == FaultAction::Drop));
+    }
+
+    #[test]
+    fn bandwidth_delay_is_length_over_rate() {
+        let config = FaultConfig::default()
+            .with_delay(Duration::from_millis(5))
+            .with_bandwidth(1_000);
+        let mut injector = FaultInjector::new(config, 0).unwrap();
+
+        // 250 bytes at 1000 B/s take 250 ms on the link, after the 5 ms latency
+        assert_eq!(
+            injector.next_action(250),
+            FaultAction::Deliver(FrameDelay {
+                latency: Duration::from_millis(5),
+                transmission: Duration::from_millis(250),
+            })
+        );
+        assert_eq!(
+            injector.next_action(0),
+            FaultAction::Deliver(FrameDelay::latency(Duration::from_millis(5)))
+        );
+    }
+
+    #[test]
+    fn jitter_stays_within_its_bound() {
+        let delay = Duration::from_millis(10);
+        let jitter = Duration::from_millis(20);
+        let config = FaultConfig::default()
+            .with_delay(delay)
+            .with_jitter(jitter)
+            .with_seed(3);
+
+        let delays: Vec<Duration> = actions(&config, 0, 500)
+            .into_iter()
+            .map(|action| match action {
+                FaultAction::Deliver(delay) => delay.latency,
+                FaultAction::Drop => panic!("nothing should be dropped"),
+            })
+            .collect();
+        assert!(delays.iter().all(|&d| d >= delay && d <= delay + jitter));
+        assert!(delays.iter().any(|&d| d != delays[0]));
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        for drop_probability in [1.5, -0.1, f64::NAN] {
+            let config = FaultConfig {
+                drop_probability,
+                ..FaultConfig::default()
+            };
+            assert!(config.validate().is_err(), "{}", drop_probability);
+            assert!(FaultInjector::new(config, 0).is_err());
+        }
+
+        let config = FaultConfig {
+            bandwidth_bytes_per_sec: Some(0),
+            ..FaultConfig::default()
+        };
+        assert!(FaultInjector::new(config, 0).is_err());
+
+        // The setters clamp instead
+        let config = FaultConfig::default()
+            .with_drop_probability(f64::NAN)
+            .with_bandwidth(0);
+        assert_eq!(config.drop_probability, 0.0);
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            FaultConfig::default()
+                .with_drop_probability(1.5)
+                .drop_probability,
+            1.0
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use crate::grid::PolarGrid;
+use crate::power::SampleUnit;
+use serde::{Deserialize, Serialize};
+
+// Names of the echo-strength field, the first field of every simulated sweep
+pub const INTENSITY: &str = "intensity"; // Unitless samples
+pub const POWER: &str = "power"; // dBm
+pub const REFLECTIVITY: &str = "reflectivity"; // dBZ
+
+// Missing samples (nothing measured, or censored) are NaN in every field.
+// On the wire they get a code of their own, see `quantize` and `delta`.
+pub const MISSING: f32 = f32::NAN;
+
+pub fn is_missing(value: f32) -> bool {
+    value.is_nan()
+}
+
+// Mean of the samples that are not missing, missing if none are
+pub fn mean_present(values: impl IntoIterator<Item = f32>) -> f32 {
+    let (sum, count) = values
+        .into_iter()
+        .filter(|&value| !is_missing(value))
+        .fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    if count == 0 {
+        MISSING
+    } else {
+        sum / count as f32
+    }
+}
+
+// One named quantity measured at every gate of a sweep, e.g. reflectivity or
+// radial velocity. All fields of a sweep share its azimuths and range bins.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SweepField {
+    pub name: String,
+    pub units: SampleUnit,
+    pub data: PolarGrid,           // [azimuth][range] samples
+    pub overlap_region: PolarGrid, // Overlap rows for merging
+}
+
+impl SweepField {
+    // Field of a complete sweep, which has no overlap region yet
+    pub fn new(name: &str, units: SampleUnit, data: PolarGrid) -> Self {
+        Self {
+            name: name.to_string(),
+            units,
+            overlap_region: PolarGrid::new(0, data.range_count()),
+            data,
+        }
+    }
+
+    // Same name and units with other samples
+    pub fn with_grids(&self, data: PolarGrid, overlap_region: PolarGrid) -> Self {
+        Self {
+            name: self.name.clone(),
+            units: self.units,
+            data,
+            overlap_region,
+        }
+    }
+
+    // Smallest and largest sample that is not missing, `None` if all are
+    pub fn value_range(&self) -> Option<(f32, f32)> {
+        let (min, max) = self
+            .data
+            .values()
+            .iter()
+            .filter(|value| value.is_finite())
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &value| {
+                (min.min(value), max.max(value))
+            });
+        (min <= max).then_some((min, max))
+    }
+}
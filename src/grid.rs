@@ -0,0 +1,262 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::ops::{Index, IndexMut, Range};
+use std::slice::{ChunksExact, ChunksExactMut};
+
+// Polar samples in one contiguous row-major buffer:
+// one row per azimuth step, one column per range gate
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(try_from = "GridParts")]
+pub struct PolarGrid {
+    azimuth_count: usize, // Rows
+    range_count: usize,   // Gates per row
+    values: Vec<f32>,     // azimuth_count * range_count samples
+}
+
+// Wire shape of a grid, checked before it becomes a `PolarGrid`
+#[derive(Deserialize)]
+struct GridParts {
+    azimuth_count: usize,
+    range_count: usize,
+    values: Vec<f32>,
+}
+
+impl TryFrom<GridParts> for PolarGrid {
+    type Error = Box<dyn Error>;
+
+    fn try_from(parts: GridParts) -> Result<Self, Box<dyn Error>> {
+        PolarGrid::from_values(parts.azimuth_count, parts.range_count, parts.values)
+    }
+}
+
+impl PolarGrid {
+    // Zero-filled grid
+    pub fn new(azimuth_count: usize, range_count: usize) -> Self {
+        Self {
+            azimuth_count,
+            range_count,
+            values: vec![0.0; azimuth_count * range_count],
+        }
+    }
+
+    pub fn from_values(
+        azimuth_count: usize,
+        range_count: usize,
+        values: Vec<f32>,
+    ) -> Result<Self, Box<dyn Error>> {
+        if azimuth_count.checked_mul(range_count) != Some(values.len()) {
+            return Err(format!(
+                "{} samples do not fill a {}x{} grid",
+                values.len(),
+                azimuth_count,
+                range_count
+            )
+            .into());
+        }
+
+        Ok(Self {
+            azimuth_count,
+            range_count,
+            values,
+        })
+    }
+
+    pub fn azimuth_count(&self) -> usize {
+        self.azimuth_count
+    }
+
+    pub fn range_count(&self) -> usize {
+        self.range_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    // All samples, row after row
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    pub fn values_mut(&mut self) -> &mut [f32] {
+        &mut self.values
+    }
+
+    pub fn get(&self, azimuth: usize, gate: usize) -> Option<f32> {
+        self.row(azimuth)?.get(gate).copied()
+    }
+
+    pub fn row(&self, azimuth: usize) -> Option<&[f32]> {
+        if azimuth >= self.azimuth_count {
+            return None;
+        }
+        let start = azimuth * self.range_count;
+        Some(&self.values[start..start + self.range_count])
+    }
+
+    pub fn row_mut(&mut self, azimuth: usize) -> Option<&mut [f32]> {
+        if azimuth >= self.azimuth_count {
+            return None;
+        }
+        let start = azimuth * self.range_count;
+        Some(&mut self.values[start..start + self.range_count])
+    }
+
+    pub fn rows(&self) -> ChunksExact<'_, f32> {
+        self.values.chunks_exact(self.range_count.max(1))
+    }
+
+    pub fn rows_mut(&mut self) -> ChunksExactMut<'_, f32> {
+        self.values.chunks_exact_mut(self.range_count.max(1))
+    }
+
+    // Append one azimuth row, which must have `range_count` gates
+    pub fn push_row(&mut self, row: &[f32]) {
+        assert_eq!(
+            row.len(),
+            self.range_count,
+            "row length must match the grid"
+        );
+        self.values.extend_from_slice(row);
+        self.azimuth_count += 1;
+    }
+
+    // Append every row of `other`, which must have the same gate count
+    pub fn append(&mut self, other: &PolarGrid) {
+        assert_eq!(
+            other.range_count, self.range_count,
+            "gate counts must match to append grids"
+        );
+        self.values.extend_from_slice(&other.values);
+        self.azimuth_count += other.azimuth_count;
+    }
+
+    // Copy of the rows in `azimuths`, clamped to the grid
+    pub fn slice_rows(&self, azimuths: Range<usize>) -> PolarGrid {
+        let end = azimuths.end.min(self.azimuth_count);
+        let start = azimuths.start.min(end);
+
+        PolarGrid {
+            azimuth_count: end - start,
+            range_count: self.range_count,
+            values: self.values[start * self.range_count..end * self.range_count].to_vec(),
+        }
+    }
+
+    // Copy of `row_count` rows from `start_row`, wrapping past the last row
+    pub fn azimuth_window(&self, start_row: usize, row_count: usize) -> PolarGrid {
+        if self.azimuth_count == 0 {
+            return PolarGrid::new(0, self.range_count);
+        }
+
+        let row_count = row_count.min(self.azimuth_count);
+        let start_row = start_row % self.azimuth_count;
+        let first = (self.azimuth_count - start_row).min(row_count);
+
+        let mut window = self.slice_rows(start_row..start_row + first);
+        if first < row_count {
+            window.append(&self.slice_rows(0..row_count - first));
+        }
+        window
+    }
+}
+
+// `grid[(azimuth, gate)]`
+impl Index<(usize, usize)> for PolarGrid {
+    type Output = f32;
+
+    fn index(&self, (azimuth, gate): (usize, usize)) -> &f32 {
+        assert!(azimuth < self.azimuth_count && gate < self.range_count);
+        &self.values[azimuth * self.range_count + gate]
+    }
+}
+
+impl IndexMut<(usize, usize)> for PolarGrid {
+    fn index_mut(&mut self, (azimuth, gate): (usize, usize)) -> &mut f32 {
+        assert!(azimuth < self.azimuth_count && gate < self.range_count);
+        &mut self.values[azimuth * self.range_count + gate]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Grid whose every sample holds its own row number
+    fn numbered_grid(azimuth_count: usize, range_count: usize) -> PolarGrid {
+        let values = (0..azimuth_count)
+            .flat_map(|row| vec![row as f32; range_count])
+            .collect();
+        PolarGrid::from_values(azimuth_count, range_count, values).unwrap()
+    }
+
+    fn row_numbers(grid: &PolarGrid) -> Vec<usize> {
+        grid.rows().map(|row| row[0] as usize).collect()
+    }
+
+    #[test]
+    fn rows_are_stored_back_to_back() {
+        let mut grid = PolarGrid::new(0, 3);
+        grid.push_row(&[1.0, 2.0, 3.0]);
+        grid.push_row(&[4.0, 5.0, 6.0]);
+
+        assert_eq!(grid.azimuth_count(), 2);
+        assert_eq!(grid.values(), &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(grid.row(1), Some(&[4.0, 5.0, 6.0][..]));
+        assert_eq!(grid[(1, 2)], 6.0);
+        assert_eq!(grid.get(2, 0), None);
+        assert_eq!(grid.get(0, 3), None);
+    }
+
+    #[test]
+    fn from_values_needs_exactly_one_sample_per_cell() {
+        assert!(PolarGrid::from_values(2, 3, vec![0.0; 6]).is_ok());
+        assert!(PolarGrid::from_values(2, 3, vec![0.0; 5]).is_err());
+        assert!(PolarGrid::from_values(2, 3, vec![0.0; 7]).is_err());
+        assert!(PolarGrid::from_values(usize::MAX, 2, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn azimuth_window_wraps_past_the_last_row() {
+        let grid = numbered_grid(360, 2);
+
+        // A window across north: 350°..360° then 0°..10°
+        let window = grid.azimuth_window(350, 20);
+        let expected: Vec<usize> = (350..360).chain(0..10).collect();
+        assert_eq!(row_numbers(&window), expected);
+        assert_eq!(window.range_count(), 2);
+
+        // A start past the end wraps as well
+        assert_eq!(row_numbers(&grid.azimuth_window(365, 3)), vec![5, 6, 7]);
+    }
+
+    #[test]
+    fn azimuth_window_covers_a_full_circle_once() {
+        let grid = numbered_grid(360, 2);
+
+        let full = grid.azimuth_window(90, 360);
+        let expected: Vec<usize> = (90..360).chain(0..90).collect();
+        assert_eq!(row_numbers(&full), expected);
+
+        // Asking for more than a full circle does not repeat rows
+        assert_eq!(grid.azimuth_window(0, 400), grid);
+    }
+
+    #[test]
+    fn azimuth_window_of_an_empty_grid_is_empty() {
+        let window = PolarGrid::new(0, 4).azimuth_window(10, 5);
+        assert!(window.is_empty());
+        assert_eq!(window.range_count(), 4);
+    }
+
+    #[test]
+    fn deserializing_checks_the_shape() {
+        let grid = numbered_grid(2, 2);
+        let bytes = bincode::serialize(&grid).unwrap();
+        assert_eq!(bincode::deserialize::<PolarGrid>(&bytes).unwrap(), grid);
+
+        // Same layout as a grid, but one sample short
+        let bad = bincode::serialize(&(2usize, 2usize, vec![0.0f32, 1.0, 2.0])).unwrap();
+        assert!(bincode::deserialize::<PolarGrid>(&bad).is_err());
+    }
+}
@@ -0,0 +1,307 @@
+// Target motion on a flat earth centred on the radar: x east, y north (km).
+// Range and azimuth are derived from the position after every step.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Kinematics {
+    pub x_km: f32,
+    pub y_km: f32,
+    pub speed_kmh: f32,           // Ground speed
+    pub heading_deg: f32,         // Direction of travel (degrees from north)
+    pub acceleration_ms2: f32,    // Along-track acceleration
+    pub turn_rate_deg_s: f32,     // Positive turns clockwise; caps steering on a path
+    pub waypoints: Vec<[f32; 2]>, // Path to fly, as [east_km, north_km]
+    pub repeat_waypoints: bool,   // Start the path again after the last point
+    next_waypoint: usize,
+}
+
+impl Kinematics {
+    // Standing still at `azimuth`/`range_km`
+    pub fn from_polar(azimuth: f32, range_km: f32) -> Self {
+        let (az_sin, az_cos) = azimuth.to_radians().sin_cos();
+        Self {
+            x_km: range_km * az_sin,
+            y_km: range_km * az_cos,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_velocity(mut self, heading_deg: f32, speed_kmh: f32) -> Self {
+        self.heading_deg = wrap_degrees(heading_deg);
+        self.speed_kmh = speed_kmh;
+        self
+    }
+
+    pub fn with_acceleration(mut self, acceleration_ms2: f32) -> Self {
+        self.acceleration_ms2 = acceleration_ms2;
+        self
+    }
+
+    pub fn with_turn_rate(mut self, turn_rate_deg_s: f32) -> Self {
+        self.turn_rate_deg_s = turn_rate_deg_s;
+        self
+    }
+
+    // Fly through `waypoints` in order, heading straight for the first one
+    pub fn with_waypoints(mut self, waypoints: Vec<[f32; 2]>, repeat: bool) -> Self {
+        self.waypoints = waypoints;
+        self.repeat_waypoints = repeat;
+        self.next_waypoint = 0;
+        if let Some(heading) = self.heading_to_waypoint() {
+            self.heading_deg = heading;
+        }
+        self
+    }
+
+    pub fn range_km(&self) -> f32 {
+        self.x_km.hypot(self.y_km)
+    }
+
+    pub fn azimuth_deg(&self) -> f32 {
+        wrap_degrees(self.x_km.atan2(self.y_km).to_degrees())
+    }
+
+    // Velocity over the ground as [east, north] in km/h
+    pub fn velocity_kmh(&self) -> [f32; 2] {
+        let (heading_sin, heading_cos) = self.heading_deg.to_radians().sin_cos();
+        [self.speed_kmh * heading_sin, self.speed_kmh * heading_cos]
+    }
+
+    // Waypoint being flown to, `None` once a one-way path is done
+    pub fn next_waypoint(&self) -> Option<[f32; 2]> {
+        self.waypoints.get(self.next_waypoint).copied()
+    }
+
+    // Swing the position around the radar, keeping range
+    pub fn rotate(&mut self, degrees: f32) {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let (x, y) = (self.x_km, self.y_km);
+        self.x_km = x * cos + y * sin;
+        self.y_km = y * cos - x * sin;
+        self.heading_deg = wrap_degrees(self.heading_deg + degrees);
+    }
+
+    // Advance `dt` seconds: steer, change speed, then move
+    pub fn step(&mut self, dt: f32) {
+        match self.heading_to_waypoint() {
+            Some(heading) if self.turn_rate_deg_s == 0.0 => self.heading_deg = heading,
+            Some(heading) => {
+                let max_turn = self.turn_rate_deg_s.abs() * dt;
+                let turn = (heading - self.heading_deg + 180.0).rem_euclid(360.0) - 180.0;
+                self.heading_deg += turn.clamp(-max_turn, max_turn);
+            }
+            None => self.heading_deg += self.turn_rate_deg_s * dt,
+        }
+        self.heading_deg = wrap_degrees(self.heading_deg);
+
+        let speed_kmh = self.speed_kmh + self.acceleration_ms2 * 3.6 * dt;
+        self.speed_kmh = speed_kmh.max(0.0); // Slows to a stop, never reverses
+
+        let step_km = self.speed_kmh / 3600.0 * dt;
+        if let Some([x, y]) = self.next_waypoint() {
+            let distance_km = (x - self.x_km).hypot(y - self.y_km);
+            if distance_km <= step_km {
+                // Reached it: continue from the point towards the next one
+                self.x_km = x;
+                self.y_km = y;
+                self.advance_waypoint();
+                return;
+            }
+            if distance_km <= self.turn_radius_km() {
+                // Close enough: a point inside the turn radius may be impossible
+                // to hit and would be circled forever, so fly by it instead
+                self.advance_waypoint();
+            }
+        }
+
+        let [vx, vy] = self.velocity_kmh();
+        self.x_km += vx / 3600.0 * dt;
+        self.y_km += vy / 3600.0 * dt;
+    }
+
+    // Radius of the tightest circle the turn rate allows, zero when steering
+    // is not capped
+    fn turn_radius_km(&self) -> f32 {
+        let turn_rate_rad_s = self.turn_rate_deg_s.abs().to_radians();
+        if turn_rate_rad_s == 0.0 {
+            return 0.0;
+        }
+        self.speed_kmh / 3600.0 / turn_rate_rad_s
+    }
+
+    fn advance_waypoint(&mut self) {
+        self.next_waypoint += 1;
+        if self.repeat_waypoints && self.next_waypoint == self.waypoints.len() {
+            self.next_waypoint = 0;
+        }
+    }
+
+    fn heading_to_waypoint(&self) -> Option<f32> {
+        let [x, y] = self.next_waypoint()?;
+        let (dx, dy) = (x - self.x_km, y - self.y_km);
+        if dx == 0.0 && dy == 0.0 {
+            return None;
+        }
+        Some(wrap_degrees(dx.atan2(dy).to_degrees()))
+    }
+}
+
+// Angle in [0, 360); `rem_euclid` alone can round tiny negative angles up to 360
+fn wrap_degrees(degrees: f32) -> f32 {
+    let wrapped = degrees.rem_euclid(360.0);
+    if wrapped >= 360.0 {
+        0.0
+    } else {
+        wrapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32, tolerance: f32) -> bool {
+        (a - b).abs() <= tolerance
+    }
+
+    #[test]
+    fn polar_position_round_trips() {
+        let kinematics = Kinematics::from_polar(135.0, 20.0);
+        assert!(close(kinematics.range_km(), 20.0, 1e-4));
+        assert!(close(kinematics.azimuth_deg(), 135.0, 1e-3));
+        assert!(close(kinematics.x_km, 14.142, 1e-3));
+        assert!(close(kinematics.y_km, -14.142, 1e-3));
+    }
+
+    #[test]
+    fn moves_in_a_straight_line() {
+        // 360 km/h due east is 0.1 km per second
+        let mut kinematics = Kinematics::from_polar(0.0, 10.0).with_velocity(90.0, 360.0);
+        for _ in 0..100 {
+            kinematics.step(0.1);
+        }
+        assert!(close(kinematics.x_km, 1.0, 1e-3));
+        assert!(close(kinematics.y_km, 10.0, 1e-3));
+        assert_eq!(kinematics.heading_deg, 90.0);
+    }
+
+    #[test]
+    fn acceleration_stops_without_reversing() {
+        let mut kinematics = Kinematics::from_polar(0.0, 10.0)
+            .with_velocity(0.0, 36.0)
+            .with_acceleration(-5.0);
+        kinematics.step(1.0); // 10 m/s minus 5 m/s
+        assert!(close(kinematics.speed_kmh, 18.0, 1e-4));
+        kinematics.step(5.0);
+        assert_eq!(kinematics.speed_kmh, 0.0);
+
+        let position = (kinematics.x_km, kinematics.y_km);
+        kinematics.step(10.0);
+        assert_eq!((kinematics.x_km, kinematics.y_km), position);
+    }
+
+    #[test]
+    fn turn_rate_turns_without_a_path() {
+        let mut kinematics = Kinematics::from_polar(0.0, 10.0)
+            .with_velocity(350.0, 100.0)
+            .with_turn_rate(3.0);
+        kinematics.step(10.0);
+        assert!(close(kinematics.heading_deg, 20.0, 1e-3));
+
+        kinematics.turn_rate_deg_s = -3.0;
+        kinematics.step(10.0);
+        assert!(close(kinematics.heading_deg, 350.0, 1e-3));
+    }
+
+    #[test]
+    fn turn_rate_caps_steering_towards_a_waypoint() {
+        // Waypoint due west while flying north: a 90° turn at 3°/s takes 30 s
+        let mut kinematics = Kinematics::from_polar(0.0, 10.0).with_velocity(0.0, 360.0);
+        kinematics = kinematics.with_turn_rate(3.0);
+        kinematics.waypoints = vec![[-30.0, 10.0]];
+
+        kinematics.step(1.0);
+        assert!(close(kinematics.heading_deg, 357.0, 1e-3)); // Shorter way round
+        for _ in 0..9 {
+            kinematics.step(1.0);
+        }
+        assert!(close(kinematics.heading_deg, 330.0, 0.1));
+
+        // Without a cap the heading snaps straight to the waypoint
+        let mut direct = Kinematics::from_polar(0.0, 10.0).with_velocity(0.0, 360.0);
+        direct.waypoints = vec![[-30.0, 10.0]];
+        direct.step(1.0);
+        assert!(close(direct.heading_deg, 270.0, 0.1));
+    }
+
+    #[test]
+    fn waypoints_are_flown_in_order() {
+        let mut kinematics = Kinematics::from_polar(0.0, 0.0)
+            .with_velocity(0.0, 360.0)
+            .with_waypoints(vec![[1.0, 0.0], [1.0, 1.0]], false);
+        assert_eq!(kinematics.heading_deg, 90.0);
+
+        for _ in 0..10 {
+            kinematics.step(1.0);
+        }
+        // Landed exactly on the first point, now heading north for the second
+        assert_eq!((kinematics.x_km, kinematics.y_km), (1.0, 0.0));
+        assert_eq!(kinematics.next_waypoint(), Some([1.0, 1.0]));
+
+        for _ in 0..10 {
+            kinematics.step(1.0);
+        }
+        assert_eq!(kinematics.next_waypoint(), None);
+        assert_eq!((kinematics.x_km, kinematics.y_km), (1.0, 1.0));
+
+        // A finished one-way path keeps its last heading
+        kinematics.step(1.0);
+        assert!(close(kinematics.heading_deg, 0.0, 1e-3));
+        assert!(close(kinematics.y_km, 1.1, 1e-4));
+    }
+
+    #[test]
+    fn repeating_paths_start_over() {
+        let mut kinematics = Kinematics::from_polar(0.0, 0.0)
+            .with_velocity(0.0, 3600.0)
+            .with_waypoints(vec![[0.0, 1.0], [0.0, 0.0]], true);
+        for _ in 0..2 {
+            kinematics.step(1.0);
+        }
+        assert_eq!(kinematics.next_waypoint(), Some([0.0, 1.0]));
+    }
+
+    #[test]
+    fn waypoint_inside_the_turn_radius_is_not_circled_forever() {
+        // 720 km/h at 3°/s turns on a 3.8 km radius; the waypoint sits 1 km
+        // off the beam, so pure pursuit would orbit it without ever arriving
+        let mut kinematics = Kinematics::from_polar(0.0, 10.0)
+            .with_velocity(90.0, 720.0)
+            .with_turn_rate(3.0);
+        kinematics.waypoints = vec![[0.0, 11.0], [20.0, 10.0]];
+
+        let mut steps = 0;
+        while kinematics.next_waypoint() == Some([0.0, 11.0]) {
+            kinematics.step(0.1);
+            steps += 1;
+            assert!(steps < 10_000, "still circling the first waypoint");
+        }
+        assert_eq!(kinematics.next_waypoint(), Some([20.0, 10.0]));
+
+        // And the next, reachable one is reached
+        for _ in 0..10_000 {
+            kinematics.step(0.1);
+            if kinematics.next_waypoint().is_none() {
+                break;
+            }
+        }
+        assert_eq!(kinematics.next_waypoint(), None);
+    }
+
+    #[test]
+    fn rotate_keeps_range() {
+        let mut kinematics = Kinematics::from_polar(10.0, 25.0).with_velocity(0.0, 100.0);
+        kinematics.rotate(-20.0);
+        assert!(close(kinematics.range_km(), 25.0, 1e-4));
+        assert!(close(kinematics.azimuth_deg(), 350.0, 1e-3));
+        assert!(close(kinematics.heading_deg, 340.0, 1e-3));
+    }
+}
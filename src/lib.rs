@@ -1,11 +1,50 @@
+pub mod antenna;
+pub mod command;
+pub mod compression;
+pub mod config;
+pub mod delta;
+pub mod doppler;
+pub mod fault;
+pub mod field;
+pub mod grid;
+pub mod kinematics;
+pub mod power;
+pub mod protocol;
+pub mod quantize;
 pub mod radar_simulator;
+pub mod scan;
+pub mod scenario;
+pub mod sector;
+pub mod subscription;
 pub mod tcp_server;
+pub mod weather;
+pub mod writer;
 
 // Re-export commonly used types and functions for convenience
 pub use radar_simulator::{
-    RadarSweep, RadarTarget, RadarSimulator, TargetType,
-    extract_client_portion, RANGE_BINS, MAX_RANGE_KM, RANGE_RESOLUTION_M, OVERLAP_DEGREES
+    Radial, RadarSweep, RadarTarget, RadarSimulator, TargetType,
+    extract_client_portion, RANGE_BINS, MAX_RANGE_KM, RANGE_RESOLUTION_M, OVERLAP_DEGREES,
+    DEFAULT_ROTATION_RPM
 };
+pub use antenna::BeamPattern;
+pub use command::{Ack, Command, CommandError};
+pub use compression::{Compression, CompressionReport};
+pub use config::RadarConfig;
+pub use delta::{DeltaEncoder, DeltaSweep};
+pub use doppler::DopplerConfig;
+pub use fault::{FaultAction, FaultConfig, FaultInjector, FrameDelay};
+pub use field::SweepField;
+pub use grid::PolarGrid;
+pub use kinematics::Kinematics;
+pub use power::{PowerModel, SampleUnit};
+pub use protocol::{Message, MessageType, RadarClient, RadarCodec, StreamHeader};
+pub use quantize::{QuantizedSweep, SampleFormat};
+pub use scan::{ScanStrategy, Volume, VolumeAssembler};
+pub use scenario::{Lifetime, MotionModel, Scenario, ScenarioFormat, TargetSpec};
+pub use sector::{SectorBoundary, SectorPlan, SectorSpan};
+pub use subscription::{AzimuthWindow, StreamMode, Subscription};
 pub use tcp_server::{
-    RadarTcpServer, ClientConnections, radar_data_broadcaster, send_radar_data
+    RadarTcpServer, ClientConnections, GroupPolicy, ClientSession, ClientSessions, ServerStats, radar_data_broadcaster, send_radar_data
 };
+pub use weather::{WeatherCell, Wind};
+pub use writer::{ClientWriter, OverflowPolicy, WriterConfig, WriterStats};
@@ -1,29 +1,77 @@
-mod radar_simulator;
-mod tcp_server;
-
-use radar_simulator::{MAX_RANGE_KM, OVERLAP_DEGREES, RANGE_RESOLUTION_M};
 use std::io;
-use tcp_server::RadarTcpServer;
+use rust_tcp_server::{GroupPolicy, RadarConfig, RadarTcpServer, Scenario};
 
 // Application-specific parameters
 const DATA_RATE_HZ: f64 = 0.2; // 1Hz data rate
 
 #[tokio::main]
 async fn main() -> io::Result<()> {
+    let radar_config = RadarConfig::default();
+    let sector_plan = radar_config.sector_plan();
+
+    // Optional scenario file: server [scenario.toml|.json|.yaml]
+    let scenario = match std::env::args().nth(1) {
+        Some(path) => Scenario::load(&path)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?,
+        None => Scenario::default(),
+    };
+
     println!("Starting Enhanced Radar Data Server...");
     println!("Parameters:");
     println!("  - Data Rate: {}Hz", DATA_RATE_HZ);
-    println!("  - Azimuth Resolution: 1°");
     println!(
-        "  - Range: {} km, {} m resolution",
-        MAX_RANGE_KM, RANGE_RESOLUTION_M
+        "  - Scenario: {} ({} targets, noise seed {})",
+        scenario.name,
+        scenario.targets.len(),
+        scenario.noise_seed
+    );
+    match &scenario.power {
+        Some(power) => println!(
+            "  - Samples: {} ({} kW, {} cm, {} dB gain)",
+            power.output, power.transmit_power_kw, power.wavelength_cm, power.antenna_gain_db
+        ),
+        None => println!("  - Samples: unitless intensity"),
+    }
+    if let Some(doppler) = &scenario.doppler {
+        println!(
+            "  - Doppler: velocity and spectrum width, Nyquist ±{} m/s",
+            doppler.nyquist_velocity_ms
+        );
+    }
+    println!(
+        "  - Azimuth Resolution: {}° ({} radials)",
+        radar_config.azimuth_resolution_deg,
+        radar_config.azimuth_count()
+    );
+    println!(
+        "  - Range: {} km, {} m resolution ({} gates from {} m)",
+        radar_config.max_range_km(),
+        radar_config.gate_spacing_m,
+        radar_config.range_gate_count,
+        radar_config.first_gate_m
+    );
+    for (index, span) in sector_plan.spans().iter().enumerate() {
+        println!(
+            "  - Client {}: {:.0}-{:.0}° (overlap: {:.0}° leading, {:.0}° trailing)",
+            index + 1,
+            span.azimuth_start,
+            span.azimuth_end,
+            span.leading_overlap,
+            span.trailing_overlap
+        );
+    }
+    println!(
+        "  - Overlap Region: {} degrees",
+        radar_config.overlap_degrees
     );
-    println!("  - Client 1: 0-190° (overlap: 170-190°)");
-    println!("  - Client 2: 170-360° (overlap: 170-190°)");
-    println!("  - Overlap Region: {} degrees", OVERLAP_DEGREES);
 
     let ports = vec![8080, 8081];
-    let server = RadarTcpServer::new(ports, DATA_RATE_HZ);
+    let server = RadarTcpServer::new(ports, DATA_RATE_HZ)
+        .with_radar_config(radar_config) // Also sets the two-way sector plan
+        .with_scenario(scenario)
+        .with_group_policy(GroupPolicy::Synchronized) // enhanced_client merges matching pairs
+        .with_port_sector(8080, 0) // Client 1 always gets 0-190°
+        .with_port_sector(8081, 1); // Client 2 always gets 170-360°
 
     server.start().await
 }
@@ -0,0 +1,359 @@
+use crate::antenna::{BeamPattern, SPEED_OF_LIGHT_MS};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::f32::consts::PI;
+use std::fmt;
+
+const THERMAL_NOISE_DBM_HZ: f32 = -174.0; // kT at 290 K
+const WATER_DIELECTRIC_K2: f32 = 0.93; // |K|² of liquid water
+const MM6_PER_M6: f32 = 1.0e18; // Z in mm⁶/m³ per m⁶/m³
+
+// Weather and clutter echoes of 1.0 correspond to this reflectivity
+pub const REFERENCE_DBZ: f32 = 55.0;
+
+// What the numbers in a sweep field or radial mean. The echo-strength unit
+// is announced in the stream header, every sweep field carries its own.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleUnit {
+    #[default]
+    Intensity, // Unitless echo strength, roughly 0..1
+    Dbm,             // Received power
+    Dbz,             // Equivalent reflectivity factor
+    MetersPerSecond, // Doppler velocity and spectrum width
+}
+
+impl SampleUnit {
+    pub fn is_logarithmic(&self) -> bool {
+        matches!(self, SampleUnit::Dbm | SampleUnit::Dbz)
+    }
+
+    // Values spanning a typical picture, for colour scales
+    pub fn display_range(&self) -> (f32, f32) {
+        match self {
+            SampleUnit::Intensity => (0.0, 1.0),
+            SampleUnit::Dbm => (-115.0, -40.0),
+            SampleUnit::Dbz => (-10.0, 70.0),
+            SampleUnit::MetersPerSecond => (-30.0, 30.0),
+        }
+    }
+}
+
+impl fmt::Display for SampleUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SampleUnit::Intensity => write!(f, "intensity"),
+            SampleUnit::Dbm => write!(f, "dBm"),
+            SampleUnit::Dbz => write!(f, "dBZ"),
+            SampleUnit::MetersPerSecond => write!(f, "m/s"),
+        }
+    }
+}
+
+// Radar equation turning echoes into received power. Weather and clutter
+// fill the beam (volume targets, R⁻²), aircraft are point targets (R⁻⁴)
+// whose intensity is their cross section in m². Defaults describe an S-band
+// weather radar; beamwidth and pulse width come from the `BeamPattern`.
+//
+//   power = { output = "dbz", transmit_power_kw = 250.0, wavelength_cm = 10.0 }
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PowerModel {
+    pub output: SampleUnit, // `dbm` or `dbz`
+    pub transmit_power_kw: f32,
+    pub antenna_gain_db: f32,
+    pub wavelength_cm: f32,
+    pub attenuation_db_per_km: f32, // One-way atmospheric loss
+    pub system_loss_db: f32,        // Waveguide and receiver losses
+    pub noise_figure_db: f32,
+    pub bandwidth_mhz: f32, // Receiver bandwidth, sets the thermal noise floor
+}
+
+impl Default for PowerModel {
+    fn default() -> Self {
+        Self {
+            output: SampleUnit::Dbz,
+            transmit_power_kw: 250.0,
+            antenna_gain_db: 45.0,
+            wavelength_cm: 10.0,
+            attenuation_db_per_km: 0.008,
+            system_loss_db: 2.0,
+            noise_figure_db: 3.0,
+            bandwidth_mhz: 1.0,
+        }
+    }
+}
+
+impl PowerModel {
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if !self.output.is_logarithmic() {
+            return Err("Power model output must be dbm or dbz".into());
+        }
+        let positive = [
+            ("transmit power", self.transmit_power_kw),
+            ("wavelength", self.wavelength_cm),
+            ("bandwidth", self.bandwidth_mhz),
+        ];
+        for (name, value) in positive {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(format!("Invalid {} {}", name, value).into());
+            }
+        }
+        let finite = [
+            ("antenna gain", self.antenna_gain_db),
+            ("attenuation", self.attenuation_db_per_km),
+            ("system loss", self.system_loss_db),
+            ("noise figure", self.noise_figure_db),
+        ];
+        for (name, value) in finite {
+            if !value.is_finite() {
+                return Err(format!("Invalid {} {}", name, value).into());
+            }
+        }
+        if self.attenuation_db_per_km < 0.0 {
+            return Err("Atmospheric attenuation cannot be negative".into());
+        }
+        Ok(())
+    }
+
+    // Thermal noise power kTBF (W)
+    pub fn noise_floor_w(&self) -> f32 {
+        let dbm = THERMAL_NOISE_DBM_HZ
+            + 10.0 * (self.bandwidth_mhz * 1.0e6).log10()
+            + self.noise_figure_db;
+        dbm_to_watts(dbm)
+    }
+
+    // Power (W) from a volume target of reflectivity `z_mm6` (mm⁶/m³) at
+    // `range_m`, before atmospheric loss: Probert-Jones equation
+    pub fn volume_power_w(&self, beam: &BeamPattern, z_mm6: f32, range_m: f32) -> f32 {
+        self.radar_constant(beam) * z_mm6 / MM6_PER_M6 / (range_m * range_m)
+    }
+
+    // Power (W) from a point target of cross section `rcs_m2` at `range_m`,
+    // before atmospheric loss
+    pub fn point_power_w(&self, rcs_m2: f32, range_m: f32) -> f32 {
+        let wavelength_m = self.wavelength_cm / 100.0;
+        self.transmit_power_w() * self.gain().powi(2) * wavelength_m.powi(2) * rcs_m2
+            / ((4.0 * PI).powi(3) * range_m.powi(4))
+            / self.system_loss()
+    }
+
+    // Two-way atmospheric transmission over `range_m`, between 0 and 1
+    pub fn transmission(&self, range_m: f32) -> f32 {
+        10f32.powf(-2.0 * self.attenuation_db_per_km * range_m / 1000.0 / 10.0)
+    }
+
+    // Received power (W) as a sample in `output` units. dBZ is the
+    // reflectivity a volume target would need to return this power.
+    pub fn to_output(&self, beam: &BeamPattern, power_w: f32, range_m: f32) -> f32 {
+        match self.output {
+            SampleUnit::Dbz => {
+                let z_m6 = power_w * range_m * range_m
+                    / (self.radar_constant(beam) * self.transmission(range_m));
+                10.0 * (z_m6 * MM6_PER_M6).log10()
+            }
+            _ => 10.0 * (power_w * 1000.0).log10(), // dBm
+        }
+    }
+
+    // Everything in the weather radar equation except Z and R:
+    // π³ c Pt G² θ² τ |K|² / (1024 ln2 λ² L)
+    fn radar_constant(&self, beam: &BeamPattern) -> f32 {
+        let wavelength_m = self.wavelength_cm / 100.0;
+        let beamwidth_rad = beam.beamwidth_deg.to_radians();
+        let pulse_s = beam.pulse_width_us * 1.0e-6;
+        PI.powi(3)
+            * SPEED_OF_LIGHT_MS
+            * self.transmit_power_w()
+            * self.gain().powi(2)
+            * beamwidth_rad.powi(2)
+            * pulse_s
+            * WATER_DIELECTRIC_K2
+            / (1024.0 * std::f32::consts::LN_2 * wavelength_m.powi(2) * self.system_loss())
+    }
+
+    fn transmit_power_w(&self) -> f32 {
+        self.transmit_power_kw * 1000.0
+    }
+
+    fn gain(&self) -> f32 {
+        10f32.powf(self.antenna_gain_db / 10.0)
+    }
+
+    fn system_loss(&self) -> f32 {
+        10f32.powf(self.system_loss_db / 10.0)
+    }
+}
+
+// Linear reflectivity (mm⁶/m³) of a weather or clutter echo
+pub fn echo_reflectivity_mm6(echo: f32) -> f32 {
+    echo * 10f32.powf(REFERENCE_DBZ / 10.0)
+}
+
+fn dbm_to_watts(dbm: f32) -> f32 {
+    10f32.powf(dbm / 10.0) / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db(ratio: f32) -> f32 {
+        10.0 * ratio.log10()
+    }
+
+    fn close(a: f32, b: f32, tolerance: f32) -> bool {
+        (a - b).abs() <= tolerance
+    }
+
+    #[test]
+    fn point_targets_fall_off_40_db_per_decade() {
+        let model = PowerModel::default();
+        let near = model.point_power_w(10.0, 10_000.0);
+        assert!(close(
+            db(model.point_power_w(10.0, 20_000.0) / near),
+            -12.04,
+            0.01
+        ));
+        assert!(close(
+            db(model.point_power_w(10.0, 100_000.0) / near),
+            -40.0,
+            0.01
+        ));
+
+        // Twice the cross section, twice the power
+        assert!(close(
+            db(model.point_power_w(20.0, 10_000.0) / near),
+            3.01,
+            0.01
+        ));
+    }
+
+    #[test]
+    fn volume_targets_fall_off_20_db_per_decade() {
+        let model = PowerModel::default();
+        let beam = BeamPattern::default();
+        let near = model.volume_power_w(&beam, 1000.0, 10_000.0);
+        assert!(close(
+            db(model.volume_power_w(&beam, 1000.0, 100_000.0) / near),
+            -20.0,
+            0.01
+        ));
+        assert!(close(
+            db(model.volume_power_w(&beam, 10_000.0, 10_000.0) / near),
+            10.0,
+            0.01
+        ));
+
+        // A wider beam and a longer pulse fill a bigger volume
+        let wide = BeamPattern {
+            beamwidth_deg: 2.0,
+            pulse_width_us: 2.0,
+            ..beam
+        };
+        assert!(close(
+            db(model.volume_power_w(&wide, 1000.0, 10_000.0) / near),
+            9.03,
+            0.01
+        ));
+    }
+
+    #[test]
+    fn attenuation_counts_both_ways() {
+        let model = PowerModel::default();
+        assert_eq!(model.transmission(0.0), 1.0);
+        // 0.008 dB/km out and back over 100 km
+        assert!(close(db(model.transmission(100_000.0)), -1.6, 1e-4));
+
+        let clear = PowerModel {
+            attenuation_db_per_km: 0.0,
+            ..PowerModel::default()
+        };
+        assert_eq!(clear.transmission(250_000.0), 1.0);
+    }
+
+    #[test]
+    fn dbz_output_recovers_the_reflectivity() {
+        let model = PowerModel::default();
+        let beam = BeamPattern::default();
+        for (dbz, range_m) in [(-5.0, 5_000.0), (20.0, 60_000.0), (55.0, 200_000.0)] {
+            let z_mm6 = 10f32.powf(dbz / 10.0);
+            let power_w = model.volume_power_w(&beam, z_mm6, range_m) * model.transmission(range_m);
+            let output = model.to_output(&beam, power_w, range_m);
+            assert!(close(output, dbz, 0.01), "{} dBZ read as {}", dbz, output);
+        }
+    }
+
+    #[test]
+    fn dbm_output_is_power_over_a_milliwatt() {
+        let model = PowerModel {
+            output: SampleUnit::Dbm,
+            ..PowerModel::default()
+        };
+        let beam = BeamPattern::default();
+        assert!(close(model.to_output(&beam, 1.0e-3, 50_000.0), 0.0, 1e-4));
+        assert!(close(
+            model.to_output(&beam, 1.0e-12, 50_000.0),
+            -90.0,
+            1e-3
+        ));
+    }
+
+    #[test]
+    fn noise_floor_is_ktbf() {
+        // -174 dBm/Hz + 60 dB for 1 MHz + 3 dB noise figure
+        let floor_dbm = db(PowerModel::default().noise_floor_w() * 1000.0);
+        assert!(close(floor_dbm, -111.0, 1e-3));
+    }
+
+    #[test]
+    fn full_echo_is_the_reference_reflectivity() {
+        assert!(close(db(echo_reflectivity_mm6(1.0)), REFERENCE_DBZ, 1e-4));
+        assert!(close(
+            db(echo_reflectivity_mm6(0.1)),
+            REFERENCE_DBZ - 10.0,
+            1e-4
+        ));
+        assert_eq!(echo_reflectivity_mm6(0.0), 0.0);
+    }
+
+    #[test]
+    fn invalid_models_are_rejected() {
+        assert!(PowerModel::default().validate().is_ok());
+
+        let invalid = [
+            PowerModel {
+                output: SampleUnit::Intensity,
+                ..PowerModel::default()
+            },
+            PowerModel {
+                wavelength_cm: 0.0,
+                ..PowerModel::default()
+            },
+            PowerModel {
+                bandwidth_mhz: f32::NAN,
+                ..PowerModel::default()
+            },
+            PowerModel {
+                antenna_gain_db: f32::INFINITY,
+                ..PowerModel::default()
+            },
+            PowerModel {
+                attenuation_db_per_km: -0.01,
+                ..PowerModel::default()
+            },
+        ];
+        for model in invalid {
+            assert!(model.validate().is_err(), "{:?} accepted", model);
+        }
+    }
+
+    #[test]
+    fn units_describe_their_scale() {
+        assert!(!SampleUnit::Intensity.is_logarithmic());
+        assert!(SampleUnit::Dbm.is_logarithmic() && SampleUnit::Dbz.is_logarithmic());
+        assert_eq!(SampleUnit::Dbz.to_string(), "dBZ");
+        assert_eq!(SampleUnit::Dbm.display_range(), (-115.0, -40.0));
+    }
+}
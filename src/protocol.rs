@@ -0,0 +1,807 @@
+use crate::compression::COMPRESSION_FLAG_MASK;
+use bytes::{Buf, BufMut, BytesMut};
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use tokio::io::AsyncWriteExt;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio_util::codec::{Decoder, Encoder, FramedRead};
+
+// Wire types shared by the server and every client
+pub use crate::command::{Ack, Command, CommandError};
+pub use crate::compression::{Compression, CompressionReport};
+pub use crate::config::RadarConfig;
+pub use crate::delta::{DeltaField, DeltaSweep};
+pub use crate::doppler::DopplerConfig;
+pub use crate::field::SweepField;
+pub use crate::grid::PolarGrid;
+pub use crate::power::SampleUnit;
+pub use crate::quantize::{QuantizedField, QuantizedSweep, SampleFormat};
+pub use crate::radar_simulator::{RadarSweep, Radial};
+pub use crate::scan::ScanStrategy;
+pub use crate::subscription::{AzimuthWindow, StreamMode, Subscription};
+
+// Frame layout: [FrameHeader][bincode payload]
+//
+// Header (big-endian): magic u32 | version u16 | message type u8 | flags u8 | payload length u32
+// Flags bits 0-1 select the payload compression, the payload length is the compressed size
+pub const PROTOCOL_MAGIC: u32 = 0x5244_5253; // "RDRS"
+
+// Bumped with every change to the header or to a payload layout, so peers
+// built against another layout are rejected instead of misreading frames.
+// 1: framed messages with this header
+// 2: typed acks and command errors
+// 3: subscription parameters in acks
+// 4: dropped frame count in the server status
+// 5: sweep data in a contiguous polar grid
+// 6: quantized sample formats
+// 7: payload compression in flag bits 0-1
+// 8: delta sweeps and the resync command
+// 9: radials and the stream mode
+// 10: stream header with the radar geometry
+// 11: sample unit in the stream header
+// 12: Doppler moments in sweeps and the stream header
+// 13: sweeps as named fields with units
+// 14: volume scans: volume id, tilt and elevation in sweeps and radials
+pub const PROTOCOL_VERSION: u16 = 14;
+
+pub const HEADER_BYTES: usize = 12;
+pub const MAX_FRAME_BYTES: usize = 16 * 1024 * 1024; // Anything larger means a desynced stream
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    Sweep = 1,
+    Ack = 2,
+    Error = 3,
+    Heartbeat = 4,
+    Status = 5,
+    QuantizedSweep = 6,
+    DeltaSweep = 7,
+    Radial = 8,
+    StreamHeader = 9,
+}
+
+impl TryFrom<u8> for MessageType {
+    type Error = ProtocolError;
+
+    fn try_from(value: u8) -> Result<Self, ProtocolError> {
+        match value {
+            1 => Ok(MessageType::Sweep),
+            2 => Ok(MessageType::Ack),
+            3 => Ok(MessageType::Error),
+            4 => Ok(MessageType::Heartbeat),
+            5 => Ok(MessageType::Status),
+            6 => Ok(MessageType::QuantizedSweep),
+            7 => Ok(MessageType::DeltaSweep),
+            8 => Ok(MessageType::Radial),
+            9 => Ok(MessageType::StreamHeader),
+            other => Err(ProtocolError::UnknownMessageType(other)),
+        }
+    }
+}
+
+// Reasons a frame is rejected; surfaced to callers inside `io::Error`
+#[derive(Debug)]
+pub enum ProtocolError {
+    BadMagic(u32),
+    UnsupportedVersion(u16),
+    UnknownMessageType(u8),
+    FrameTooLarge(usize),
+    UnknownCompression(u8),
+    ReservedFlags(u8),
+    Decompression(String),
+    Payload(bincode::Error),
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::BadMagic(magic) => {
+                write!(f, "Bad frame magic {:#010x}, stream is out of sync", magic)
+            }
+            ProtocolError::UnsupportedVersion(version) => write!(
+                f,
+                "Unsupported protocol version {} (expected {})",
+                version, PROTOCOL_VERSION
+            ),
+            ProtocolError::UnknownMessageType(kind) => write!(f, "Unknown message type {}", kind),
+            ProtocolError::FrameTooLarge(len) => {
+                write!(f, "Frame of {} bytes exceeds the frame size limit", len)
+            }
+            ProtocolError::UnknownCompression(flags) => {
+                write!(f, "Unknown compression in frame flags {:#04x}", flags)
+            }
+            ProtocolError::ReservedFlags(flags) => {
+                write!(f, "Reserved bits set in frame flags {:#04x}", flags)
+            }
+            ProtocolError::Decompression(e) => write!(f, "Corrupt compressed payload: {}", e),
+            ProtocolError::Payload(e) => write!(f, "Invalid payload: {}", e),
+        }
+    }
+}
+
+impl Error for ProtocolError {}
+
+impl From<ProtocolError> for io::Error {
+    fn from(e: ProtocolError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    pub version: u16,
+    pub message_type: MessageType,
+    pub flags: u8, // Compression in bits 0-1, other bits reserved (zero)
+    pub payload_len: u32,
+}
+
+impl FrameHeader {
+    pub fn new(message_type: MessageType, payload_len: u32) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            message_type,
+            flags: 0,
+            payload_len,
+        }
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.flags = (self.flags & !COMPRESSION_FLAG_MASK) | compression as u8;
+        self
+    }
+
+    pub fn compression(&self) -> Compression {
+        Compression::from_flags(self.flags).unwrap_or_default()
+    }
+
+    pub fn write(&self, dst: &mut BytesMut) {
+        dst.put_u32(PROTOCOL_MAGIC);
+        dst.put_u16(self.version);
+        dst.put_u8(self.message_type as u8);
+        dst.put_u8(self.flags);
+        dst.put_u32(self.payload_len);
+    }
+
+    // Parse a header from at least `HEADER_BYTES` bytes
+    pub fn parse(mut src: &[u8]) -> Result<Self, ProtocolError> {
+        let magic = src.get_u32();
+        if magic != PROTOCOL_MAGIC {
+            return Err(ProtocolError::BadMagic(magic));
+        }
+
+        let version = src.get_u16();
+        if version != PROTOCOL_VERSION {
+            return Err(ProtocolError::UnsupportedVersion(version));
+        }
+
+        let message_type = MessageType::try_from(src.get_u8())?;
+        let flags = src.get_u8();
+        if flags & !COMPRESSION_FLAG_MASK != 0 {
+            return Err(ProtocolError::ReservedFlags(flags));
+        }
+        if Compression::from_flags(flags).is_none() {
+            return Err(ProtocolError::UnknownCompression(flags));
+        }
+        let payload_len = src.get_u32();
+
+        Ok(Self {
+            version,
+            message_type,
+            flags,
+            payload_len,
+        })
+    }
+}
+
+// First frame on every connection: how the server's radar is set up
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StreamHeader {
+    pub radar: RadarConfig,
+    pub data_rate_hz: f64,              // Complete sweeps per second
+    pub rotation_rpm: f64,              // Antenna speed in radial mode
+    pub units: SampleUnit,              // Meaning of every sample value
+    pub doppler: Option<DopplerConfig>, // Set when sweeps carry velocity and spectrum width
+    pub scan: ScanStrategy,             // Tilts of every volume scan, one sweep each
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ServerStatus {
+    pub connected_clients: usize,
+    pub streaming_clients: usize,
+    pub last_sequence_id: u64,
+    pub dropped_frames: u64, // Frames the requesting client lost to a full send queue
+    pub compression: Vec<CompressionReport>, // Sweep encoding cost per codec in use
+}
+
+// Every message that can travel from the server to a client
+#[derive(Debug, Clone)]
+pub enum Message {
+    Sweep(RadarSweep),
+    Ack(Ack),
+    Error(CommandError),
+    Heartbeat(u64), // Server timestamp (microseconds)
+    Status(ServerStatus),
+    QuantizedSweep(QuantizedSweep), // Sweep with u8/u16 samples, see `SampleFormat`
+    DeltaSweep(DeltaSweep),         // Difference against an earlier sweep
+    Radial(Radial),                 // One antenna position, see `StreamMode::Radial`
+    StreamHeader(StreamHeader),
+}
+
+impl Message {
+    pub fn message_type(&self) -> MessageType {
+        match self {
+            Message::Sweep(_) => MessageType::Sweep,
+            Message::Ack(_) => MessageType::Ack,
+            Message::Error(_) => MessageType::Error,
+            Message::Heartbeat(_) => MessageType::Heartbeat,
+            Message::Status(_) => MessageType::Status,
+            Message::QuantizedSweep(_) => MessageType::QuantizedSweep,
+            Message::DeltaSweep(_) => MessageType::DeltaSweep,
+            Message::Radial(_) => MessageType::Radial,
+            Message::StreamHeader(_) => MessageType::StreamHeader,
+        }
+    }
+}
+
+// Write one complete frame (header + bincode payload) into `dst`
+pub fn encode_payload<T: Serialize>(
+    message_type: MessageType,
+    payload: &T,
+    dst: &mut BytesMut,
+) -> io::Result<()> {
+    let payload_len = bincode::serialized_size(payload).map_err(ProtocolError::Payload)? as usize;
+    if payload_len > MAX_FRAME_BYTES {
+        return Err(ProtocolError::FrameTooLarge(payload_len).into());
+    }
+
+    dst.reserve(HEADER_BYTES + payload_len);
+    FrameHeader::new(message_type, payload_len as u32).write(dst);
+    bincode::serialize_into(dst.writer(), payload).map_err(|e| ProtocolError::Payload(e).into())
+}
+
+// Like `encode_payload`, compressing the payload first. Returns the
+// uncompressed payload size.
+pub fn encode_compressed_payload<T: Serialize>(
+    message_type: MessageType,
+    payload: &T,
+    compression: Compression,
+    dst: &mut BytesMut,
+) -> io::Result<usize> {
+    if compression == Compression::None {
+        let start = dst.len();
+        encode_payload(message_type, payload, dst)?;
+        return Ok(dst.len() - start - HEADER_BYTES);
+    }
+
+    let raw = bincode::serialize(payload).map_err(ProtocolError::Payload)?;
+    if raw.len() > MAX_FRAME_BYTES {
+        return Err(ProtocolError::FrameTooLarge(raw.len()).into());
+    }
+    let compressed = compression.compress(&raw);
+    if compressed.len() > MAX_FRAME_BYTES {
+        return Err(ProtocolError::FrameTooLarge(compressed.len()).into());
+    }
+
+    dst.reserve(HEADER_BYTES + compressed.len());
+    FrameHeader::new(message_type, compressed.len() as u32)
+        .with_compression(compression)
+        .write(dst);
+    dst.put_slice(&compressed);
+    Ok(raw.len())
+}
+
+// Header-framed bincode codec for every server message
+#[derive(Debug, Clone)]
+pub struct RadarCodec {
+    max_frame_bytes: usize,
+}
+
+impl RadarCodec {
+    pub fn new() -> Self {
+        Self {
+            max_frame_bytes: MAX_FRAME_BYTES,
+        }
+    }
+
+    pub fn with_max_frame_bytes(max_frame_bytes: usize) -> Self {
+        Self { max_frame_bytes }
+    }
+}
+
+impl Default for RadarCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for RadarCodec {
+    type Item = Message;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Message>> {
+        if src.len() < HEADER_BYTES {
+            src.reserve(HEADER_BYTES - src.len());
+            return Ok(None);
+        }
+
+        // Validate the header before waiting on a payload it describes
+        let header = FrameHeader::parse(&src[..HEADER_BYTES])?;
+        let payload_len = header.payload_len as usize;
+        if payload_len > self.max_frame_bytes {
+            return Err(ProtocolError::FrameTooLarge(payload_len).into());
+        }
+
+        let frame_len = HEADER_BYTES + payload_len;
+        if src.len() < frame_len {
+            // Wait for the rest of the frame
+            src.reserve(frame_len - src.len());
+            return Ok(None);
+        }
+
+        src.advance(HEADER_BYTES);
+        let payload = src.split_to(payload_len);
+        let decompressed;
+        let payload: &[u8] = match header.compression() {
+            Compression::None => &payload,
+            compression => {
+                decompressed = compression
+                    .decompress(&payload, self.max_frame_bytes)
+                    .map_err(|e| ProtocolError::Decompression(e.to_string()))?;
+                &decompressed
+            }
+        };
+        let message = match header.message_type {
+            MessageType::Sweep => Message::Sweep(deserialize_payload(payload)?),
+            MessageType::Ack => Message::Ack(deserialize_payload(payload)?),
+            MessageType::Error => Message::Error(deserialize_payload(payload)?),
+            MessageType::Heartbeat => Message::Heartbeat(deserialize_payload(payload)?),
+            MessageType::Status => Message::Status(deserialize_payload(payload)?),
+            MessageType::QuantizedSweep => Message::QuantizedSweep(deserialize_payload(payload)?),
+            MessageType::DeltaSweep => Message::DeltaSweep(deserialize_payload(payload)?),
+            MessageType::Radial => Message::Radial(deserialize_payload(payload)?),
+            MessageType::StreamHeader => Message::StreamHeader(deserialize_payload(payload)?),
+        };
+
+        Ok(Some(message))
+    }
+}
+
+impl Encoder<&Message> for RadarCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, message: &Message, dst: &mut BytesMut) -> io::Result<()> {
+        let message_type = message.message_type();
+        match message {
+            Message::Sweep(sweep) => encode_payload(message_type, sweep, dst),
+            Message::Ack(ack) => encode_payload(message_type, ack, dst),
+            Message::Error(error) => encode_payload(message_type, error, dst),
+            Message::Heartbeat(timestamp) => encode_payload(message_type, timestamp, dst),
+            Message::Status(status) => encode_payload(message_type, status, dst),
+            Message::QuantizedSweep(sweep) => encode_payload(message_type, sweep, dst),
+            Message::DeltaSweep(sweep) => encode_payload(message_type, sweep, dst),
+            Message::Radial(radial) => encode_payload(message_type, radial, dst),
+            Message::StreamHeader(header) => encode_payload(message_type, header, dst),
+        }
+    }
+}
+
+fn deserialize_payload<T: DeserializeOwned>(payload: &[u8]) -> Result<T, ProtocolError> {
+    bincode::deserialize(payload).map_err(ProtocolError::Payload)
+}
+
+// Typed client handle: sends commands and yields decoded sweeps
+pub struct RadarClient {
+    port: u16,
+    reader: FramedRead<OwnedReadHalf, RadarCodec>,
+    writer: OwnedWriteHalf,
+    delta_references: HashMap<usize, RadarSweep>, // Last sweep of each tilt, kept while delta streaming
+    tracking_deltas: bool,
+    resync_pending: bool,                // RESYNC sent, waiting for a keyframe
+    stream_header: Option<StreamHeader>, // Radar setup, once the server's header arrived
+}
+
+impl RadarClient {
+    pub async fn connect(host: &str, port: u16) -> io::Result<Self> {
+        let stream = TcpStream::connect(format!("{}:{}", host, port)).await?;
+        let (reader, writer) = stream.into_split();
+
+        Ok(Self {
+            port,
+            reader: FramedRead::new(reader, RadarCodec::new()),
+            writer,
+            delta_references: HashMap::new(),
+            tracking_deltas: false,
+            resync_pending: false,
+            stream_header: None,
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn stream_header(&self) -> Option<&StreamHeader> {
+        self.stream_header.as_ref()
+    }
+
+    // Geometry of the sweeps on this connection, known after the first message
+    pub fn radar_config(&self) -> Option<&RadarConfig> {
+        self.stream_header.as_ref().map(|header| &header.radar)
+    }
+
+    // Meaning of the sample values, intensity until the header arrives
+    pub fn sample_unit(&self) -> SampleUnit {
+        self.stream_header
+            .as_ref()
+            .map_or(SampleUnit::Intensity, |header| header.units)
+    }
+
+    // Replies arrive later as `Message::Ack` / `Message::Error`
+    pub async fn send_command(&mut self, command: &Command) -> io::Result<()> {
+        if let Command::Subscribe(subscription) = command {
+            if let Some(interval) = subscription.delta_keyframe_interval {
+                self.tracking_deltas = interval > 0;
+            }
+        }
+
+        let line = format!("{}\n", command);
+        self.writer.write_all(line.as_bytes()).await?;
+        self.writer.flush().await
+    }
+
+    pub async fn start_streaming(&mut self) -> io::Result<()> {
+        self.send_command(&Command::Subscribe(Subscription::default()))
+            .await
+    }
+
+    pub async fn subscribe(&mut self, subscription: Subscription) -> io::Result<()> {
+        self.send_command(&Command::Subscribe(subscription)).await
+    }
+
+    // Sweeps after the ack arrive compressed; the codec decompresses them
+    pub async fn set_compression(&mut self, compression: Compression) -> io::Result<()> {
+        self.send_command(&Command::Compress(compression)).await
+    }
+
+    pub async fn stop_streaming(&mut self) -> io::Result<()> {
+        self.send_command(&Command::Unsubscribe).await
+    }
+
+    // Next message of any type, `None` once the connection is closed.
+    // Cancel-safe: a partially received frame stays buffered.
+    pub async fn next_message(&mut self) -> io::Result<Option<Message>> {
+        let message = self.reader.next().await.transpose()?;
+        if let Some(Message::StreamHeader(header)) = &message {
+            self.stream_header = Some(header.clone());
+        }
+        Ok(message)
+    }
+
+    // Next radial of a `mode=radial` subscription, skipping everything else
+    pub async fn next_radial(&mut self) -> io::Result<Option<Radial>> {
+        while let Some(message) = self.next_message().await? {
+            match message {
+                Message::Radial(radial) => return Ok(Some(radial)),
+                Message::Error(error) => {
+                    eprintln!("Server error on port {}: {}", self.port, error);
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    // Next sweep, skipping acks, heartbeats and status messages.
+    // Quantized sweeps are decoded back to float samples and deltas are
+    // applied to the previous sweep of the same tilt; a delta that cannot be
+    // applied (a frame was lost) is skipped and a keyframe requested with RESYNC.
+    pub async fn next_sweep(&mut self) -> io::Result<Option<RadarSweep>> {
+        while let Some(message) = self.next_message().await? {
+            let sweep = match message {
+                Message::Sweep(sweep) => sweep,
+                Message::QuantizedSweep(sweep) => sweep
+                    .decode()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?,
+                Message::DeltaSweep(delta) => {
+                    self.tracking_deltas = true;
+                    let applied = match self.delta_references.get(&delta.tilt) {
+                        Some(reference) => delta.apply(reference).map_err(|e| e.to_string()),
+                        None => Err("no base frame".to_string()),
+                    };
+                    match applied {
+                        Ok(sweep) => {
+                            self.delta_references.insert(sweep.tilt, sweep.clone());
+                            return Ok(Some(sweep));
+                        }
+                        Err(e) => {
+                            if !self.resync_pending {
+                                eprintln!(
+                                    "Lost delta base on port {} ({}), requesting keyframe",
+                                    self.port, e
+                                );
+                                self.resync_pending = true;
+                                self.send_command(&Command::Resync).await?;
+                            }
+                            continue;
+                        }
+                    }
+                }
+                Message::Error(error) => {
+                    eprintln!("Server error on port {}: {}", self.port, error);
+                    continue;
+                }
+                _ => continue,
+            };
+
+            // Keyframe: the base for the deltas that follow
+            self.resync_pending = false;
+            if self.tracking_deltas {
+                self.delta_references.insert(sweep.tilt, sweep.clone());
+            }
+            return Ok(Some(sweep));
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn small_sweep() -> RadarSweep {
+        let values = (0..12).map(|i| i as f32 * 0.5).collect();
+        RadarSweep {
+            timestamp: 1_000,
+            sequence_id: 7,
+            volume_id: 3,
+            tilt: 1,
+            elevation_deg: 1.5,
+            azimuth_start: 10.0,
+            azimuth_end: 13.0,
+            range_bins: vec![0.0, 0.25, 0.5, 0.75],
+            fields: vec![SweepField::new(
+                "intensity",
+                SampleUnit::Intensity,
+                PolarGrid::from_values(3, 4, values).unwrap(),
+            )],
+            client_id: 2,
+        }
+    }
+
+    fn encode(message: &Message) -> BytesMut {
+        let mut frame = BytesMut::new();
+        RadarCodec::new().encode(message, &mut frame).unwrap();
+        frame
+    }
+
+    fn header_bytes(version: u16, message_type: u8, flags: u8, payload_len: u32) -> BytesMut {
+        let mut header = BytesMut::new();
+        header.put_u32(PROTOCOL_MAGIC);
+        header.put_u16(version);
+        header.put_u8(message_type);
+        header.put_u8(flags);
+        header.put_u32(payload_len);
+        header
+    }
+
+    fn decode_error(mut src: BytesMut) -> String {
+        RadarCodec::new().decode(&mut src).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn header_round_trips() {
+        let header =
+            FrameHeader::new(MessageType::DeltaSweep, 1234).with_compression(Compression::Lz4);
+        let mut bytes = BytesMut::new();
+        header.write(&mut bytes);
+
+        assert_eq!(bytes.len(), HEADER_BYTES);
+        assert_eq!(FrameHeader::parse(&bytes).unwrap(), header);
+        assert_eq!(header.compression(), Compression::Lz4);
+    }
+
+    #[test]
+    fn sweep_round_trips() {
+        let sweep = small_sweep();
+        let mut frame = encode(&Message::Sweep(sweep.clone()));
+
+        let Some(Message::Sweep(decoded)) = RadarCodec::new().decode(&mut frame).unwrap() else {
+            panic!("expected a sweep");
+        };
+        assert_eq!(decoded.sequence_id, sweep.sequence_id);
+        assert_eq!(decoded.volume_id, sweep.volume_id);
+        assert_eq!(decoded.tilt, sweep.tilt);
+        assert_eq!(decoded.range_bins, sweep.range_bins);
+        assert_eq!(decoded.fields, sweep.fields);
+        assert!(frame.is_empty());
+    }
+
+    #[test]
+    fn compressed_sweep_round_trips() {
+        let sweep = small_sweep();
+        for compression in Compression::ALL {
+            let mut frame = BytesMut::new();
+            encode_compressed_payload(MessageType::Sweep, &sweep, compression, &mut frame).unwrap();
+            assert_eq!(
+                FrameHeader::parse(&frame).unwrap().compression(),
+                compression
+            );
+
+            let Some(Message::Sweep(decoded)) = RadarCodec::new().decode(&mut frame).unwrap()
+            else {
+                panic!("expected a sweep");
+            };
+            assert_eq!(decoded.fields, sweep.fields);
+        }
+    }
+
+    #[test]
+    fn split_frames_wait_for_every_byte() {
+        let mut stream = encode(&Message::Heartbeat(42));
+        stream.extend_from_slice(&encode(&Message::Ack(Ack::Pong(9))));
+
+        // Feed one byte at a time: nothing decodes until a frame is complete
+        let mut codec = RadarCodec::new();
+        let mut src = BytesMut::new();
+        let mut messages = Vec::new();
+        for &byte in stream.iter() {
+            src.put_u8(byte);
+            if let Some(message) = codec.decode(&mut src).unwrap() {
+                messages.push(message);
+            }
+        }
+
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(messages[0], Message::Heartbeat(42)));
+        assert!(matches!(messages[1], Message::Ack(Ack::Pong(9))));
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn partial_header_is_not_an_error() {
+        let frame = encode(&Message::Heartbeat(1));
+        let mut src = BytesMut::from(&frame[..HEADER_BYTES - 1]);
+        assert!(RadarCodec::new().decode(&mut src).unwrap().is_none());
+        assert_eq!(src.len(), HEADER_BYTES - 1);
+    }
+
+    #[test]
+    fn bad_magic_is_rejected() {
+        let mut frame = encode(&Message::Heartbeat(1));
+        frame[0] ^= 0xff;
+        assert!(decode_error(frame).contains("Bad frame magic"));
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let header = header_bytes(PROTOCOL_VERSION + 1, MessageType::Heartbeat as u8, 0, 8);
+        assert!(matches!(
+            FrameHeader::parse(&header),
+            Err(ProtocolError::UnsupportedVersion(version)) if version == PROTOCOL_VERSION + 1
+        ));
+        assert!(decode_error(header).contains("Unsupported protocol version"));
+    }
+
+    #[test]
+    fn previous_version_is_rejected() {
+        let header = header_bytes(PROTOCOL_VERSION - 1, MessageType::Heartbeat as u8, 0, 8);
+        assert!(matches!(
+            FrameHeader::parse(&header),
+            Err(ProtocolError::UnsupportedVersion(version)) if version == PROTOCOL_VERSION - 1
+        ));
+    }
+
+    #[test]
+    fn sweep_from_the_previous_layout_is_rejected() {
+        // A current sweep frame stamped with the version before the field layout
+        let mut frame = encode(&Message::Sweep(small_sweep()));
+        frame[4..6].copy_from_slice(&(PROTOCOL_VERSION - 1).to_be_bytes());
+
+        let err = RadarCodec::new().decode(&mut frame).unwrap_err();
+        let cause = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<ProtocolError>());
+        assert!(matches!(
+            cause,
+            Some(ProtocolError::UnsupportedVersion(version)) if *version == PROTOCOL_VERSION - 1
+        ));
+    }
+
+    #[test]
+    fn unknown_message_type_is_rejected() {
+        let header = header_bytes(PROTOCOL_VERSION, 200, 0, 8);
+        assert!(matches!(
+            FrameHeader::parse(&header),
+            Err(ProtocolError::UnknownMessageType(200))
+        ));
+    }
+
+    #[test]
+    fn oversized_frame_is_rejected_before_its_payload_arrives() {
+        let header = header_bytes(PROTOCOL_VERSION, MessageType::Sweep as u8, 0, 1025);
+        let mut src = header;
+        let error = RadarCodec::with_max_frame_bytes(1024)
+            .decode(&mut src)
+            .unwrap_err();
+        assert!(error.to_string().contains("exceeds the frame size limit"));
+
+        let header = header_bytes(
+            PROTOCOL_VERSION,
+            MessageType::Sweep as u8,
+            0,
+            MAX_FRAME_BYTES as u32 + 1,
+        );
+        assert!(decode_error(header).contains("exceeds the frame size limit"));
+    }
+
+    #[test]
+    fn oversized_payload_is_not_encoded() {
+        let payload = vec![0u8; MAX_FRAME_BYTES + 1];
+        let mut frame = BytesMut::new();
+        assert!(encode_payload(MessageType::Sweep, &payload, &mut frame).is_err());
+        assert!(frame.is_empty());
+    }
+
+    #[tokio::test]
+    async fn client_reads_a_sweep_split_across_writes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let frame = encode(&Message::Sweep(small_sweep()));
+            let (head, tail) = frame.split_at(HEADER_BYTES + 5);
+            socket.write_all(head).await.unwrap();
+            socket.flush().await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            socket.write_all(tail).await.unwrap();
+        });
+
+        let mut client = RadarClient::connect("127.0.0.1", port).await.unwrap();
+        let sweep = client.next_sweep().await.unwrap().unwrap();
+        assert_eq!(sweep.sequence_id, small_sweep().sequence_id);
+        assert_eq!(sweep.fields, small_sweep().fields);
+
+        server.await.unwrap();
+        assert!(client.next_sweep().await.unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_compression_bits_are_rejected() {
+        let header = header_bytes(PROTOCOL_VERSION, MessageType::Sweep as u8, 0b11, 8);
+        assert!(matches!(
+            FrameHeader::parse(&header),
+            Err(ProtocolError::UnknownCompression(0b11))
+        ));
+        assert!(decode_error(header).contains("Unknown compression"));
+    }
+
+    #[test]
+    fn reserved_flag_bits_are_rejected() {
+        for flags in [0b0000_0100, 0b1000_0000, 0b1000_0001] {
+            let header = header_bytes(PROTOCOL_VERSION, MessageType::Sweep as u8, flags, 8);
+            assert!(matches!(
+                FrameHeader::parse(&header),
+                Err(ProtocolError::ReservedFlags(bits)) if bits == flags
+            ));
+        }
+    }
+
+    #[test]
+    fn corrupt_compressed_payload_is_rejected() {
+        let mut frame = header_bytes(
+            PROTOCOL_VERSION,
+            MessageType::Sweep as u8,
+            Compression::Lz4 as u8,
+            4,
+        );
+        frame.put_slice(&[0xff, 0xff, 0xff, 0xff]);
+        assert!(RadarCodec::new().decode(&mut frame).is_err());
+    }
+}
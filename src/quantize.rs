@@ -0,0 +1,555 @@
+use crate::field::{is_missing, SweepField, MISSING};
+use crate::grid::PolarGrid;
+use crate::power::SampleUnit;
+use crate::radar_simulator::RadarSweep;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+// NEXRAD-style reflectivity byte: code = (dBZ + 32) * 2 + 2
+const DBZ_BELOW_THRESHOLD: u8 = 0; // No echo (sample <= 0)
+const DBZ_MISSING: u8 = 1; // Missing sample, as NEXRAD's range-folded code
+const DBZ_FIRST_CODE: u8 = 2; // -32 dBZ
+const DBZ_STEP: f32 = 0.5; // dBZ per code
+const DBZ_MIN: f32 = -32.0;
+
+// How samples are carried on the wire, chosen per client with
+// `SUBSCRIBE format=<f32|u8|u16|dbz>`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SampleFormat {
+    #[default]
+    Float32, // Raw f32 samples (4 bytes each)
+    Linear8,  // u8 with per-sweep scale/offset
+    Linear16, // u16 with per-sweep scale/offset
+    Dbz8,     // u8 in 0.5 dBZ steps from -32 dBZ, see `QuantizedField::encode`
+}
+
+impl fmt::Display for SampleFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SampleFormat::Float32 => write!(f, "f32"),
+            SampleFormat::Linear8 => write!(f, "u8"),
+            SampleFormat::Linear16 => write!(f, "u16"),
+            SampleFormat::Dbz8 => write!(f, "dbz"),
+        }
+    }
+}
+
+impl FromStr for SampleFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "f32" | "float" => Ok(SampleFormat::Float32),
+            "u8" => Ok(SampleFormat::Linear8),
+            "u16" => Ok(SampleFormat::Linear16),
+            "dbz" => Ok(SampleFormat::Dbz8),
+            _ => Err(format!("Unknown sample format '{}'", value)),
+        }
+    }
+}
+
+// Maps codes back to samples: `sample = offset + (code - 1) * scale` for
+// linear formats, the dBZ table for `Dbz` (to linear Z) and `DbzValue` (to
+// dBZ). Code 0 (linear) or 1 (dBZ) marks a missing sample.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum SampleEncoding {
+    Linear { scale: f32, offset: f32 },
+    Dbz,
+    DbzValue,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum SampleCodes {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+}
+
+impl SampleCodes {
+    pub fn len(&self) -> usize {
+        match self {
+            SampleCodes::U8(codes) => codes.len(),
+            SampleCodes::U16(codes) => codes.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Quantized counterpart of `PolarGrid`, same row-major layout
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QuantizedGrid {
+    pub azimuth_count: usize,
+    pub range_count: usize,
+    pub codes: SampleCodes,
+}
+
+// Quantized counterpart of `RadarSweep`, one `QuantizedField` per field
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QuantizedSweep {
+    pub timestamp: u64,
+    pub sequence_id: u64,
+    pub volume_id: u64,
+    pub tilt: usize,
+    pub elevation_deg: f32,
+    pub azimuth_start: f32,
+    pub azimuth_end: f32,
+    pub range_bins: Vec<f32>,
+    pub fields: Vec<QuantizedField>,
+    pub client_id: usize,
+}
+
+// Quantized counterpart of `SweepField`. One encoding covers both the data
+// and the overlap region, so merged overlaps decode consistently.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QuantizedField {
+    pub name: String,
+    pub units: SampleUnit,
+    pub encoding: SampleEncoding,
+    pub data: QuantizedGrid,
+    pub overlap_region: QuantizedGrid,
+}
+
+impl QuantizedField {
+    // `Dbz8` reads intensity samples as linear Z and dBZ samples as they are;
+    // other units are not a reflectivity, so they fall back to `Linear8`.
+    // `None` for `SampleFormat::Float32`.
+    pub fn encode(field: &SweepField, format: SampleFormat) -> Option<Self> {
+        let (format, encoding) = match (format, field.units) {
+            (SampleFormat::Float32, _) => return None,
+            (SampleFormat::Linear16, _) => (format, linear_encoding(field, u16::MAX as f32)),
+            (SampleFormat::Dbz8, SampleUnit::Intensity) => (format, SampleEncoding::Dbz),
+            (SampleFormat::Dbz8, SampleUnit::Dbz) => (format, SampleEncoding::DbzValue),
+            (SampleFormat::Linear8 | SampleFormat::Dbz8, _) => (
+                SampleFormat::Linear8,
+                linear_encoding(field, u8::MAX as f32),
+            ),
+        };
+
+        Some(Self {
+            name: field.name.clone(),
+            units: field.units,
+            encoding,
+            data: quantize_grid(&field.data, format, encoding),
+            overlap_region: quantize_grid(&field.overlap_region, format, encoding),
+        })
+    }
+
+    pub fn decode(&self) -> Result<SweepField, Box<dyn Error>> {
+        Ok(SweepField {
+            name: self.name.clone(),
+            units: self.units,
+            data: dequantize_grid(&self.data, self.encoding)?,
+            overlap_region: dequantize_grid(&self.overlap_region, self.encoding)?,
+        })
+    }
+}
+
+impl QuantizedSweep {
+    // `None` for `SampleFormat::Float32`, which is sent as a plain `RadarSweep`
+    pub fn encode(sweep: &RadarSweep, format: SampleFormat) -> Option<Self> {
+        let fields = sweep
+            .fields
+            .iter()
+            .map(|field| QuantizedField::encode(field, format))
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            timestamp: sweep.timestamp,
+            sequence_id: sweep.sequence_id,
+            volume_id: sweep.volume_id,
+            tilt: sweep.tilt,
+            elevation_deg: sweep.elevation_deg,
+            azimuth_start: sweep.azimuth_start,
+            azimuth_end: sweep.azimuth_end,
+            range_bins: sweep.range_bins.clone(),
+            fields,
+            client_id: sweep.client_id,
+        })
+    }
+
+    // Reconstruct float samples for display and merging
+    pub fn decode(&self) -> Result<RadarSweep, Box<dyn Error>> {
+        Ok(RadarSweep {
+            timestamp: self.timestamp,
+            sequence_id: self.sequence_id,
+            volume_id: self.volume_id,
+            tilt: self.tilt,
+            elevation_deg: self.elevation_deg,
+            azimuth_start: self.azimuth_start,
+            azimuth_end: self.azimuth_end,
+            range_bins: self.range_bins.clone(),
+            fields: self
+                .fields
+                .iter()
+                .map(QuantizedField::decode)
+                .collect::<Result<_, _>>()?,
+            client_id: self.client_id,
+        })
+    }
+}
+
+// Spread the value range of a field and its overlap region over every code
+// but the missing one
+fn linear_encoding(field: &SweepField, max_code: f32) -> SampleEncoding {
+    let (min, max) = field
+        .data
+        .values()
+        .iter()
+        .chain(field.overlap_region.values())
+        .filter(|value| value.is_finite())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &value| {
+            (min.min(value), max.max(value))
+        });
+
+    if min > max {
+        return SampleEncoding::Linear {
+            scale: 0.0,
+            offset: 0.0,
+        }; // Nothing finite to encode
+    }
+    SampleEncoding::Linear {
+        scale: (max - min) / (max_code - 1.0),
+        offset: min,
+    }
+}
+
+fn quantize_grid(
+    grid: &PolarGrid,
+    format: SampleFormat,
+    encoding: SampleEncoding,
+) -> QuantizedGrid {
+    let values = grid.values().iter();
+    let codes = match (format, encoding) {
+        (_, SampleEncoding::Dbz) => SampleCodes::U8(values.map(|&value| dbz_code(value)).collect()),
+        (_, SampleEncoding::DbzValue) => {
+            SampleCodes::U8(values.map(|&dbz| dbz_value_code(dbz)).collect())
+        }
+        (SampleFormat::Linear16, _) => SampleCodes::U16(
+            values
+                .map(|&value| linear_code(value, encoding, u16::MAX as f32) as u16)
+                .collect(),
+        ),
+        _ => SampleCodes::U8(
+            values
+                .map(|&value| linear_code(value, encoding, u8::MAX as f32) as u8)
+                .collect(),
+        ),
+    };
+
+    QuantizedGrid {
+        azimuth_count: grid.azimuth_count(),
+        range_count: grid.range_count(),
+        codes,
+    }
+}
+
+fn dequantize_grid(
+    grid: &QuantizedGrid,
+    encoding: SampleEncoding,
+) -> Result<PolarGrid, Box<dyn Error>> {
+    let values = match (&grid.codes, encoding) {
+        (SampleCodes::U8(codes), SampleEncoding::Dbz) => {
+            codes.iter().map(|&code| dbz_value(code)).collect()
+        }
+        (SampleCodes::U8(codes), SampleEncoding::DbzValue) => {
+            codes.iter().map(|&code| code_dbz(code)).collect()
+        }
+        (SampleCodes::U8(codes), SampleEncoding::Linear { scale, offset }) => codes
+            .iter()
+            .map(|&code| linear_value(code as u32, scale, offset))
+            .collect(),
+        (SampleCodes::U16(codes), SampleEncoding::Linear { scale, offset }) => codes
+            .iter()
+            .map(|&code| linear_value(code as u32, scale, offset))
+            .collect(),
+        (SampleCodes::U16(_), SampleEncoding::Dbz | SampleEncoding::DbzValue) => {
+            return Err("dBZ samples must be 8-bit codes".into())
+        }
+    };
+
+    PolarGrid::from_values(grid.azimuth_count, grid.range_count, values)
+}
+
+// Code 0 is kept for missing samples
+fn linear_code(value: f32, encoding: SampleEncoding, max_code: f32) -> f32 {
+    if is_missing(value) {
+        return 0.0;
+    }
+    match encoding {
+        SampleEncoding::Linear { scale, offset } if scale > 0.0 => {
+            ((value - offset) / scale)
+                .round()
+                .clamp(0.0, max_code - 1.0)
+                + 1.0
+        }
+        _ => 1.0,
+    }
+}
+
+fn linear_value(code: u32, scale: f32, offset: f32) -> f32 {
+    if code == 0 {
+        return MISSING;
+    }
+    offset + (code - 1) as f32 * scale
+}
+
+// Linear reflectivity factor Z -> dBZ code
+fn dbz_code(value: f32) -> u8 {
+    if is_missing(value) {
+        return DBZ_MISSING;
+    }
+    if value <= 0.0 {
+        return DBZ_BELOW_THRESHOLD;
+    }
+    dbz_value_code(10.0 * value.log10())
+}
+
+// dBZ -> dBZ code, clamped to the table
+fn dbz_value_code(dbz: f32) -> u8 {
+    if is_missing(dbz) {
+        return DBZ_MISSING;
+    }
+    let code = ((dbz - DBZ_MIN) / DBZ_STEP).round() + DBZ_FIRST_CODE as f32;
+    code.clamp(DBZ_FIRST_CODE as f32, u8::MAX as f32) as u8
+}
+
+// dBZ code -> linear reflectivity factor Z
+fn dbz_value(code: u8) -> f32 {
+    if code == DBZ_MISSING {
+        return MISSING;
+    }
+    if code < DBZ_FIRST_CODE {
+        return 0.0;
+    }
+    10f32.powf(code_dbz(code) / 10.0)
+}
+
+// dBZ code -> dBZ, the bottom of the table for "no echo"
+fn code_dbz(code: u8) -> f32 {
+    if code == DBZ_MISSING {
+        return MISSING;
+    }
+    DBZ_MIN + code.saturating_sub(DBZ_FIRST_CODE) as f32 * DBZ_STEP
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn field(units: SampleUnit, data: Vec<f32>, overlap: Vec<f32>) -> SweepField {
+        SweepField {
+            name: "test".to_string(),
+            units,
+            data: PolarGrid::from_values(data.len() / 4, 4, data).unwrap(),
+            overlap_region: PolarGrid::from_values(overlap.len() / 4, 4, overlap).unwrap(),
+        }
+    }
+
+    // Samples in `lo..hi` with every fifth one missing
+    fn samples(count: usize, lo: f32, hi: f32, seed: u64) -> Vec<f32> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|i| {
+                if i % 5 == 3 {
+                    MISSING
+                } else {
+                    rng.gen_range(lo..hi)
+                }
+            })
+            .collect()
+    }
+
+    fn round_trip(field: &SweepField, format: SampleFormat) -> SweepField {
+        QuantizedField::encode(field, format)
+            .expect("quantized format")
+            .decode()
+            .unwrap()
+    }
+
+    // Every sample within `tolerance(original)`, missing exactly where it was
+    fn assert_close(original: &PolarGrid, decoded: &PolarGrid, tolerance: impl Fn(f32) -> f32) {
+        assert_eq!(original.azimuth_count(), decoded.azimuth_count());
+        assert_eq!(original.range_count(), decoded.range_count());
+        for (&before, &after) in original.values().iter().zip(decoded.values()) {
+            if is_missing(before) {
+                assert!(is_missing(after), "missing sample decoded as {}", after);
+            } else {
+                assert!(
+                    (after - before).abs() <= tolerance(before),
+                    "{} decoded as {}",
+                    before,
+                    after
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn linear_formats_stay_within_half_a_step() {
+        for (format, max_code) in [
+            (SampleFormat::Linear8, u8::MAX as f32),
+            (SampleFormat::Linear16, u16::MAX as f32),
+        ] {
+            let original = field(
+                SampleUnit::Dbm,
+                samples(400, -110.0, -20.0, 1),
+                samples(40, -120.0, -30.0, 2),
+            );
+            let decoded = round_trip(&original, format);
+
+            // The range covers data and overlap: -120..-20 over every code but 0
+            let half_step = 100.0 / (max_code - 1.0) / 2.0 + 1e-3;
+            assert_close(&original.data, &decoded.data, |_| half_step);
+            assert_close(&original.overlap_region, &decoded.overlap_region, |_| {
+                half_step
+            });
+        }
+    }
+
+    #[test]
+    fn linear_extremes_decode_exactly() {
+        let original = field(SampleUnit::Intensity, vec![0.0, 0.25, 0.5, 1.0], vec![]);
+        let decoded = round_trip(&original, SampleFormat::Linear8);
+        assert_eq!(decoded.data.values()[0], 0.0);
+        assert!((decoded.data.values()[3] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn constant_and_all_missing_fields_survive() {
+        let constant = field(SampleUnit::Intensity, vec![0.7; 8], vec![]);
+        let decoded = round_trip(&constant, SampleFormat::Linear8);
+        assert!(decoded.data.values().iter().all(|&value| value == 0.7));
+
+        let missing = field(SampleUnit::MetersPerSecond, vec![MISSING; 8], vec![]);
+        for format in [
+            SampleFormat::Linear8,
+            SampleFormat::Linear16,
+            SampleFormat::Dbz8,
+        ] {
+            let decoded = round_trip(&missing, format);
+            assert!(decoded.data.values().iter().all(|&value| is_missing(value)));
+        }
+    }
+
+    #[test]
+    fn dbz_values_stay_within_a_quarter_db() {
+        let original = field(
+            SampleUnit::Dbz,
+            samples(400, DBZ_MIN, 94.0, 3),
+            samples(20, -10.0, 60.0, 4),
+        );
+        let decoded = round_trip(&original, SampleFormat::Dbz8);
+        assert!(matches!(
+            QuantizedField::encode(&original, SampleFormat::Dbz8)
+                .unwrap()
+                .encoding,
+            SampleEncoding::DbzValue
+        ));
+        assert_close(&original.data, &decoded.data, |_| DBZ_STEP / 2.0 + 1e-4);
+        assert_close(&original.overlap_region, &decoded.overlap_region, |_| {
+            DBZ_STEP / 2.0 + 1e-4
+        });
+    }
+
+    #[test]
+    fn dbz_values_clamp_to_the_table() {
+        let original = field(SampleUnit::Dbz, vec![-60.0, 200.0, MISSING, 0.0], vec![]);
+        let decoded = round_trip(&original, SampleFormat::Dbz8).data;
+        let max_dbz = DBZ_MIN + (u8::MAX - DBZ_FIRST_CODE) as f32 * DBZ_STEP;
+        assert_eq!(decoded.values()[0], DBZ_MIN);
+        assert_eq!(decoded.values()[1], max_dbz);
+        assert!(is_missing(decoded.values()[2]));
+        assert_eq!(decoded.values()[3], 0.0);
+    }
+
+    #[test]
+    fn intensity_as_dbz_keeps_relative_error_small() {
+        let original = field(
+            SampleUnit::Intensity,
+            samples(200, 0.001, 1000.0, 5),
+            vec![],
+        );
+        let decoded = round_trip(&original, SampleFormat::Dbz8);
+
+        // A quarter dB either way, at most 6% of the linear value
+        let relative = 10f32.powf(DBZ_STEP / 2.0 / 10.0) - 1.0;
+        assert_close(&original.data, &decoded.data, |z| z * relative + 1e-6);
+    }
+
+    #[test]
+    fn intensity_without_echo_reads_as_zero() {
+        let original = field(SampleUnit::Intensity, vec![0.0, -1.0, MISSING, 1.0], vec![]);
+        let decoded = round_trip(&original, SampleFormat::Dbz8).data;
+        assert_eq!(decoded.values()[0], 0.0);
+        assert_eq!(decoded.values()[1], 0.0);
+        assert!(is_missing(decoded.values()[2]));
+        assert_eq!(decoded.values()[3], 1.0);
+    }
+
+    #[test]
+    fn dbz_format_falls_back_to_linear_for_other_units() {
+        let velocity = field(
+            SampleUnit::MetersPerSecond,
+            samples(40, -25.0, 25.0, 6),
+            vec![],
+        );
+        let quantized = QuantizedField::encode(&velocity, SampleFormat::Dbz8).unwrap();
+        assert!(matches!(quantized.encoding, SampleEncoding::Linear { .. }));
+        assert!(matches!(quantized.data.codes, SampleCodes::U8(_)));
+        assert_close(&velocity.data, &quantized.decode().unwrap().data, |_| {
+            50.0 / 254.0 / 2.0 + 1e-3
+        });
+    }
+
+    #[test]
+    fn float32_is_not_quantized() {
+        let original = field(SampleUnit::Intensity, vec![1.0; 4], vec![]);
+        assert!(QuantizedField::encode(&original, SampleFormat::Float32).is_none());
+    }
+
+    #[test]
+    fn sweeps_keep_their_metadata() {
+        let sweep = RadarSweep {
+            timestamp: 5,
+            sequence_id: 6,
+            volume_id: 7,
+            tilt: 2,
+            elevation_deg: 2.4,
+            azimuth_start: 170.0,
+            azimuth_end: 360.0,
+            range_bins: vec![0.0, 0.1, 0.2, 0.3],
+            fields: vec![field(SampleUnit::Dbz, samples(8, 0.0, 50.0, 7), vec![])],
+            client_id: 1,
+        };
+        let decoded = QuantizedSweep::encode(&sweep, SampleFormat::Linear16)
+            .unwrap()
+            .decode()
+            .unwrap();
+        assert_eq!(
+            (decoded.sequence_id, decoded.volume_id, decoded.tilt),
+            (6, 7, 2)
+        );
+        assert_eq!(decoded.elevation_deg, 2.4);
+        assert_eq!(decoded.range_bins, sweep.range_bins);
+        assert_eq!(decoded.field_names(), vec!["test"]);
+    }
+
+    #[test]
+    fn formats_parse_and_display() {
+        for format in [
+            SampleFormat::Float32,
+            SampleFormat::Linear8,
+            SampleFormat::Linear16,
+            SampleFormat::Dbz8,
+        ] {
+            assert_eq!(format.to_string().parse::<SampleFormat>(), Ok(format));
+        }
+        assert!("u32".parse::<SampleFormat>().is_err());
+    }
+}
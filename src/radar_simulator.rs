@@ -1,37 +1,137 @@
+use crate::antenna::BeamPattern;
+use crate::config::RadarConfig;
+use crate::doppler::{DopplerConfig, DopplerSpectrum, SPECTRUM_WIDTH, VELOCITY};
+use crate::field::{self, SweepField, MISSING};
+use crate::grid::PolarGrid;
+use crate::kinematics::Kinematics;
+use crate::power::{echo_reflectivity_mm6, PowerModel, SampleUnit};
+use crate::scan::{beam_height_km, ScanStrategy};
+use crate::scenario::{Lifetime, Scenario};
+use crate::sector::SectorPlan;
+use crate::weather::WeatherCell;
 use noise::{Fbm, NoiseFn, Perlin};
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-// Radar system parameters
+// Default radar system parameters, see `RadarConfig`
 pub const RANGE_BINS: usize = 500; // 50km range, 100m resolution
 pub const MAX_RANGE_KM: f32 = 50.0;
 pub const RANGE_RESOLUTION_M: f32 = 100.0;
 pub const OVERLAP_DEGREES: f32 = 20.0; // 20 degree overlap
+pub const DEFAULT_ROTATION_RPM: f64 = 12.0; // One rotation every 5 seconds
 
-// Enhanced radar data structure
+// Weather echoes end at 1.5 times a cell's half-axes
+const WEATHER_EDGE: f32 = 1.5;
+
+// Ground clutter patches, only visible inside the radar horizon
+const CLUTTER_SPREAD_DEGREES: f32 = 4.0;
+const CLUTTER_SPREAD_KM: f32 = 1.5;
+const CLUTTER_HORIZON_KM: f32 = 20.0;
+
+// Doppler spectrum width of each echo type: turbulence inside weather cells,
+// engine and propeller modulation on aircraft, swaying vegetation in clutter
+const WEATHER_SPECTRUM_WIDTH_MS: f32 = 2.0;
+const AIRCRAFT_SPECTRUM_WIDTH_MS: f32 = 1.0;
+const CLUTTER_SPECTRUM_WIDTH_MS: f32 = 0.3;
+
+// Velocity and spectrum width are missing where the echo is weaker than the
+// noise (0 dB SNR), as a real processor would censor them
+const DOPPLER_MIN_SNR: f32 = 1.0;
+
+// Enhanced radar data structure: one or more named fields over the same
+// azimuths and range gates. Simulated sweeps start with the echo strength
+// (see `field::INTENSITY`), followed by the Doppler moments when enabled.
+// Each sweep is one tilt of a volume scan, see `ScanStrategy`.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RadarSweep {
-    pub timestamp: u64,                // Microsecond timestamp
-    pub sequence_id: u64,              // Frame sequence number
-    pub azimuth_start: f32,            // Starting azimuth (degrees)
-    pub azimuth_end: f32,              // Ending azimuth (degrees)
-    pub range_bins: Vec<f32>,          // Range gate distances (km)
-    pub data: Vec<Vec<f32>>,           // [azimuth][range] intensity values
-    pub overlap_region: Vec<Vec<f32>>, // Overlap data for merging
-    pub client_id: usize,              // Which client this data is for
+    pub timestamp: u64,          // Microsecond timestamp
+    pub sequence_id: u64,        // Frame sequence number
+    pub volume_id: u64,          // Volume scan this sweep is part of
+    pub tilt: usize,             // Position in the scan strategy, 0 = lowest
+    pub elevation_deg: f32,      // Antenna elevation of this tilt
+    pub azimuth_start: f32,      // Starting azimuth (degrees)
+    pub azimuth_end: f32,        // Ending azimuth (degrees)
+    pub range_bins: Vec<f32>,    // Range gate distances (km)
+    pub fields: Vec<SweepField>, // Named samples, each with its overlap region
+    pub client_id: usize,        // Which client this data is for
+}
+
+impl RadarSweep {
+    pub fn field(&self, name: &str) -> Option<&SweepField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    // The first field, the echo strength for simulated sweeps
+    pub fn primary_field(&self) -> Option<&SweepField> {
+        self.fields.first()
+    }
+
+    pub fn field_names(&self) -> Vec<&str> {
+        self.fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect()
+    }
+
+    pub fn azimuth_count(&self) -> usize {
+        self.primary_field()
+            .map_or(0, |field| field.data.azimuth_count())
+    }
+
+    pub fn range_count(&self) -> usize {
+        self.range_bins.len()
+    }
+
+    pub fn overlap_azimuth_count(&self) -> usize {
+        self.primary_field()
+            .map_or(0, |field| field.overlap_region.azimuth_count())
+    }
+
+    // Every field with its grids replaced by `cut(field)`, which returns the
+    // new data and overlap region
+    pub fn map_fields(
+        &self,
+        cut: impl Fn(&SweepField) -> (PolarGrid, PolarGrid),
+    ) -> Vec<SweepField> {
+        self.fields
+            .iter()
+            .map(|field| {
+                let (data, overlap_region) = cut(field);
+                field.with_grids(data, overlap_region)
+            })
+            .collect()
+    }
+}
+
+// One beam position of the rotating antenna, streamed as the antenna turns
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Radial {
+    pub timestamp: u64,       // Microsecond timestamp
+    pub sequence_id: u64,     // Rotation number, shared by every radial of one sweep
+    pub azimuth: f32,         // Beam azimuth (degrees)
+    pub tilt: usize,          // Tilt of the latest complete sweep, which the antenna is on
+    pub elevation_deg: f32,   // Antenna elevation of that tilt
+    pub first_gate_km: f32,   // Range of the first sample
+    pub gate_spacing_km: f32, // Range between neighbouring samples
+    pub samples: Vec<f32>,    // Echo strength per range gate, first field only
 }
 
 // Simulated radar target
 #[derive(Debug, Clone)]
 pub struct RadarTarget {
-    pub azimuth: f32,   // Current azimuth position
-    pub range: f32,     // Range in km
-    pub intensity: f32, // Radar cross section
-    pub velocity: f32,  // Azimuth velocity (degrees/second)
+    pub azimuth: f32,              // Current azimuth, derived from `kinematics`
+    pub range: f32,                // Current range in km, derived from `kinematics`
+    pub intensity: f32,            // Radar cross section
+    pub velocity: f32,             // Azimuth drift around the radar (degrees/second)
+    pub kinematics: Kinematics,    // Position and motion over the ground
+    pub cell: Option<WeatherCell>, // Shape and life cycle of weather targets
     pub target_type: TargetType,
+    pub lifetime: Lifetime, // When the target is present
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
 pub enum TargetType {
     Aircraft,
     Weather,
@@ -43,68 +143,143 @@ pub struct RadarSimulator {
     pub current_time: u64,
     pub sequence_counter: u64,
     pub targets: Vec<RadarTarget>,
+    pub rotation_rpm: f64,
+    config: RadarConfig,
     noise_generator: Fbm<Perlin>,
+    noise_level: f32,
     weather_intensity: f32,
+    wind_kmh: [f32; 2],              // Advection of weather targets, [east, north]
+    power_model: Option<PowerModel>, // `None` keeps unitless intensities
+    beam: BeamPattern,
+    doppler: Option<DopplerConfig>, // `None` measures intensity only
+    scan: ScanStrategy,
+    next_tilt: usize,     // Tilt of the next complete sweep
+    antenna_tilt: usize,  // Tilt of the last complete sweep, where radials are sampled
+    volume_counter: u64,  // Volume scan of the last complete sweep
+    antenna_degrees: f64, // Total rotation since start
+    next_radial: u64,     // First radial the antenna has not passed yet
 }
 
 impl RadarSimulator {
     pub fn new() -> Self {
-        let mut targets = Vec::new();
-
-        // Only weather patterns - remove aircraft and ground clutter
-        targets.push(RadarTarget {
-            azimuth: 45.0,
-            range: 15.0,
-            intensity: 0.6,
-            velocity: 0.5, // Slow moving weather system
-            target_type: TargetType::Weather,
-        });
-
-        targets.push(RadarTarget {
-            azimuth: 120.0,
-            range: 30.0,
-            intensity: 0.8,
-            velocity: 0.2,
-            target_type: TargetType::Weather,
-        });
-
-        targets.push(RadarTarget {
-            azimuth: 200.0,
-            range: 25.0,
-            intensity: 0.7,
-            velocity: -0.3,
-            target_type: TargetType::Weather,
-        });
-
-        // Add a larger weather system spanning multiple ranges
-        targets.push(RadarTarget {
-            azimuth: 280.0,
-            range: 20.0,
-            intensity: 0.9,
-            velocity: 0.1,
-            target_type: TargetType::Weather,
-        });
+        Self::from_scenario(&Scenario::default())
+    }
 
+    // Simulator set up with a scenario's targets, weather and noise
+    pub fn from_scenario(scenario: &Scenario) -> Self {
         Self {
             current_time: 0,
             sequence_counter: 0,
-            targets,
-            noise_generator: Fbm::<Perlin>::new(42),
-            weather_intensity: 0.4, // Increase weather intensity
+            targets: scenario.radar_targets(),
+            rotation_rpm: DEFAULT_ROTATION_RPM,
+            config: RadarConfig::default(),
+            noise_generator: Fbm::<Perlin>::new(scenario.noise_seed),
+            noise_level: scenario.noise_level,
+            weather_intensity: scenario.weather_intensity,
+            wind_kmh: scenario.wind.velocity_kmh(),
+            power_model: scenario.power,
+            beam: scenario.antenna,
+            doppler: scenario.doppler,
+            scan: scenario.scan.clone(),
+            next_tilt: 0,
+            antenna_tilt: 0,
+            volume_counter: 0,
+            antenna_degrees: 0.0,
+            next_radial: 0,
         }
     }
 
+    pub fn with_rotation_rpm(mut self, rotation_rpm: f64) -> Self {
+        self.rotation_rpm = rotation_rpm;
+        self
+    }
+
+    // Geometry of every sweep and radial, checked with `RadarConfig::validate`
+    pub fn with_config(mut self, config: RadarConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &RadarConfig {
+        &self.config
+    }
+
+    // Output samples in dBm or dBZ, checked with `PowerModel::validate`
+    pub fn with_power_model(mut self, power_model: Option<PowerModel>) -> Self {
+        self.power_model = power_model;
+        self
+    }
+
+    // Antenna pattern and pulse, checked with `BeamPattern::validate`
+    pub fn with_beam_pattern(mut self, beam: BeamPattern) -> Self {
+        self.beam = beam;
+        self
+    }
+
+    // Velocity and spectrum width moments, checked with `DopplerConfig::validate`
+    pub fn with_doppler(mut self, doppler: Option<DopplerConfig>) -> Self {
+        self.doppler = doppler;
+        self
+    }
+
+    pub fn doppler(&self) -> Option<&DopplerConfig> {
+        self.doppler.as_ref()
+    }
+
+    // Tilts of every volume scan, checked with `ScanStrategy::validate`.
+    // The next sweep starts a new volume.
+    pub fn with_scan_strategy(mut self, scan: ScanStrategy) -> Self {
+        self.scan = scan;
+        self.next_tilt = 0;
+        self
+    }
+
+    pub fn scan_strategy(&self) -> &ScanStrategy {
+        &self.scan
+    }
+
+    // What the samples of every sweep and radial mean
+    pub fn sample_unit(&self) -> SampleUnit {
+        self.power_model
+            .map_or(SampleUnit::Intensity, |model| model.output)
+    }
+
+    // Name of the first field of every sweep, after its unit
+    pub fn echo_field_name(&self) -> &'static str {
+        match self.sample_unit() {
+            SampleUnit::Dbz => field::REFLECTIVITY,
+            SampleUnit::Dbm => field::POWER,
+            _ => field::INTENSITY,
+        }
+    }
+
+    // Simulation time in seconds, the clock of target lifetimes
+    pub fn time_s(&self) -> f32 {
+        self.current_time as f32 / 1_000_000.0
+    }
+
     pub fn update_targets(&mut self, dt: f32) {
+        let time_s = self.time_s();
         for target in &mut self.targets {
-            target.azimuth += target.velocity * dt;
-            target.azimuth = target.azimuth % 360.0;
-            if target.azimuth < 0.0 {
-                target.azimuth += 360.0;
+            if !target.lifetime.is_alive(time_s) {
+                continue; // Targets start moving when they appear
+            }
+            if target.velocity != 0.0 {
+                target.kinematics.rotate(target.velocity * dt);
+            }
+            target.kinematics.step(dt);
+            if target.target_type == TargetType::Weather {
+                // Carried along by the wind on top of its own motion
+                target.kinematics.x_km += self.wind_kmh[0] / 3600.0 * dt;
+                target.kinematics.y_km += self.wind_kmh[1] / 3600.0 * dt;
             }
+            target.range = target.kinematics.range_km();
+            target.azimuth = target.kinematics.azimuth_deg();
         }
     }
 
-    // Generate ONE complete 360° radar sweep (real-world approach)
+    // Generate ONE complete 360° radar sweep (real-world approach) at the
+    // next tilt of the volume scan
     pub fn generate_complete_sweep(&mut self) -> RadarSweep {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -112,115 +287,630 @@ impl RadarSimulator {
             .as_micros() as u64;
 
         self.sequence_counter += 1;
+        let tilt = self.next_tilt;
+        if tilt == 0 {
+            self.volume_counter += 1;
+        }
+        self.next_tilt = (tilt + 1) % self.scan.tilt_count().max(1);
+        self.antenna_tilt = tilt;
+        let elevation_deg = self.scan.elevation_deg(tilt).unwrap_or_default();
 
         // Complete 360° sweep - this is what ONE radar antenna produces
-        let azimuth_range = 360;
-        let mut data = vec![vec![0.0; RANGE_BINS]; azimuth_range];
-
-        // Generate range bins
-        let range_bins: Vec<f32> = (0..RANGE_BINS)
-            .map(|i| (i as f32) * RANGE_RESOLUTION_M / 1000.0)
-            .collect();
-
-        // Fill complete sweep with base noise level
-        for az_idx in 0..azimuth_range {
-            let azimuth = az_idx as f32;
-
-            for range_idx in 0..RANGE_BINS {
-                let range_km = range_bins[range_idx];
-
-                // Base noise level with range attenuation
-                let range_attenuation = 1.0 / (1.0 + range_km * 0.1);
-                let noise_value = self.noise_generator.get([
-                    azimuth as f64 * 0.1,
-                    range_km as f64 * 0.2,
-                    self.current_time as f64 * 0.001,
-                ]);
-                let base_intensity = (noise_value.abs() as f32) * 0.1 * range_attenuation;
-
-                data[az_idx][range_idx] = base_intensity;
-            }
+        let mut data = PolarGrid::new(self.config.azimuth_count(), self.config.range_gate_count);
+        let range_bins = self.config.range_bins();
+
+        let mut doppler_grids = self.doppler.map(|_| {
+            let grid = PolarGrid::new(self.config.azimuth_count(), self.config.range_gate_count);
+            (grid.clone(), grid)
+        });
+
+        // Fill the sweep one radial at a time, as the antenna would
+        for (az_idx, row) in data.rows_mut().enumerate() {
+            let doppler_rows = doppler_grids.as_mut().map(|(velocity, width)| {
+                (
+                    velocity.row_mut(az_idx).expect("radial of the sweep"),
+                    width.row_mut(az_idx).expect("radial of the sweep"),
+                )
+            });
+            self.fill_radial(az_idx, elevation_deg, &range_bins, row, doppler_rows);
         }
 
-        // Add all targets to the complete sweep
-        for target in &self.targets {
-            let az_idx = (target.azimuth as usize) % 360;
-            let range_idx =
-                ((target.range / (RANGE_RESOLUTION_M / 1000.0)) as usize).min(RANGE_BINS - 1);
-
-            // Add target with some spread
-            for az_offset in -2..=2 {
-                for range_offset in -3..=3 {
-                    let target_az = ((az_idx as i32 + az_offset + 360) % 360) as usize;
-                    let target_range = (range_idx as i32 + range_offset).max(0) as usize;
-
-                    if target_range < RANGE_BINS {
-                        let distance =
-                            ((az_offset * az_offset + range_offset * range_offset) as f32).sqrt();
-                        let intensity_factor = (-distance * 0.5).exp();
-
-                        match target.target_type {
-                            TargetType::Weather => {
-                                data[target_az][target_range] +=
-                                    target.intensity * intensity_factor * self.weather_intensity;
-                            }
-                            _ => {} // Only process weather targets
-                        }
-                    }
-                }
-            }
+        // Overlap regions are filled when extracting client portions
+        let unit = self.sample_unit();
+        let mut fields = vec![SweepField::new(self.echo_field_name(), unit, data)];
+        if let Some((velocity, width)) = doppler_grids {
+            fields.push(SweepField::new(
+                VELOCITY,
+                SampleUnit::MetersPerSecond,
+                velocity,
+            ));
+            fields.push(SweepField::new(
+                SPECTRUM_WIDTH,
+                SampleUnit::MetersPerSecond,
+                width,
+            ));
         }
 
         RadarSweep {
             timestamp,
             sequence_id: self.sequence_counter,
+            volume_id: self.volume_counter,
+            tilt,
+            elevation_deg,
             azimuth_start: 0.0,
             azimuth_end: 360.0,
             range_bins,
-            data,
-            overlap_region: vec![], // Will be filled when extracting client portions
-            client_id: 999,         // Indicates complete sweep
+            fields,
+            client_id: 999, // Indicates complete sweep
+        }
+    }
+
+    // Turn the antenna for `dt` seconds at `rotation_rpm`, moving targets along.
+    // Returns the radials the beam passed, numbered from the first rotation;
+    // after a long stall only the last rotation is kept.
+    pub fn advance_antenna(&mut self, dt: f32) -> Range<u64> {
+        self.update_targets(dt);
+        self.current_time += (dt as f64 * 1_000_000.0) as u64; // microseconds
+
+        let degrees_per_radial = self.config.azimuth_resolution_deg as f64;
+        self.antenna_degrees += self.rotation_rpm * 6.0 * dt as f64; // 1 RPM = 6°/s
+        let end = (self.antenna_degrees / degrees_per_radial).floor() as u64 + 1;
+        let start = self
+            .next_radial
+            .max(end.saturating_sub(self.config.azimuth_count() as u64));
+        self.next_radial = self.next_radial.max(end);
+        start..self.next_radial
+    }
+
+    // Radial `number` of the scan: azimuth step `number % N` of rotation
+    // `number / N + 1` for N radials per rotation, sampled at the simulator's
+    // current time
+    pub fn generate_radial(&self, number: u64) -> Radial {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_micros() as u64;
+
+        let azimuth_count = self.config.azimuth_count().max(1) as u64;
+        let az_idx = (number % azimuth_count) as usize;
+        let rotation = number / azimuth_count + 1;
+
+        let range_bins = self.config.range_bins();
+        let mut samples = vec![0.0; range_bins.len()];
+        let tilt = self.antenna_tilt;
+        let elevation_deg = self.scan.elevation_deg(tilt).unwrap_or_default();
+        self.fill_radial(az_idx, elevation_deg, &range_bins, &mut samples, None);
+
+        Radial {
+            timestamp,
+            sequence_id: rotation,
+            azimuth: self.config.azimuth_of(az_idx),
+            tilt,
+            elevation_deg,
+            first_gate_km: self.config.first_gate_km(),
+            gate_spacing_km: self.config.gate_spacing_km(),
+            samples,
+        }
+    }
+
+    // Samples of one radial at `elevation_deg`: background noise plus the echo
+    // of every target present, drawn according to its type. With a power model
+    // the echoes go through the radar equation instead of being added up as
+    // they are. With Doppler processing on, `doppler_rows` receives velocity
+    // and spectrum width.
+    fn fill_radial(
+        &self,
+        az_idx: usize,
+        elevation_deg: f32,
+        range_bins: &[f32],
+        row: &mut [f32],
+        mut doppler_rows: Option<(&mut [f32], &mut [f32])>,
+    ) {
+        let azimuth = self.config.azimuth_of(az_idx);
+        let beam = azimuth + self.config.azimuth_resolution_deg / 2.0;
+        let (beam_sin, beam_cos) = beam.to_radians().sin_cos();
+        let horizontal_share = elevation_deg.to_radians().cos(); // Of motion over the ground seen along the beam
+
+        // Volume echoes (weather, clutter) and point echoes (aircraft) fall
+        // off differently with range, so they are kept apart, and so are
+        // their Doppler spectra
+        let mut volume = vec![0.0; row.len()];
+        let mut point = vec![0.0; row.len()];
+        let spectrum_gates = if self.doppler.is_some() { row.len() } else { 0 };
+        let mut volume_spectra = vec![DopplerSpectrum::default(); spectrum_gates];
+        let mut point_spectra = vec![DopplerSpectrum::default(); spectrum_gates];
+
+        let mut echo = vec![0.0; row.len()];
+        let time_s = self.time_s();
+        for (index, target) in self.targets.iter().enumerate() {
+            if !target.lifetime.is_alive(time_s) {
+                continue;
+            }
+            echo.fill(0.0);
+            let width_ms = match target.target_type {
+                TargetType::Weather => {
+                    self.add_weather(index, target, azimuth, elevation_deg, range_bins, &mut echo);
+                    WEATHER_SPECTRUM_WIDTH_MS
+                }
+                TargetType::Aircraft => {
+                    self.add_aircraft(target, azimuth, range_bins, &mut echo);
+                    AIRCRAFT_SPECTRUM_WIDTH_MS
+                }
+                TargetType::GroundClutter => {
+                    self.add_clutter(target, azimuth, elevation_deg, range_bins, &mut echo);
+                    CLUTTER_SPECTRUM_WIDTH_MS
+                }
+            };
+            let (echoes, spectra) = match target.target_type {
+                TargetType::Aircraft => (&mut point, &mut point_spectra),
+                _ => (&mut volume, &mut volume_spectra),
+            };
+            for (sum, value) in echoes.iter_mut().zip(&echo) {
+                *sum += value;
+            }
+
+            if let Some(doppler) = &self.doppler {
+                // Only motion along the beam shifts the frequency
+                let [east_ms, north_ms] = self.ground_velocity_ms(target);
+                let radial_ms = (east_ms * beam_sin + north_ms * beam_cos) * horizontal_share;
+                for (spectrum, &value) in spectra.iter_mut().zip(&echo) {
+                    if value > 0.0 {
+                        spectrum.add(&DopplerSpectrum::echo(doppler, value, radial_ms, width_ms));
+                    }
+                }
+            }
+        }
+
+        let gate_spacing_km = self.config.gate_spacing_km();
+        for (gate, (value, &range_km)) in row.iter_mut().zip(range_bins).enumerate() {
+            let noise_value = self.noise_generator.get([
+                azimuth as f64 * 0.1,
+                range_km as f64 * 0.2,
+                self.current_time as f64 * 0.001,
+            ]) as f32;
+
+            // Sample, plus the power of the volume echoes, point echoes and
+            // noise in the same units for weighting the Doppler spectra
+            let (sample, powers) = match &self.power_model {
+                None => {
+                    // Base noise level with range attenuation
+                    let range_attenuation = 1.0 / (1.0 + range_km * 0.1);
+                    let noise = noise_value.abs() * self.noise_level * range_attenuation;
+                    (
+                        noise + volume[gate] + point[gate],
+                        [volume[gate], point[gate], noise],
+                    )
+                }
+                Some(model) => {
+                    // Gate centre, so the first gate is not at the radar itself
+                    let range_m = (range_km + gate_spacing_km / 2.0) * 1000.0;
+                    let transmission = model.transmission(range_m);
+                    let volume_w = model.volume_power_w(
+                        &self.beam,
+                        echo_reflectivity_mm6(volume[gate]),
+                        range_m,
+                    ) * transmission;
+                    let point_w = model.point_power_w(point[gate], range_m) * transmission;
+                    // Thermal noise fluctuating around the floor
+                    let noise_w = model.noise_floor_w() * noise_value.exp();
+                    (
+                        model.to_output(&self.beam, volume_w + point_w + noise_w, range_m),
+                        [volume_w, point_w, noise_w],
+                    )
+                }
+            };
+            *value = sample;
+
+            if let (Some(doppler), Some((velocity, width))) = (&self.doppler, &mut doppler_rows) {
+                let [volume_power, point_power, noise_power] = powers;
+                if volume_power + point_power < noise_power * DOPPLER_MIN_SNR {
+                    velocity[gate] = MISSING;
+                    width[gate] = MISSING;
+                    continue;
+                }
+                let noise_phase = noise_phase(az_idx, gate, self.current_time);
+
+                let mut spectrum = volume_spectra[gate].scaled_to(volume_power);
+                spectrum.add(&point_spectra[gate].scaled_to(point_power));
+                spectrum.add(&DopplerSpectrum::noise(noise_power, noise_phase));
+                velocity[gate] = spectrum.velocity_ms(doppler);
+                width[gate] = spectrum.width_ms(doppler);
+            }
+        }
+    }
+
+    // Velocity over the ground as [east, north] in m/s; weather drifts with
+    // the wind on top of its own motion
+    fn ground_velocity_ms(&self, target: &RadarTarget) -> [f32; 2] {
+        let [mut east, mut north] = target.kinematics.velocity_kmh();
+        if target.target_type == TargetType::Weather {
+            east += self.wind_kmh[0];
+            north += self.wind_kmh[1];
+        }
+        [east / 3.6, north / 3.6]
+    }
+
+    // Elliptical cell of physical size: a Gaussian core scaled by the cell's
+    // life-cycle strength, broken up by texture that moves with the cell, and
+    // blurred by the beam and pulse, so far-away cells look wider. The beam
+    // climbs with range, so higher tilts only catch the near part of a cell
+    // below its echo top.
+    fn add_weather(
+        &self,
+        index: usize,
+        target: &RadarTarget,
+        azimuth: f32,
+        elevation_deg: f32,
+        range_bins: &[f32],
+        row: &mut [f32],
+    ) {
+        let cell = target.cell.unwrap_or_default();
+        let age_s = self.time_s() - target.lifetime.start_s;
+        let strength = cell.strength(age_s);
+        if strength <= 0.0 {
+            return;
+        }
+        let (half_length, half_width) = cell.half_axes_km(strength);
+        let extent_km = half_length.max(half_width) * WEATHER_EDGE;
+        let half_beam = self.beam.beamwidth_deg / 2.0;
+        let half_pulse_km = self.beam.range_resolution_m() / 2000.0;
+
+        // Skip radials that pass the cell by
+        let beam = azimuth + self.config.azimuth_resolution_deg / 2.0;
+        let (beam_sin, beam_cos) = beam.to_radians().sin_cos();
+        let (cell_x, cell_y) = (target.kinematics.x_km, target.kinematics.y_km);
+        let along_km = cell_x * beam_sin + cell_y * beam_cos;
+        let across_km = cell_x * beam_cos - cell_y * beam_sin;
+        let reach_km = extent_km + along_km.abs() * half_beam.to_radians() + half_pulse_km;
+        if across_km.abs() > reach_km || along_km < -reach_km {
+            return;
+        }
+
+        // Position along (u) and across (v) the cell's major axis
+        let (axis_sin, axis_cos) = cell.orientation_deg.to_radians().sin_cos();
+        let cell_coords = |range_km: f32, sin: f32, cos: f32| {
+            let dx = range_km * sin - cell_x;
+            let dy = range_km * cos - cell_y;
+            (dx * axis_sin + dy * axis_cos, dx * axis_cos - dy * axis_sin)
+        };
+
+        // The resolution volume, sampled at three angles across the main lobe
+        // and three ranges along the pulse, weighted by the radar's response
+        let mut volume_samples = Vec::with_capacity(9);
+        let mut total_weight = 0.0;
+        for angle in [-half_beam, 0.0, half_beam] {
+            let (sin, cos) = (beam + angle).to_radians().sin_cos();
+            for range_offset_km in [-half_pulse_km, 0.0, half_pulse_km] {
+                let weight = self.beam.two_way_gain(angle)
+                    * self.beam.range_weight(range_offset_km * 1000.0);
+                volume_samples.push((sin, cos, range_offset_km, weight));
+                total_weight += weight;
+            }
+        }
+
+        // The beam's height extent, sampled at three elevations across the
+        // main lobe like its width
+        let elevation_samples = [-half_beam, 0.0, half_beam]
+            .map(|angle| (elevation_deg + angle, self.beam.two_way_gain(angle)));
+        let elevation_weight: f32 = elevation_samples.iter().map(|&(_, weight)| weight).sum();
+
+        for (value, &range_km) in row.iter_mut().zip(range_bins) {
+            if (range_km - along_km).abs() > reach_km {
+                continue;
+            }
+            let vertical = elevation_samples
+                .iter()
+                .map(|&(elevation, weight)| {
+                    weight * cell.vertical_profile(beam_height_km(range_km, elevation), strength)
+                })
+                .sum::<f32>()
+                / elevation_weight;
+            if vertical <= 0.0 {
+                continue; // Beam above the echo top
+            }
+
+            let mut core = 0.0;
+            for &(sin, cos, range_offset_km, weight) in &volume_samples {
+                let (u, v) = cell_coords(range_km + range_offset_km, sin, cos);
+                let distance2 = (u / half_length).powi(2) + (v / half_width).powi(2);
+                if distance2 <= WEATHER_EDGE * WEATHER_EDGE {
+                    core += weight * (-2.0 * distance2).exp();
+                }
+            }
+            if core <= 0.0 {
+                continue;
+            }
+            core /= total_weight;
+
+            let (u, v) = cell_coords(range_km, beam_sin, beam_cos);
+            let texture_noise = self.noise_generator.get([
+                u as f64 * 0.8,
+                v as f64 * 0.8,
+                500.0 + index as f64 * 37.0 + age_s as f64 * 0.02,
+            ]) as f32;
+            let structure = 1.0 - cell.texture + cell.texture * (1.0 + texture_noise).max(0.0);
+            *value +=
+                target.intensity * strength * core * vertical * structure * self.weather_intensity;
         }
     }
 
+    // Point echo shaped by the beam: the antenna pattern across azimuth,
+    // sidelobe ghosts included, and the pulse response along range
+    fn add_aircraft(
+        &self,
+        target: &RadarTarget,
+        azimuth: f32,
+        range_bins: &[f32],
+        row: &mut [f32],
+    ) {
+        // Offset from the beam centre, the short way around
+        let beam_centre = azimuth + self.config.azimuth_resolution_deg / 2.0;
+        let offset = (target.azimuth - beam_centre + 180.0).rem_euclid(360.0) - 180.0;
+        let gain = self.beam.two_way_gain(offset);
+        if gain <= 0.0 {
+            return;
+        }
+        let half_gate_km = self.config.gate_spacing_km() / 2.0;
+        for (value, &range_km) in row.iter_mut().zip(range_bins) {
+            let weight = self
+                .beam
+                .range_weight((range_km + half_gate_km - target.range) * 1000.0);
+            *value += target.intensity * gain * weight;
+        }
+    }
+
+    // Static patch of returns within `CLUTTER_HORIZON_KM`: its speckle does not
+    // change between scans and it fades towards the horizon. Only the lower
+    // edge of the main lobe touches the ground, so clutter fades quickly on
+    // higher tilts and is left to the sidelobes.
+    fn add_clutter(
+        &self,
+        target: &RadarTarget,
+        azimuth: f32,
+        elevation_deg: f32,
+        range_bins: &[f32],
+        row: &mut [f32],
+    ) {
+        let offset = (azimuth - target.azimuth + 180.0).rem_euclid(360.0) - 180.0;
+        if offset.abs() > CLUTTER_SPREAD_DEGREES {
+            return;
+        }
+        let ground_gain = self
+            .beam
+            .two_way_gain((elevation_deg - self.beam.beamwidth_deg / 2.0).max(0.0));
+        if ground_gain <= 0.0 {
+            return;
+        }
+        let az_taper = 1.0 - offset.abs() / CLUTTER_SPREAD_DEGREES;
+
+        for (value, &range_km) in row.iter_mut().zip(range_bins) {
+            if range_km >= CLUTTER_HORIZON_KM || (range_km - target.range).abs() > CLUTTER_SPREAD_KM
+            {
+                continue;
+            }
+            // Frozen speckle: noise sampled off the time axis
+            let speckle =
+                self.noise_generator
+                    .get([azimuth as f64 * 0.5, range_km as f64 * 2.0, -1000.0]);
+            let horizon_taper = 1.0 - range_km / CLUTTER_HORIZON_KM;
+            *value += target.intensity
+                * (0.5 + speckle.abs() as f32)
+                * az_taper
+                * horizon_taper
+                * ground_gain;
+        }
+    }
+
+    // Restart sequence numbering, and the volume scan from its lowest tilt
     pub fn reset_sequence(&mut self) {
         self.sequence_counter = 0;
+        self.volume_counter = 0;
+        self.next_tilt = 0;
     }
 }
 
-// Extract portion of complete sweep for specific client (real-world data splitting)
+// Random phase between -1 and 1 of the receiver noise in one gate, fixed for
+// a given time. A hash rather than the noise field, which is too smooth and
+// too slow to sample again for every gate.
+fn noise_phase(az_idx: usize, gate: usize, time_us: u64) -> f32 {
+    let mut hash = (az_idx as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (gate as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+        ^ time_us;
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    hash ^= hash >> 33;
+    (hash >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
+
+impl Default for RadarSimulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Extract portion of complete sweep for specific client (legacy two-client split).
+// Use `SectorPlan` directly for any other sector layout.
 pub fn extract_client_portion(complete_sweep: &RadarSweep, client_id: usize) -> RadarSweep {
-    let (azimuth_start, azimuth_end) = match client_id {
-        0 => (0.0, 190.0),   // Client 1: 0-190° with overlap
-        1 => (170.0, 360.0), // Client 2: 170-360° with overlap
-        _ => (0.0, 360.0),   // Fallback
-    };
-
-    let start_idx = azimuth_start as usize;
-    let end_idx = azimuth_end as usize;
-
-    // Extract data portion from complete sweep
-    let mut client_data = Vec::new();
-    if client_id == 0 {
-        // Client 1: 0-190° (simple slice)
-        client_data = complete_sweep.data[start_idx..end_idx].to_vec();
-    } else if client_id == 1 {
-        // Client 2: 170-360° (wrap around case)
-        client_data.extend_from_slice(&complete_sweep.data[start_idx..360]);
-        // Note: end_idx would be 360, so we don't need to add anything from the beginning
-    }
-
-    // Extract overlap region (170-190° for both clients) - same data for seamless merging
-    let overlap_data = complete_sweep.data[170..190].to_vec();
-
-    RadarSweep {
-        timestamp: complete_sweep.timestamp, // Same timestamp - critical for merging
-        sequence_id: complete_sweep.sequence_id, // Same sequence - critical for merging
-        azimuth_start,
-        azimuth_end,
-        range_bins: complete_sweep.range_bins.clone(),
-        data: client_data,
-        overlap_region: overlap_data, // Same overlap data for both clients
-        client_id,
+    SectorPlan::two_way(OVERLAP_DEGREES)
+        .extract(complete_sweep, client_id)
+        .unwrap_or_else(|| RadarSweep {
+            azimuth_start: 0.0, // Fallback: the full sweep
+            azimuth_end: 360.0,
+            fields: complete_sweep.map_fields(|field| {
+                (
+                    field.data.clone(),
+                    PolarGrid::new(0, field.data.range_count()),
+                )
+            }),
+            client_id,
+            ..complete_sweep.clone()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Turn the antenna in `dt` steps for `seconds`, checking that each step
+    // picks up exactly where the previous one stopped. The steps used below
+    // are exact in binary, so radial boundaries are hit without rounding.
+    fn turn(sim: &mut RadarSimulator, dt: f32, seconds: f32, radials: &mut Vec<u64>) {
+        for _ in 0..(seconds / dt).round() as usize {
+            let passed = sim.advance_antenna(dt);
+            assert_eq!(passed.start, radials.last().map_or(0, |&last| last + 1));
+            radials.extend(passed);
+        }
+    }
+
+    #[test]
+    fn each_rotation_covers_every_azimuth_once() {
+        let mut sim = RadarSimulator::new();
+        let mut radials = Vec::new();
+        turn(&mut sim, 0.125, 15.0, &mut radials); // Three rotations at 12 RPM
+        let per_rotation = sim.config().azimuth_count();
+
+        let expected: Vec<u64> = (0..=(3 * per_rotation) as u64).collect();
+        assert_eq!(radials, expected);
+
+        let mut seen = vec![0usize; per_rotation];
+        for &number in &radials[per_rotation..2 * per_rotation] {
+            let radial = sim.generate_radial(number);
+            assert_eq!(radial.sequence_id, 2);
+            assert_eq!(radial.samples.len(), RANGE_BINS);
+            seen[radial.azimuth.round() as usize] += 1;
+        }
+        assert!(seen.iter().all(|&count| count == 1));
+    }
+
+    #[test]
+    fn rotation_rate_changes_leave_no_gaps_or_repeats() {
+        let mut sim = RadarSimulator::new();
+        let mut radials = Vec::new();
+
+        turn(&mut sim, 0.25, 2.0, &mut radials); // 144° at 12 RPM
+        sim.rotation_rpm = 30.0;
+        turn(&mut sim, 0.5, 3.0, &mut radials); // 540° at 30 RPM
+        sim.rotation_rpm = 2.5;
+        turn(&mut sim, 0.125, 4.0, &mut radials); // 60° at 2.5 RPM
+
+        // Every radial from the start up to the antenna's position, once each
+        let expected: Vec<u64> = (0..=744).collect();
+        assert_eq!(radials, expected);
+    }
+
+    #[test]
+    fn long_stall_keeps_only_the_last_rotation() {
+        let mut sim = RadarSimulator::new();
+        let first = sim.advance_antenna(1.0); // 72°
+        assert_eq!(first, 0..73);
+
+        let passed = sim.advance_antenna(60.0); // 12 rotations
+        assert_eq!(passed.end, 72 * 61 + 1);
+        assert_eq!(
+            passed.end - passed.start,
+            sim.config().azimuth_count() as u64,
+            "one full rotation, not twelve"
+        );
+        assert_eq!(sim.advance_antenna(0.0), passed.end..passed.end);
+    }
+
+    fn stationary(target_type: TargetType, azimuth: f32, range: f32) -> RadarTarget {
+        RadarTarget {
+            azimuth,
+            range,
+            intensity: 1.0,
+            velocity: 0.0,
+            kinematics: Kinematics::from_polar(azimuth, range),
+            cell: None,
+            target_type,
+            lifetime: Lifetime::default(),
+        }
+    }
+
+    // Simulator showing only a stationary `target_type` target
+    fn lone_target(target_type: TargetType, azimuth: f32, range: f32) -> RadarSimulator {
+        let mut sim = RadarSimulator::new();
+        sim.targets = vec![stationary(target_type, azimuth, range)];
+        sim
+    }
+
+    // (azimuth index, gate, value) of every cell above `threshold`
+    fn cells_above(sweep: &RadarSweep, threshold: f32) -> Vec<(usize, usize, f32)> {
+        sweep
+            .primary_field()
+            .unwrap()
+            .data
+            .rows()
+            .enumerate()
+            .flat_map(|(az_idx, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(move |(_, &value)| value > threshold)
+                    .map(move |(gate, &value)| (az_idx, gate, value))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn aircraft_is_a_compact_strong_return_at_its_position() {
+        // Centre of the 90° radial, in the gate starting at 30 km
+        let mut sim = lone_target(TargetType::Aircraft, 90.5, 30.05);
+        let sweep = sim.generate_complete_sweep();
+
+        let peak = cells_above(&sweep, 0.0)
+            .into_iter()
+            .max_by(|a, b| a.2.total_cmp(&b.2))
+            .unwrap();
+        assert_eq!((peak.0, peak.1), (90, 300));
+        assert!(peak.2 >= 1.0, "full echo plus noise, got {}", peak.2);
+
+        // Only its own cell stands out, neighbouring radials see the beam's skirt
+        assert_eq!(cells_above(&sweep, peak.2 / 2.0).len(), 1);
+    }
+
+    #[test]
+    fn clutter_is_identical_between_scans() {
+        let mut sim = lone_target(TargetType::GroundClutter, 45.0, 10.0);
+        sim.noise_level = 0.0; // Leave only the clutter, noise moves with time
+
+        let first = sim.generate_complete_sweep();
+        sim.advance_antenna(5.0); // One rotation later
+        let second = sim.generate_complete_sweep();
+
+        assert!(!cells_above(&first, 0.0).is_empty());
+        assert_eq!(first.fields, second.fields);
+    }
+
+    #[test]
+    fn no_clutter_beyond_the_horizon() {
+        // Patch straddling the horizon, and one entirely behind it
+        let mut sim = lone_target(TargetType::GroundClutter, 200.0, CLUTTER_HORIZON_KM - 0.5);
+        sim.targets
+            .push(stationary(TargetType::GroundClutter, 300.0, 30.0));
+        sim.noise_level = 0.0;
+        let sweep = sim.generate_complete_sweep();
+
+        let cells = cells_above(&sweep, 0.0);
+        assert!(!cells.is_empty());
+        for (az_idx, gate, _) in cells {
+            assert!(sweep.range_bins[gate] < CLUTTER_HORIZON_KM);
+            assert!((196..=204).contains(&az_idx), "clutter at {}°", az_idx);
+        }
+    }
+
+    #[test]
+    fn radials_follow_the_tilt_of_the_latest_sweep() {
+        let scan = ScanStrategy {
+            elevations_deg: vec![0.5, 4.0, 10.0],
+        };
+        let mut sim = RadarSimulator::new().with_scan_strategy(scan);
+        assert_eq!(sim.generate_radial(0).tilt, 0);
+
+        for tilt in [0, 1, 2, 0] {
+            let sweep = sim.generate_complete_sweep();
+            let radial = sim.generate_radial(0);
+            assert_eq!((sweep.tilt, radial.tilt), (tilt, tilt));
+            assert_eq!(radial.elevation_deg, sweep.elevation_deg);
+        }
     }
 }
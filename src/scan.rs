@@ -0,0 +1,247 @@
+use crate::radar_simulator::RadarSweep;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+// 4/3 of the Earth's radius: standard refraction bends the beam back
+// towards the ground, as if the Earth were that much larger
+pub const EFFECTIVE_EARTH_RADIUS_KM: f32 = 6371.0 * 4.0 / 3.0;
+
+// Elevation angles the antenna steps through, one full rotation at each.
+// One pass through every tilt is a volume scan; its sweeps share a volume ID.
+//
+//   scan = { elevations_deg = [0.5, 1.5, 2.4, 3.4, 6.0] }
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanStrategy {
+    pub elevations_deg: Vec<f32>, // Lowest tilt first
+}
+
+impl Default for ScanStrategy {
+    fn default() -> Self {
+        Self {
+            elevations_deg: vec![0.5], // A single low tilt, the classic planar sweep
+        }
+    }
+}
+
+impl ScanStrategy {
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.elevations_deg.is_empty() {
+            return Err("Scan strategy needs at least one elevation".into());
+        }
+        for &elevation in &self.elevations_deg {
+            if !elevation.is_finite() || !(-2.0..90.0).contains(&elevation) {
+                return Err(format!("Invalid elevation {}°", elevation).into());
+            }
+        }
+        if self
+            .elevations_deg
+            .windows(2)
+            .any(|pair| pair[1] <= pair[0])
+        {
+            return Err("Scan elevations must increase from one tilt to the next".into());
+        }
+        Ok(())
+    }
+
+    // Sweeps in one volume scan
+    pub fn tilt_count(&self) -> usize {
+        self.elevations_deg.len()
+    }
+
+    pub fn elevation_deg(&self, tilt: usize) -> Option<f32> {
+        self.elevations_deg.get(tilt).copied()
+    }
+}
+
+// Height (km) of a beam at `elevation_deg` above the radar, `range_km` along
+// the beam, over a spherical Earth with standard refraction
+pub fn beam_height_km(range_km: f32, elevation_deg: f32) -> f32 {
+    let radius = EFFECTIVE_EARTH_RADIUS_KM;
+    let climb = 2.0 * range_km * radius * elevation_deg.to_radians().sin();
+    (range_km * range_km + radius * radius + climb).sqrt() - radius
+}
+
+// Every tilt of one volume scan, lowest first
+#[derive(Debug, Clone)]
+pub struct Volume {
+    pub volume_id: u64,
+    pub sweeps: Vec<RadarSweep>, // One per tilt, in scan order
+}
+
+impl Volume {
+    pub fn tilt(&self, tilt: usize) -> Option<&RadarSweep> {
+        self.sweeps.iter().find(|sweep| sweep.tilt == tilt)
+    }
+
+    pub fn elevations_deg(&self) -> Vec<f32> {
+        self.sweeps
+            .iter()
+            .map(|sweep| sweep.elevation_deg)
+            .collect()
+    }
+}
+
+// Client-side collection of sweeps into volumes. A volume is complete once
+// every tilt the client expects has arrived; volumes older than a completed
+// one are given up, their missing tilts will not come any more.
+#[derive(Debug, Clone)]
+pub struct VolumeAssembler {
+    tilts: Vec<usize>,                       // Tilts that make up a volume
+    pending: BTreeMap<u64, Vec<RadarSweep>>, // Volume ID -> sweeps so far
+}
+
+impl VolumeAssembler {
+    // Expect every tilt of `scan`
+    pub fn new(scan: &ScanStrategy) -> Self {
+        Self::with_tilts((0..scan.tilt_count()).collect())
+    }
+
+    // Expect only `tilts`, e.g. those requested with `SUBSCRIBE tilts=...`
+    pub fn with_tilts(mut tilts: Vec<usize>) -> Self {
+        tilts.sort_unstable();
+        tilts.dedup();
+        Self {
+            tilts,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    // Add a sweep; returns its volume when this sweep completed it
+    pub fn push(&mut self, sweep: RadarSweep) -> Option<Volume> {
+        if !self.tilts.contains(&sweep.tilt) {
+            return None;
+        }
+        let volume_id = sweep.volume_id;
+        let sweeps = self.pending.entry(volume_id).or_default();
+        sweeps.retain(|held| held.tilt != sweep.tilt); // A repeated tilt replaces the old one
+        sweeps.push(sweep);
+        if sweeps.len() < self.tilts.len() {
+            return None;
+        }
+
+        let mut sweeps = self.pending.remove(&volume_id)?;
+        sweeps.sort_by_key(|sweep| sweep.tilt);
+        self.pending.retain(|&pending_id, _| pending_id > volume_id);
+        Some(Volume { volume_id, sweeps })
+    }
+
+    // Volumes still waiting for tilts
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sweep(volume_id: u64, tilt: usize) -> RadarSweep {
+        RadarSweep {
+            timestamp: volume_id * 1000 + tilt as u64,
+            sequence_id: volume_id * 3 + tilt as u64,
+            volume_id,
+            tilt,
+            elevation_deg: 0.5 + tilt as f32,
+            azimuth_start: 0.0,
+            azimuth_end: 360.0,
+            range_bins: Vec::new(),
+            fields: Vec::new(),
+            client_id: 0,
+        }
+    }
+
+    fn three_tilts() -> ScanStrategy {
+        ScanStrategy {
+            elevations_deg: vec![0.5, 1.5, 2.5],
+        }
+    }
+
+    #[test]
+    fn beam_climbs_over_a_four_thirds_earth() {
+        assert_eq!(beam_height_km(0.0, 0.5), 0.0);
+        // Level beam: the Earth curves away, r²/2R
+        assert!((beam_height_km(100.0, 0.0) - 0.589).abs() < 0.01);
+        assert!((beam_height_km(50.0, 0.5) - 0.583).abs() < 0.01);
+        assert!((beam_height_km(35.0, 6.0) - 3.730).abs() < 0.01);
+        assert!((beam_height_km(200.0, 0.5) - 4.099).abs() < 0.01);
+    }
+
+    #[test]
+    fn volume_is_complete_after_its_last_tilt() {
+        let mut assembler = VolumeAssembler::new(&three_tilts());
+        assert!(assembler.push(sweep(4, 0)).is_none());
+        assert!(assembler.push(sweep(4, 2)).is_none());
+        assert_eq!(assembler.pending_count(), 1);
+
+        let volume = assembler.push(sweep(4, 1)).expect("every tilt arrived");
+        assert_eq!(volume.volume_id, 4);
+        assert_eq!(volume.elevations_deg(), vec![0.5, 1.5, 2.5]);
+        assert_eq!(volume.tilt(2).unwrap().sequence_id, 14);
+        assert!(volume.tilt(3).is_none());
+        assert_eq!(assembler.pending_count(), 0);
+    }
+
+    #[test]
+    fn repeated_tilts_replace_the_earlier_sweep() {
+        let mut assembler = VolumeAssembler::new(&three_tilts());
+        assembler.push(sweep(1, 0));
+        let mut again = sweep(1, 0);
+        again.timestamp = 99;
+        assert!(assembler.push(again).is_none());
+        assert!(assembler.push(sweep(1, 1)).is_none());
+
+        let volume = assembler.push(sweep(1, 2)).unwrap();
+        assert_eq!(volume.sweeps.len(), 3);
+        assert_eq!(volume.tilt(0).unwrap().timestamp, 99);
+    }
+
+    #[test]
+    fn only_subscribed_tilts_are_expected() {
+        let mut assembler = VolumeAssembler::with_tilts(vec![2, 0, 2]);
+        assert!(assembler.push(sweep(7, 1)).is_none()); // Not subscribed, ignored
+        assert_eq!(assembler.pending_count(), 0);
+        assert!(assembler.push(sweep(7, 2)).is_none());
+
+        let volume = assembler.push(sweep(7, 0)).unwrap();
+        let tilts: Vec<usize> = volume.sweeps.iter().map(|sweep| sweep.tilt).collect();
+        assert_eq!(tilts, vec![0, 2]);
+    }
+
+    #[test]
+    fn older_incomplete_volumes_are_given_up() {
+        let mut assembler = VolumeAssembler::new(&three_tilts());
+        assembler.push(sweep(1, 0)); // Tilts 1 and 2 of volume 1 were lost
+        assembler.push(sweep(3, 0)); // Volume 3 is still coming
+        for tilt in 0..3 {
+            assembler.push(sweep(2, tilt));
+        }
+        assert_eq!(assembler.pending_count(), 1);
+
+        assert!(assembler.push(sweep(1, 1)).is_none());
+        assert!(assembler.push(sweep(3, 1)).is_none());
+        assert_eq!(assembler.push(sweep(3, 2)).unwrap().volume_id, 3);
+    }
+
+    #[test]
+    fn scan_strategies_step_upwards() {
+        let scan = three_tilts();
+        assert!(scan.validate().is_ok());
+        assert_eq!(scan.tilt_count(), 3);
+        assert_eq!(scan.elevation_deg(1), Some(1.5));
+        assert_eq!(scan.elevation_deg(3), None);
+        assert!(ScanStrategy::default().validate().is_ok());
+
+        for elevations_deg in [
+            vec![],
+            vec![0.5, 0.5],
+            vec![1.5, 0.5],
+            vec![0.5, 90.0],
+            vec![f32::NAN],
+        ] {
+            let scan = ScanStrategy { elevations_deg };
+            assert!(scan.validate().is_err(), "{:?} accepted", scan);
+        }
+    }
+}
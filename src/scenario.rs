@@ -0,0 +1,616 @@
+use crate::antenna::BeamPattern;
+use crate::doppler::DopplerConfig;
+use crate::kinematics::Kinematics;
+use crate::power::PowerModel;
+use crate::radar_simulator::{RadarTarget, TargetType};
+use crate::scan::ScanStrategy;
+use crate::weather::{WeatherCell, Wind};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// A reproducible simulator setup: targets, weather and noise.
+// Loaded from TOML, JSON or YAML, picked by file extension:
+//
+//   name = "Squall line crossing the seam"
+//   noise_seed = 7
+//   wind = { speed_kmh = 40.0, from_deg = 90.0 }   # carries weather cells
+//   power = { output = "dbz" }                     # optional, see `PowerModel`
+//   antenna = { beamwidth_deg = 1.0 }              # see `BeamPattern`
+//   doppler = { nyquist_velocity_ms = 25.0 }       # optional, see `DopplerConfig`
+//   scan = { elevations_deg = [0.5, 1.5, 3.0] }    # tilts of a volume scan, see `ScanStrategy`
+//
+//   [[targets]]
+//   type = "weather"                  # weather | aircraft | ground_clutter
+//   azimuth = 170.0                   # degrees
+//   range_km = 20.0
+//   intensity = 0.9
+//   motion = { model = "azimuth_drift", degrees_per_second = 1.0 }
+//   # or motion = { model = "straight", heading_deg = 90.0, speed_kmh = 450.0 }
+//   # or motion = { model = "waypoints", speed_kmh = 300.0, points = [[-20.0, 10.0], [20.0, 10.0]] }
+//   lifetime = { start_s = 0.0, end_s = 60.0 }
+//   cell = { length_km = 12.0, width_km = 5.0 }    # weather only, see `WeatherCell`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_noise_seed")]
+    pub noise_seed: u32, // Seed of the background noise field
+    #[serde(default = "default_noise_level")]
+    pub noise_level: f32, // Background noise amplitude
+    #[serde(default = "default_weather_intensity")]
+    pub weather_intensity: f32, // Scale applied to every weather target
+    #[serde(default)]
+    pub wind: Wind,
+    #[serde(default)]
+    pub power: Option<PowerModel>, // Radar equation, `None` for unitless intensities
+    #[serde(default)]
+    pub antenna: BeamPattern,
+    #[serde(default)]
+    pub doppler: Option<DopplerConfig>, // Velocity and spectrum width, `None` for intensity only
+    #[serde(default)]
+    pub scan: ScanStrategy,
+    #[serde(default)]
+    pub targets: Vec<TargetSpec>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TargetSpec {
+    #[serde(rename = "type")]
+    pub target_type: TargetType,
+    pub azimuth: f32,   // Starting azimuth (degrees)
+    pub range_km: f32,  // Starting range, the radar is at the origin
+    pub intensity: f32, // Radar cross section
+    #[serde(default)]
+    pub motion: MotionModel,
+    #[serde(default)]
+    pub lifetime: Lifetime,
+    #[serde(default)]
+    pub cell: Option<WeatherCell>, // Weather targets without one get the default cell
+}
+
+// How a target moves over time. Positions on the ground are [east_km, north_km]
+// from the radar.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(tag = "model", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MotionModel {
+    #[default]
+    Static,
+    // Constant angular speed at fixed range
+    AzimuthDrift {
+        degrees_per_second: f32,
+    },
+    // Constant velocity over the ground, e.g. an aircraft on a straight track,
+    // optionally speeding up or slowing down
+    Straight {
+        heading_deg: f32,
+        speed_kmh: f32,
+        #[serde(default)]
+        acceleration_ms2: f32,
+    },
+    // Constant-rate turn, positive clockwise
+    Turn {
+        heading_deg: f32,
+        speed_kmh: f32,
+        turn_rate_deg_s: f32,
+    },
+    // Fly through each point in turn. A turn rate of 0 turns instantly;
+    // otherwise it limits the steering, rounding the corners.
+    Waypoints {
+        speed_kmh: f32,
+        points: Vec<[f32; 2]>,
+        #[serde(default)]
+        turn_rate_deg_s: f32,
+        #[serde(default)]
+        repeat: bool,
+    },
+}
+
+// When a target exists, in seconds of simulation time
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Lifetime {
+    #[serde(default)]
+    pub start_s: f32,
+    #[serde(default)]
+    pub end_s: Option<f32>, // `None` means forever
+}
+
+impl Lifetime {
+    pub fn is_alive(&self, time_s: f32) -> bool {
+        time_s >= self.start_s && self.end_s.is_none_or(|end_s| time_s < end_s)
+    }
+}
+
+// Serialization of a scenario file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScenarioFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ScenarioFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "toml" => Some(ScenarioFormat::Toml),
+            "json" => Some(ScenarioFormat::Json),
+            "yaml" | "yml" => Some(ScenarioFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let format = ScenarioFormat::from_path(path).ok_or_else(|| {
+            format!(
+                "Unknown scenario format for {} (expected .toml, .json, .yaml or .yml)",
+                path.display()
+            )
+        })?;
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read scenario {}: {}", path.display(), e))?;
+
+        Self::parse(&text, format).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    pub fn parse(text: &str, format: ScenarioFormat) -> Result<Self, Box<dyn Error>> {
+        let scenario: Scenario = match format {
+            ScenarioFormat::Toml => toml::from_str(text)?,
+            ScenarioFormat::Json => serde_json::from_str(text)?,
+            ScenarioFormat::Yaml => serde_yaml::from_str(text)?,
+        };
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if !self.noise_level.is_finite() || self.noise_level < 0.0 {
+            return Err(format!("Invalid noise level {}", self.noise_level).into());
+        }
+        if !self.weather_intensity.is_finite() || self.weather_intensity < 0.0 {
+            return Err(format!("Invalid weather intensity {}", self.weather_intensity).into());
+        }
+        if !self.wind.speed_kmh.is_finite()
+            || self.wind.speed_kmh < 0.0
+            || !self.wind.from_deg.is_finite()
+        {
+            return Err(format!("Invalid wind {:?}", self.wind).into());
+        }
+        if let Some(power) = &self.power {
+            power.validate()?;
+        }
+        self.antenna.validate()?;
+        if let Some(doppler) = &self.doppler {
+            doppler.validate()?;
+        }
+        self.scan.validate()?;
+
+        for (index, target) in self.targets.iter().enumerate() {
+            let invalid = |what: &str| -> Box<dyn Error> {
+                format!("Target {} has an invalid {}", index, what).into()
+            };
+            if !target.azimuth.is_finite() {
+                return Err(invalid("azimuth"));
+            }
+            if !target.range_km.is_finite() || target.range_km < 0.0 {
+                return Err(invalid("range"));
+            }
+            if !target.intensity.is_finite() || target.intensity < 0.0 {
+                return Err(invalid("intensity"));
+            }
+            let valid_speed = |speed_kmh: f32| speed_kmh.is_finite() && speed_kmh >= 0.0;
+            match &target.motion {
+                MotionModel::Static => {}
+                MotionModel::AzimuthDrift { degrees_per_second } => {
+                    if !degrees_per_second.is_finite() {
+                        return Err(invalid("drift rate"));
+                    }
+                }
+                MotionModel::Straight {
+                    heading_deg,
+                    speed_kmh,
+                    acceleration_ms2,
+                } => {
+                    if !heading_deg.is_finite()
+                        || !valid_speed(*speed_kmh)
+                        || !acceleration_ms2.is_finite()
+                    {
+                        return Err(invalid("straight-line motion"));
+                    }
+                }
+                MotionModel::Turn {
+                    heading_deg,
+                    speed_kmh,
+                    turn_rate_deg_s,
+                } => {
+                    if !heading_deg.is_finite()
+                        || !valid_speed(*speed_kmh)
+                        || !turn_rate_deg_s.is_finite()
+                    {
+                        return Err(invalid("turn"));
+                    }
+                }
+                MotionModel::Waypoints {
+                    speed_kmh,
+                    points,
+                    turn_rate_deg_s,
+                    ..
+                } => {
+                    if points.is_empty()
+                        || points.iter().flatten().any(|km| !km.is_finite())
+                        || !valid_speed(*speed_kmh)
+                        || !turn_rate_deg_s.is_finite()
+                    {
+                        return Err(invalid("waypoint path"));
+                    }
+                }
+            }
+            if target.target_type == TargetType::GroundClutter
+                && target.motion != MotionModel::Static
+            {
+                return Err(format!("Target {} is ground clutter and cannot move", index).into());
+            }
+            if let Some(cell) = &target.cell {
+                if target.target_type != TargetType::Weather {
+                    return Err(format!("Target {} has a cell but is not weather", index).into());
+                }
+                cell.validate()
+                    .map_err(|e| format!("Target {}: {}", index, e))?;
+            }
+            let lifetime = target.lifetime;
+            if !lifetime.start_s.is_finite()
+                || lifetime
+                    .end_s
+                    .is_some_and(|end_s| !end_s.is_finite() || end_s <= lifetime.start_s)
+            {
+                return Err(invalid("lifetime"));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Simulator targets at their starting positions
+    pub fn radar_targets(&self) -> Vec<RadarTarget> {
+        self.targets
+            .iter()
+            .map(|target| {
+                let azimuth = target.azimuth.rem_euclid(360.0);
+                let start = Kinematics::from_polar(azimuth, target.range_km);
+                let (velocity, kinematics) = match &target.motion {
+                    MotionModel::Static => (0.0, start),
+                    MotionModel::AzimuthDrift { degrees_per_second } => {
+                        (*degrees_per_second, start)
+                    }
+                    MotionModel::Straight {
+                        heading_deg,
+                        speed_kmh,
+                        acceleration_ms2,
+                    } => (
+                        0.0,
+                        start
+                            .with_velocity(*heading_deg, *speed_kmh)
+                            .with_acceleration(*acceleration_ms2),
+                    ),
+                    MotionModel::Turn {
+                        heading_deg,
+                        speed_kmh,
+                        turn_rate_deg_s,
+                    } => (
+                        0.0,
+                        start
+                            .with_velocity(*heading_deg, *speed_kmh)
+                            .with_turn_rate(*turn_rate_deg_s),
+                    ),
+                    MotionModel::Waypoints {
+                        speed_kmh,
+                        points,
+                        turn_rate_deg_s,
+                        repeat,
+                    } => (
+                        0.0,
+                        start
+                            .with_velocity(0.0, *speed_kmh)
+                            .with_turn_rate(*turn_rate_deg_s)
+                            .with_waypoints(points.clone(), *repeat),
+                    ),
+                };
+                RadarTarget {
+                    azimuth,
+                    range: target.range_km,
+                    intensity: target.intensity,
+                    velocity,
+                    kinematics,
+                    cell: (target.target_type == TargetType::Weather)
+                        .then(|| target.cell.unwrap_or_default()),
+                    target_type: target.target_type.clone(),
+                    lifetime: target.lifetime,
+                }
+            })
+            .collect()
+    }
+}
+
+// The built-in setup: four slow weather systems
+impl Default for Scenario {
+    fn default() -> Self {
+        let weather = |azimuth, range_km, intensity, degrees_per_second| TargetSpec {
+            target_type: TargetType::Weather,
+            azimuth,
+            range_km,
+            intensity,
+            motion: MotionModel::AzimuthDrift { degrees_per_second },
+            lifetime: Lifetime::default(),
+            cell: None,
+        };
+
+        Self {
+            name: "Default weather".to_string(),
+            noise_seed: default_noise_seed(),
+            noise_level: default_noise_level(),
+            weather_intensity: default_weather_intensity(),
+            wind: Wind::default(),
+            power: None,
+            antenna: BeamPattern::default(),
+            doppler: None,
+            scan: ScanStrategy::default(),
+            targets: vec![
+                weather(45.0, 15.0, 0.6, 0.5), // Slow moving weather system
+                weather(120.0, 30.0, 0.8, 0.2),
+                weather(200.0, 25.0, 0.7, -0.3),
+                weather(280.0, 20.0, 0.9, 0.1), // Larger system spanning multiple ranges
+            ],
+        }
+    }
+}
+
+fn default_noise_seed() -> u32 {
+    42
+}
+
+fn default_noise_level() -> f32 {
+    0.1
+}
+
+fn default_weather_intensity() -> f32 {
+    0.4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+name = "Two cells"
+noise_seed = 7
+
+[[targets]]
+type = "weather"
+azimuth = 170.0
+range_km = 20.0
+intensity = 0.9
+motion = { model = "azimuth_drift", degrees_per_second = 1.0 }
+lifetime = { start_s = 5.0, end_s = 60.0 }
+
+[[targets]]
+type = "ground_clutter"
+azimuth = 10.0
+range_km = 2.0
+intensity = 0.3
+"#;
+
+    const JSON: &str = r#"{
+  "name": "Two cells",
+  "noise_seed": 7,
+  "targets": [
+    { "type": "weather", "azimuth": 170.0, "range_km": 20.0, "intensity": 0.9,
+      "motion": { "model": "azimuth_drift", "degrees_per_second": 1.0 },
+      "lifetime": { "start_s": 5.0, "end_s": 60.0 } },
+    { "type": "ground_clutter", "azimuth": 10.0, "range_km": 2.0, "intensity": 0.3 }
+  ]
+}"#;
+
+    const YAML: &str = r#"
+name: Two cells
+noise_seed: 7
+targets:
+  - type: weather
+    azimuth: 170.0
+    range_km: 20.0
+    intensity: 0.9
+    motion: { model: azimuth_drift, degrees_per_second: 1.0 }
+    lifetime: { start_s: 5.0, end_s: 60.0 }
+  - type: ground_clutter
+    azimuth: 10.0
+    range_km: 2.0
+    intensity: 0.3
+"#;
+
+    const AIRCRAFT: &str = r#"
+[[targets]]
+type = "aircraft"
+azimuth = 10.0
+range_km = 5.0
+intensity = 1.0
+"#;
+
+    fn rejected(text: &str) -> String {
+        Scenario::parse(text, ScenarioFormat::Toml)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn formats_describe_the_same_scenario() {
+        let toml = Scenario::parse(TOML, ScenarioFormat::Toml).unwrap();
+        let json = Scenario::parse(JSON, ScenarioFormat::Json).unwrap();
+        let yaml = Scenario::parse(YAML, ScenarioFormat::Yaml).unwrap();
+        assert_eq!(toml, json);
+        assert_eq!(toml, yaml);
+
+        assert_eq!(toml.noise_seed, 7);
+        assert_eq!(toml.noise_level, default_noise_level());
+        assert_eq!(toml.targets.len(), 2);
+        assert_eq!(toml.targets[1].motion, MotionModel::Static);
+        assert_eq!(toml.targets[1].lifetime, Lifetime::default());
+    }
+
+    #[test]
+    fn misspelled_keys_are_rejected() {
+        let misspelled = TOML.replace("range_km = 20.0", "range_kms = 20.0");
+        assert!(rejected(&misspelled).contains("range_kms"));
+
+        let top_level = format!("noise_sed = 3\n{}", TOML);
+        assert!(rejected(&top_level).contains("noise_sed"));
+
+        let motion = TOML.replace("degrees_per_second", "degrees_per_sec");
+        assert!(Scenario::parse(&motion, ScenarioFormat::Toml).is_err());
+
+        let json = JSON.replace("\"intensity\": 0.3", "\"intensty\": 0.3");
+        assert!(Scenario::parse(&json, ScenarioFormat::Json).is_err());
+        let yaml = YAML.replace("lifetime:", "life_time:");
+        assert!(Scenario::parse(&yaml, ScenarioFormat::Yaml).is_err());
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let cases = [
+            ("range_km = 20.0", "range_km = -1.0", "invalid range"),
+            ("intensity = 0.9", "intensity = -0.5", "invalid intensity"),
+            ("azimuth = 170.0", "azimuth = nan", "invalid azimuth"),
+            ("= 1.0 }", "= inf }", "invalid drift rate"),
+            ("end_s = 60.0", "end_s = 5.0", "invalid lifetime"),
+            ("start_s = 5.0", "start_s = -inf", "invalid lifetime"),
+        ];
+        for (valid, invalid, error) in cases {
+            let text = TOML.replace(valid, invalid);
+            let message = rejected(&text);
+            assert!(message.contains("Target 0"), "{}: {}", invalid, message);
+            assert!(message.contains(error), "{}: {}", invalid, message);
+        }
+
+        let noisy = TOML.replace("noise_seed = 7", "noise_seed = 7\nnoise_level = -0.1");
+        assert!(rejected(&noisy).contains("noise level"));
+        let weather = TOML.replace("noise_seed = 7", "noise_seed = 7\nweather_intensity = nan");
+        assert!(rejected(&weather).contains("weather intensity"));
+    }
+
+    #[test]
+    fn motion_models_are_validated() {
+        let aircraft = |motion: &str| format!("{}motion = {}\n", AIRCRAFT, motion);
+        let valid = [
+            r#"{ model = "straight", heading_deg = 90.0, speed_kmh = 500.0 }"#,
+            r#"{ model = "turn", heading_deg = 0.0, speed_kmh = 300.0, turn_rate_deg_s = -3.0 }"#,
+            r#"{ model = "waypoints", speed_kmh = 300.0, points = [[1.0, 2.0]], repeat = true }"#,
+        ];
+        for motion in valid {
+            assert!(
+                Scenario::parse(&aircraft(motion), ScenarioFormat::Toml).is_ok(),
+                "{}",
+                motion
+            );
+        }
+
+        let invalid = [
+            (
+                r#"{ model = "straight", heading_deg = 90.0, speed_kmh = -1.0 }"#,
+                "straight-line motion",
+            ),
+            (
+                r#"{ model = "straight", heading_deg = nan, speed_kmh = 500.0 }"#,
+                "straight-line motion",
+            ),
+            (
+                r#"{ model = "turn", heading_deg = 0.0, speed_kmh = 300.0, turn_rate_deg_s = inf }"#,
+                "turn",
+            ),
+            (
+                r#"{ model = "waypoints", speed_kmh = 300.0, points = [] }"#,
+                "waypoint path",
+            ),
+            (
+                r#"{ model = "waypoints", speed_kmh = 300.0, points = [[1.0, nan]] }"#,
+                "waypoint path",
+            ),
+            (
+                r#"{ model = "waypoints", speed_kmh = inf, points = [[1.0, 2.0]] }"#,
+                "waypoint path",
+            ),
+        ];
+        for (motion, error) in invalid {
+            let message = rejected(&aircraft(motion));
+            assert!(message.contains(error), "{}: {}", motion, message);
+        }
+
+        let moving_clutter = TOML.replace(
+            "intensity = 0.3",
+            "intensity = 0.3\nmotion = { model = \"azimuth_drift\", degrees_per_second = 1.0 }",
+        );
+        assert!(rejected(&moving_clutter).contains("cannot move"));
+    }
+
+    #[test]
+    fn format_comes_from_the_extension() {
+        let format = |name: &str| ScenarioFormat::from_path(Path::new(name));
+        assert_eq!(format("a.toml"), Some(ScenarioFormat::Toml));
+        assert_eq!(format("a.JSON"), Some(ScenarioFormat::Json));
+        assert_eq!(format("a.yml"), Some(ScenarioFormat::Yaml));
+        assert_eq!(format("a.yaml"), Some(ScenarioFormat::Yaml));
+        assert_eq!(format("a.txt"), None);
+        assert_eq!(format("scenario"), None);
+        assert!(Scenario::load("scenario.txt").is_err());
+    }
+
+    #[test]
+    fn bundled_scenarios_load() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+        let mut loaded = 0;
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let scenario = Scenario::load(&path)
+                .unwrap_or_else(|e| panic!("{} does not load: {}", path.display(), e));
+            assert!(
+                !scenario.targets.is_empty(),
+                "{} has no targets",
+                path.display()
+            );
+            loaded += 1;
+        }
+        assert!(loaded >= 3);
+
+        // The bundled default is the built-in one
+        let default = Scenario::load(dir.join("default.json")).unwrap();
+        assert_eq!(default, Scenario::default());
+    }
+
+    #[test]
+    fn lifetime_bounds_are_half_open() {
+        let lifetime = Lifetime {
+            start_s: 5.0,
+            end_s: Some(60.0),
+        };
+        assert!(!lifetime.is_alive(4.9));
+        assert!(lifetime.is_alive(5.0));
+        assert!(lifetime.is_alive(59.9));
+        assert!(!lifetime.is_alive(60.0));
+        assert!(Lifetime::default().is_alive(1.0e9));
+    }
+
+    #[test]
+    fn targets_start_where_the_scenario_puts_them() {
+        let mut scenario = Scenario::parse(TOML, ScenarioFormat::Toml).unwrap();
+        scenario.targets[1].azimuth = -10.0;
+        let targets = scenario.radar_targets();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].velocity, 1.0);
+        assert_eq!(targets[1].velocity, 0.0);
+        assert_eq!(targets[1].azimuth, 350.0);
+        assert!(matches!(targets[1].target_type, TargetType::GroundClutter));
+    }
+}
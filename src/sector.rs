@@ -0,0 +1,458 @@
+use crate::grid::PolarGrid;
+use crate::radar_simulator::RadarSweep;
+use std::error::Error;
+
+// A split point between two neighbouring sectors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SectorBoundary {
+    pub azimuth: f32,         // Boundary azimuth (degrees, 0-360)
+    pub overlap_degrees: f32, // Total overlap shared across this boundary
+}
+
+// Azimuth coverage of one sector, including its overlaps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SectorSpan {
+    pub azimuth_start: f32,    // Start of coverage (degrees, 0-360)
+    pub azimuth_end: f32,      // End of coverage, > 360 when the sector wraps past north
+    pub leading_overlap: f32,  // Degrees shared with the previous sector
+    pub trailing_overlap: f32, // Degrees shared with the next sector
+}
+
+impl SectorSpan {
+    pub fn width(&self) -> f32 {
+        self.azimuth_end - self.azimuth_start
+    }
+
+    pub fn contains(&self, azimuth: f32) -> bool {
+        (azimuth - self.azimuth_start).rem_euclid(360.0) < self.width()
+    }
+}
+
+// Splits a complete 360° sweep into N sectors for downstream processors.
+//
+// Sector `i` covers boundary `i` to boundary `i + 1` (the last sector wraps
+// back to the first boundary), widened by half of each boundary's overlap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectorPlan {
+    boundaries: Vec<SectorBoundary>, // Sorted by azimuth
+}
+
+impl SectorPlan {
+    pub fn new(boundaries: Vec<SectorBoundary>) -> Result<Self, Box<dyn Error>> {
+        if boundaries.is_empty() {
+            return Err("Sector plan needs at least one boundary".into());
+        }
+
+        let mut boundaries: Vec<SectorBoundary> = boundaries
+            .into_iter()
+            .map(|b| SectorBoundary {
+                azimuth: b.azimuth.rem_euclid(360.0),
+                overlap_degrees: b.overlap_degrees,
+            })
+            .collect();
+        boundaries.sort_by(|a, b| a.azimuth.total_cmp(&b.azimuth));
+
+        for boundary in &boundaries {
+            if boundary.overlap_degrees.is_nan() || boundary.overlap_degrees < 0.0 {
+                return Err(format!(
+                    "Invalid overlap {} at boundary {}°",
+                    boundary.overlap_degrees, boundary.azimuth
+                )
+                .into());
+            }
+        }
+
+        let plan = Self { boundaries };
+        for index in 0..plan.len() {
+            let core_width = plan.core_width(index);
+            if core_width <= 0.0 {
+                return Err(format!("Sector {} has zero width", index).into());
+            }
+            let (leading, trailing) = plan.overlaps(index);
+            if (leading + trailing) / 2.0 > core_width {
+                return Err(format!(
+                    "Overlaps around sector {} exceed its {}° width",
+                    index, core_width
+                )
+                .into());
+            }
+        }
+
+        Ok(plan)
+    }
+
+    // `count` equal sectors starting at north, same overlap at every boundary
+    pub fn even(count: usize, overlap_degrees: f32) -> Result<Self, Box<dyn Error>> {
+        if count == 0 {
+            return Err("Sector plan needs at least one sector".into());
+        }
+
+        let step = 360.0 / count as f32;
+        let boundaries = (0..count)
+            .map(|i| SectorBoundary {
+                azimuth: i as f32 * step,
+                overlap_degrees: if count > 1 { overlap_degrees } else { 0.0 },
+            })
+            .collect();
+        Self::new(boundaries)
+    }
+
+    // Original two-client layout: 0-190° and 170-360°, no overlap across north
+    pub fn two_way(overlap_degrees: f32) -> Self {
+        Self {
+            boundaries: vec![
+                SectorBoundary {
+                    azimuth: 0.0,
+                    overlap_degrees: 0.0,
+                },
+                SectorBoundary {
+                    azimuth: 180.0,
+                    overlap_degrees,
+                },
+            ],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.boundaries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boundaries.is_empty()
+    }
+
+    pub fn boundaries(&self) -> &[SectorBoundary] {
+        &self.boundaries
+    }
+
+    pub fn span(&self, index: usize) -> Option<SectorSpan> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let (leading, trailing) = self.overlaps(index);
+        let core_start = self.boundaries[index].azimuth;
+        let azimuth_start = (core_start - leading / 2.0).rem_euclid(360.0);
+        let width = self.core_width(index) + (leading + trailing) / 2.0;
+
+        Some(SectorSpan {
+            azimuth_start,
+            azimuth_end: azimuth_start + width.min(360.0),
+            leading_overlap: leading,
+            trailing_overlap: trailing,
+        })
+    }
+
+    pub fn spans(&self) -> Vec<SectorSpan> {
+        (0..self.len()).filter_map(|i| self.span(i)).collect()
+    }
+
+    // Cut one sector out of a complete sweep, wrapping across 0° if needed
+    pub fn extract(&self, complete_sweep: &RadarSweep, index: usize) -> Option<RadarSweep> {
+        let span = self.span(index)?;
+        let azimuth_count = complete_sweep.azimuth_count();
+        if azimuth_count == 0 {
+            return None;
+        }
+
+        let bins_per_degree = azimuth_count as f32 / 360.0;
+        let leading_bins = (span.leading_overlap * bins_per_degree).round() as usize;
+        let trailing_bins = (span.trailing_overlap * bins_per_degree).round() as usize;
+
+        // Sector rows of every field, then its leading overlap rows followed
+        // by its trailing overlap rows
+        let fields = complete_sweep.map_fields(|field| {
+            let rows = azimuth_rows(&field.data, span.azimuth_start, span.width());
+            let bin_count = rows.azimuth_count();
+            let mut overlap_region = rows.slice_rows(0..leading_bins);
+            overlap_region
+                .append(&rows.slice_rows(bin_count.saturating_sub(trailing_bins)..bin_count));
+            (rows, overlap_region)
+        });
+
+        Some(RadarSweep {
+            timestamp: complete_sweep.timestamp, // Same timestamp - critical for merging
+            sequence_id: complete_sweep.sequence_id, // Same sequence - critical for merging
+            volume_id: complete_sweep.volume_id,
+            tilt: complete_sweep.tilt,
+            elevation_deg: complete_sweep.elevation_deg,
+            azimuth_start: span.azimuth_start,
+            azimuth_end: span.azimuth_end,
+            range_bins: complete_sweep.range_bins.clone(),
+            fields,
+            client_id: index,
+        })
+    }
+
+    fn core_width(&self, index: usize) -> f32 {
+        if self.len() == 1 {
+            return 360.0;
+        }
+        let start = self.boundaries[index].azimuth;
+        let end = self.boundaries[(index + 1) % self.len()].azimuth;
+        (end - start).rem_euclid(360.0)
+    }
+
+    fn overlaps(&self, index: usize) -> (f32, f32) {
+        if self.len() == 1 {
+            return (0.0, 0.0);
+        }
+        let leading = self.boundaries[index].overlap_degrees;
+        let trailing = self.boundaries[(index + 1) % self.len()].overlap_degrees;
+        (leading, trailing)
+    }
+}
+
+// Rows of the field `name` covering `width` degrees from `azimuth_start`,
+// wrapping across 0°
+pub fn extract_azimuth_rows(
+    complete_sweep: &RadarSweep,
+    name: &str,
+    azimuth_start: f32,
+    width: f32,
+) -> Option<PolarGrid> {
+    let field = complete_sweep.field(name)?;
+    Some(azimuth_rows(&field.data, azimuth_start, width))
+}
+
+// Same for any grid covering the full 360°
+pub fn azimuth_rows(grid: &PolarGrid, azimuth_start: f32, width: f32) -> PolarGrid {
+    let azimuth_count = grid.azimuth_count();
+    if azimuth_count == 0 {
+        return PolarGrid::new(0, grid.range_count());
+    }
+
+    let bins_per_degree = azimuth_count as f32 / 360.0;
+    let start_idx = (azimuth_start.rem_euclid(360.0) * bins_per_degree).round() as usize;
+    let bin_count = ((width * bins_per_degree).round() as usize).min(azimuth_count);
+
+    grid.azimuth_window(start_idx, bin_count)
+}
+
+impl Default for SectorPlan {
+    fn default() -> Self {
+        Self::two_way(crate::radar_simulator::OVERLAP_DEGREES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::SweepField;
+    use crate::power::SampleUnit;
+
+    const GATES: usize = 3;
+
+    // Complete sweep whose every sample holds its own azimuth row number
+    fn numbered_sweep(azimuth_count: usize) -> RadarSweep {
+        let values = (0..azimuth_count)
+            .flat_map(|row| [row as f32; GATES])
+            .collect();
+        let data = PolarGrid::from_values(azimuth_count, GATES, values).unwrap();
+        RadarSweep {
+            timestamp: 1,
+            sequence_id: 2,
+            volume_id: 0,
+            tilt: 0,
+            elevation_deg: 0.5,
+            azimuth_start: 0.0,
+            azimuth_end: 360.0,
+            range_bins: vec![0.0, 0.25, 0.5],
+            fields: vec![SweepField::new("intensity", SampleUnit::Intensity, data)],
+            client_id: 0,
+        }
+    }
+
+    fn row_numbers(grid: &PolarGrid) -> Vec<usize> {
+        grid.rows().map(|row| row[0] as usize).collect()
+    }
+
+    fn assert_span(span: SectorSpan, start: f32, end: f32, leading: f32, trailing: f32) {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-3;
+        assert!(
+            close(span.azimuth_start, start)
+                && close(span.azimuth_end, end)
+                && close(span.leading_overlap, leading)
+                && close(span.trailing_overlap, trailing),
+            "{:?} is not {}-{}° ({}/{})",
+            span,
+            start,
+            end,
+            leading,
+            trailing
+        );
+    }
+
+    #[test]
+    fn one_sector_is_the_whole_circle() {
+        let plan = SectorPlan::even(1, 10.0).unwrap();
+        assert_eq!(plan.len(), 1);
+        assert_span(plan.span(0).unwrap(), 0.0, 360.0, 0.0, 0.0);
+        assert!(plan.span(1).is_none());
+
+        let sector = plan.extract(&numbered_sweep(360), 0).unwrap();
+        assert_eq!(
+            row_numbers(&sector.fields[0].data),
+            (0..360).collect::<Vec<_>>()
+        );
+        assert_eq!(sector.overlap_azimuth_count(), 0);
+    }
+
+    #[test]
+    fn two_even_sectors_share_both_boundaries() {
+        let plan = SectorPlan::even(2, 10.0).unwrap();
+        assert_span(plan.span(0).unwrap(), 355.0, 545.0, 10.0, 10.0);
+        assert_span(plan.span(1).unwrap(), 175.0, 365.0, 10.0, 10.0);
+    }
+
+    #[test]
+    fn three_even_sectors() {
+        let plan = SectorPlan::even(3, 6.0).unwrap();
+        assert_span(plan.span(0).unwrap(), 357.0, 483.0, 6.0, 6.0);
+        assert_span(plan.span(1).unwrap(), 117.0, 243.0, 6.0, 6.0);
+        assert_span(plan.span(2).unwrap(), 237.0, 363.0, 6.0, 6.0);
+    }
+
+    #[test]
+    fn many_sectors_cover_every_azimuth() {
+        let plan = SectorPlan::even(12, 4.0).unwrap();
+        let spans = plan.spans();
+        assert_eq!(spans.len(), 12);
+        for span in &spans {
+            assert!((span.width() - 34.0).abs() < 1e-3);
+        }
+
+        // Every azimuth is in one sector, or in two within 2° of a boundary
+        for tenth in 0..3600 {
+            let azimuth = (tenth as f32 + 0.5) / 10.0; // Clear of the span edges
+            let covering = spans.iter().filter(|span| span.contains(azimuth)).count();
+            let to_boundary = (azimuth % 30.0).min(30.0 - azimuth % 30.0);
+            let expected = if to_boundary < 2.0 { 2 } else { 1 };
+            assert_eq!(covering, expected, "azimuth {}°", azimuth);
+        }
+
+        let sweep = numbered_sweep(360);
+        for index in 0..12 {
+            let sector = plan.extract(&sweep, index).unwrap();
+            assert_eq!(sector.azimuth_count(), 34);
+            assert_eq!(sector.overlap_azimuth_count(), 8);
+        }
+    }
+
+    #[test]
+    fn legacy_split_is_0_190_and_170_360() {
+        let plan = SectorPlan::two_way(20.0);
+        assert_eq!(plan, SectorPlan::default());
+        assert_span(plan.span(0).unwrap(), 0.0, 190.0, 0.0, 20.0);
+        assert_span(plan.span(1).unwrap(), 170.0, 360.0, 20.0, 0.0);
+
+        let sweep = numbered_sweep(360);
+        let first = plan.extract(&sweep, 0).unwrap();
+        let second = plan.extract(&sweep, 1).unwrap();
+        assert_eq!(
+            row_numbers(&first.fields[0].data),
+            (0..190).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            row_numbers(&second.fields[0].data),
+            (170..360).collect::<Vec<_>>()
+        );
+        assert_eq!((first.client_id, second.client_id), (0, 1));
+        assert_eq!(first.sequence_id, sweep.sequence_id);
+
+        // Both halves carry the same 20° of overlap rows
+        let overlap: Vec<usize> = (170..190).collect();
+        assert_eq!(row_numbers(&first.fields[0].overlap_region), overlap);
+        assert_eq!(row_numbers(&second.fields[0].overlap_region), overlap);
+    }
+
+    #[test]
+    fn sectors_wrap_across_north() {
+        let plan = SectorPlan::even(2, 10.0).unwrap();
+        let sector = plan.extract(&numbered_sweep(360), 0).unwrap();
+
+        let expected: Vec<usize> = (355..360).chain(0..185).collect();
+        assert_eq!(row_numbers(&sector.fields[0].data), expected);
+        let overlap: Vec<usize> = (355..360).chain(0..5).chain(175..185).collect();
+        assert_eq!(row_numbers(&sector.fields[0].overlap_region), overlap);
+
+        let span = plan.span(0).unwrap();
+        assert!(span.contains(359.0) && span.contains(0.0) && span.contains(184.0));
+        assert!(!span.contains(185.5) && !span.contains(354.0));
+    }
+
+    #[test]
+    fn finer_azimuth_resolution_scales_the_rows() {
+        // 0.5° radials: every degree of the plan is two rows
+        let plan = SectorPlan::two_way(20.0);
+        let sweep = numbered_sweep(720);
+        let first = plan.extract(&sweep, 0).unwrap();
+        let second = plan.extract(&sweep, 1).unwrap();
+        assert_eq!(
+            row_numbers(&first.fields[0].data),
+            (0..380).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            row_numbers(&second.fields[0].data),
+            (340..720).collect::<Vec<_>>()
+        );
+        assert_eq!(first.overlap_azimuth_count(), 40);
+
+        // 1.5° radials with a plan on 30° boundaries
+        let plan = SectorPlan::even(4, 6.0).unwrap();
+        let sector = plan.extract(&numbered_sweep(240), 1).unwrap();
+        assert_eq!(
+            row_numbers(&sector.fields[0].data),
+            (58..122).collect::<Vec<_>>()
+        );
+        assert_eq!(sector.overlap_azimuth_count(), 8);
+    }
+
+    #[test]
+    fn uneven_boundaries_keep_their_own_overlaps() {
+        let plan = SectorPlan::new(vec![
+            SectorBoundary {
+                azimuth: 90.0,
+                overlap_degrees: 10.0,
+            },
+            SectorBoundary {
+                azimuth: -30.0, // Same as 330°
+                overlap_degrees: 4.0,
+            },
+        ])
+        .unwrap();
+        assert_eq!(plan.boundaries()[0].azimuth, 90.0);
+        assert_eq!(plan.boundaries()[1].azimuth, 330.0);
+        assert_span(plan.span(0).unwrap(), 85.0, 332.0, 10.0, 4.0);
+        assert_span(plan.span(1).unwrap(), 328.0, 455.0, 4.0, 10.0);
+    }
+
+    #[test]
+    fn invalid_plans_are_rejected() {
+        assert!(SectorPlan::new(Vec::new()).is_err());
+        assert!(SectorPlan::even(0, 10.0).is_err());
+
+        let boundary = |azimuth, overlap_degrees| SectorBoundary {
+            azimuth,
+            overlap_degrees,
+        };
+        // Two boundaries at the same azimuth leave a sector without width
+        assert!(SectorPlan::new(vec![boundary(10.0, 0.0), boundary(370.0, 0.0)]).is_err());
+        assert!(SectorPlan::new(vec![boundary(0.0, -1.0), boundary(180.0, 0.0)]).is_err());
+        assert!(SectorPlan::new(vec![boundary(0.0, f32::NAN), boundary(180.0, 0.0)]).is_err());
+        // Overlaps wider than the 20° sector between them
+        assert!(SectorPlan::new(vec![
+            boundary(0.0, 30.0),
+            boundary(20.0, 30.0),
+            boundary(180.0, 0.0)
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn empty_sweep_has_no_sectors() {
+        let mut sweep = numbered_sweep(0);
+        sweep.fields[0].data = PolarGrid::new(0, GATES);
+        assert!(SectorPlan::default().extract(&sweep, 0).is_none());
+    }
+}
@@ -0,0 +1,399 @@
+use crate::doppler::{SPECTRUM_WIDTH, VELOCITY};
+use crate::field::{is_missing, mean_present, SweepField, MISSING};
+use crate::grid::PolarGrid;
+use crate::quantize::SampleFormat;
+use crate::radar_simulator::{RadarSweep, Radial};
+use crate::sector::azimuth_rows;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// Azimuth window requested by a client, may wrap across 0°
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct AzimuthWindow {
+    pub azimuth_start: f32, // Degrees, 0-360
+    pub azimuth_end: f32,   // Degrees, 0-360 (smaller than start when wrapping)
+}
+
+impl AzimuthWindow {
+    pub fn width(&self) -> f32 {
+        let width = (self.azimuth_end - self.azimuth_start).rem_euclid(360.0);
+        if width == 0.0 {
+            360.0
+        } else {
+            width
+        }
+    }
+
+    pub fn contains(&self, azimuth: f32) -> bool {
+        (azimuth - self.azimuth_start).rem_euclid(360.0) < self.width()
+    }
+}
+
+// What a subscribed client is streamed, chosen with `SUBSCRIBE mode=<sweep|radial>`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamMode {
+    #[default]
+    Sweep, // One frame per sweep at the server's data rate
+    Radial, // One frame per radial as the antenna turns
+}
+
+impl fmt::Display for StreamMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamMode::Sweep => write!(f, "sweep"),
+            StreamMode::Radial => write!(f, "radial"),
+        }
+    }
+}
+
+impl FromStr for StreamMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "sweep" => Ok(StreamMode::Sweep),
+            "radial" => Ok(StreamMode::Radial),
+            _ => Err(format!("Unknown stream mode '{}'", value)),
+        }
+    }
+}
+
+// Per-client stream parameters set through SUBSCRIBE.
+// `None` fields keep their current value when a subscription is updated.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Subscription {
+    pub azimuth: Option<AzimuthWindow>, // `None` means a sector of the server's plan
+    pub max_range_km: Option<f32>,      // Drop range gates beyond this distance
+    pub range_decimation: Option<usize>, // Average every N range gates into one
+    pub rate_hz: Option<f64>,           // Upper bound on sweeps per second
+    pub sample_format: Option<SampleFormat>, // Wire encoding of samples, f32 when unset
+    pub delta_keyframe_interval: Option<u32>, // Send deltas with a keyframe every N sweeps, 0 = off
+    pub stream_mode: Option<StreamMode>, // Whole sweeps when unset
+    pub tilts: Option<Vec<usize>>,      // Tilts of each volume scan to send, empty = all
+}
+
+impl Subscription {
+    // Overwrite the fields that `update` sets
+    pub fn merge(&mut self, update: &Subscription) {
+        if update.azimuth.is_some() {
+            self.azimuth = update.azimuth;
+        }
+        if update.max_range_km.is_some() {
+            self.max_range_km = update.max_range_km;
+        }
+        if update.range_decimation.is_some() {
+            self.range_decimation = update.range_decimation;
+        }
+        if update.rate_hz.is_some() {
+            self.rate_hz = update.rate_hz;
+        }
+        if update.sample_format.is_some() {
+            self.sample_format = update.sample_format;
+        }
+        if update.delta_keyframe_interval.is_some() {
+            self.delta_keyframe_interval = update.delta_keyframe_interval;
+        }
+        if update.stream_mode.is_some() {
+            self.stream_mode = update.stream_mode;
+        }
+        if update.tilts.is_some() {
+            self.tilts = update.tilts.clone();
+        }
+    }
+
+    pub fn stream_mode(&self) -> StreamMode {
+        self.stream_mode.unwrap_or_default()
+    }
+
+    pub fn sample_format(&self) -> SampleFormat {
+        self.sample_format.unwrap_or_default()
+    }
+
+    // Keyframe interval when delta streaming is on
+    pub fn delta_keyframe_interval(&self) -> Option<u32> {
+        self.delta_keyframe_interval
+            .filter(|&interval| interval > 0)
+    }
+
+    // Whether sweeps of tilt `tilt` are sent to this client
+    pub fn wants_tilt(&self, tilt: usize) -> bool {
+        self.tilts
+            .as_ref()
+            .is_none_or(|tilts| tilts.is_empty() || tilts.contains(&tilt))
+    }
+
+    // Whether the client picks its own azimuth window instead of a plan sector
+    pub fn is_custom_window(&self) -> bool {
+        self.azimuth.is_some()
+    }
+
+    // Build the sweep this client asked for. `sweep` is the complete sweep for
+    // custom windows, or the client's already extracted sector otherwise.
+    pub fn apply(&self, sweep: &RadarSweep) -> RadarSweep {
+        let (azimuth_start, azimuth_end) = match self.azimuth {
+            Some(window) => (window.azimuth_start, window.azimuth_start + window.width()),
+            None => (sweep.azimuth_start, sweep.azimuth_end),
+        };
+
+        let gate_count = match self.max_range_km {
+            Some(max_range_km) => sweep
+                .range_bins
+                .iter()
+                .take_while(|&&range_km| range_km <= max_range_km)
+                .count(),
+            None => sweep.range_bins.len(),
+        };
+        let factor = self.range_decimation.unwrap_or(1).max(1);
+
+        // Every field is windowed alike. Decimation averages samples, except for
+        // the Doppler moments: an echo at +Nyquist next to one at -Nyquist would
+        // average to a still target, so each decimated gate keeps the velocity
+        // and width measured where the echo is strongest.
+        let window = |field: &SweepField| match self.azimuth {
+            Some(window) => (
+                azimuth_rows(&field.data, window.azimuth_start, window.width()),
+                PolarGrid::new(0, field.data.range_count()),
+            ),
+            None => (field.data.clone(), field.overlap_region.clone()),
+        };
+        let strength = sweep.primary_field().map(window);
+        let fields = sweep.map_fields(|field| {
+            let (data, overlap_region) = window(field);
+            match &strength {
+                Some((strength_data, strength_overlap))
+                    if matches!(field.name.as_str(), VELOCITY | SPECTRUM_WIDTH) =>
+                {
+                    (
+                        decimate_grid_by_strength(data, strength_data, gate_count, factor),
+                        decimate_grid_by_strength(
+                            overlap_region,
+                            strength_overlap,
+                            gate_count,
+                            factor,
+                        ),
+                    )
+                }
+                _ => (
+                    decimate_grid(data, gate_count, factor),
+                    decimate_grid(overlap_region, gate_count, factor),
+                ),
+            }
+        });
+
+        RadarSweep {
+            timestamp: sweep.timestamp,
+            sequence_id: sweep.sequence_id,
+            volume_id: sweep.volume_id,
+            tilt: sweep.tilt,
+            elevation_deg: sweep.elevation_deg,
+            azimuth_start,
+            azimuth_end,
+            range_bins: decimate_row(&sweep.range_bins[..gate_count], factor),
+            fields,
+            client_id: sweep.client_id,
+        }
+    }
+
+    // Range limit and decimation for one radial. Radials always carry f32
+    // samples; the azimuth window is checked by the caller.
+    pub fn apply_radial(&self, radial: &Radial) -> Radial {
+        let gate_count = match self.max_range_km {
+            Some(max_range_km) => (0..radial.samples.len())
+                .take_while(|&gate| {
+                    radial.first_gate_km + gate as f32 * radial.gate_spacing_km <= max_range_km
+                })
+                .count(),
+            None => radial.samples.len(),
+        };
+        let factor = self.range_decimation.unwrap_or(1).max(1);
+        let first_chunk = factor.min(gate_count).max(1); // Gates averaged into the first sample
+
+        Radial {
+            timestamp: radial.timestamp,
+            sequence_id: radial.sequence_id,
+            azimuth: radial.azimuth,
+            tilt: radial.tilt,
+            elevation_deg: radial.elevation_deg,
+            first_gate_km: radial.first_gate_km
+                + radial.gate_spacing_km * (first_chunk - 1) as f32 / 2.0,
+            gate_spacing_km: radial.gate_spacing_km * factor as f32,
+            samples: decimate_row(&radial.samples[..gate_count], factor),
+        }
+    }
+}
+
+fn decimate_grid(grid: PolarGrid, gate_count: usize, factor: usize) -> PolarGrid {
+    let gate_count = gate_count.min(grid.range_count());
+    if factor == 1 && gate_count == grid.range_count() {
+        return grid; // Nothing to cut or average
+    }
+    let mut decimated = PolarGrid::new(grid.azimuth_count(), gate_count.div_ceil(factor));
+    for (row, decimated_row) in grid.rows().zip(decimated.rows_mut()) {
+        decimated_row.copy_from_slice(&decimate_row(&row[..gate_count], factor));
+    }
+    decimated
+}
+
+// Like `decimate_grid`, but every decimated gate takes the sample where
+// `strength`, the echo strength over the same gates, is highest
+fn decimate_grid_by_strength(
+    grid: PolarGrid,
+    strength: &PolarGrid,
+    gate_count: usize,
+    factor: usize,
+) -> PolarGrid {
+    let gate_count = gate_count.min(grid.range_count());
+    if factor == 1 && gate_count == grid.range_count() {
+        return grid; // Nothing to cut or pick
+    }
+    let mut decimated = PolarGrid::new(grid.azimuth_count(), gate_count.div_ceil(factor));
+    for ((row, strength_row), decimated_row) in
+        grid.rows().zip(strength.rows()).zip(decimated.rows_mut())
+    {
+        let chunks = row[..gate_count]
+            .chunks(factor)
+            .zip(strength_row[..gate_count].chunks(factor));
+        for ((chunk, strength_chunk), sample) in chunks.zip(decimated_row) {
+            *sample = strongest_present(chunk, strength_chunk);
+        }
+    }
+    decimated
+}
+
+// Sample at the strongest echo, missing samples left out
+fn strongest_present(samples: &[f32], strength: &[f32]) -> f32 {
+    samples
+        .iter()
+        .zip(strength)
+        .filter(|(&sample, _)| !is_missing(sample))
+        .map(|(&sample, &strength)| {
+            let strength = if is_missing(strength) {
+                f32::NEG_INFINITY
+            } else {
+                strength
+            };
+            (sample, strength)
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map_or(MISSING, |(sample, _)| sample)
+}
+
+fn decimate_row(row: &[f32], factor: usize) -> Vec<f32> {
+    if factor == 1 {
+        return row.to_vec();
+    }
+    row.chunks(factor)
+        .map(|chunk| mean_present(chunk.iter().copied())) // Missing samples are left out
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::REFLECTIVITY;
+    use crate::power::SampleUnit;
+
+    fn doppler_sweep(reflectivity: Vec<f32>, velocity: Vec<f32>) -> RadarSweep {
+        let gates = reflectivity.len();
+        let field = |name: &str, units, values| {
+            SweepField::new(
+                name,
+                units,
+                PolarGrid::from_values(1, gates, values).unwrap(),
+            )
+        };
+        RadarSweep {
+            timestamp: 0,
+            sequence_id: 0,
+            volume_id: 0,
+            tilt: 0,
+            elevation_deg: 0.5,
+            azimuth_start: 0.0,
+            azimuth_end: 1.0,
+            range_bins: (0..gates).map(|gate| gate as f32).collect(),
+            fields: vec![
+                field(REFLECTIVITY, SampleUnit::Dbz, reflectivity),
+                field(VELOCITY, SampleUnit::MetersPerSecond, velocity.clone()),
+                field(SPECTRUM_WIDTH, SampleUnit::MetersPerSecond, velocity),
+            ],
+            client_id: 0,
+        }
+    }
+
+    fn decimated(sweep: &RadarSweep, name: &str, factor: usize) -> Vec<f32> {
+        let subscription = Subscription {
+            range_decimation: Some(factor),
+            ..Subscription::default()
+        };
+        subscription
+            .apply(sweep)
+            .field(name)
+            .unwrap()
+            .data
+            .values()
+            .to_vec()
+    }
+
+    #[test]
+    fn decimation_averages_echo_strength() {
+        let sweep = doppler_sweep(vec![10.0, 40.0, 20.0, MISSING, 6.0], vec![0.0; 5]);
+        assert_eq!(decimated(&sweep, REFLECTIVITY, 2), vec![25.0, 20.0, 6.0]);
+    }
+
+    #[test]
+    fn aliased_velocities_are_not_averaged() {
+        // Echoes either side of a 25 m/s Nyquist velocity: their mean, 0 m/s, was never measured
+        let sweep = doppler_sweep(vec![10.0, 40.0, 30.0, 20.0], vec![24.5, -24.8, 24.9, -24.6]);
+        assert_eq!(decimated(&sweep, VELOCITY, 2), vec![-24.8, 24.9]);
+        assert_eq!(decimated(&sweep, SPECTRUM_WIDTH, 2), vec![-24.8, 24.9]);
+        assert_eq!(decimated(&sweep, VELOCITY, 4), vec![-24.8]);
+    }
+
+    #[test]
+    fn censored_velocities_are_skipped() {
+        let sweep = doppler_sweep(
+            vec![10.0, 40.0, MISSING, 5.0, 1.0, 2.0],
+            vec![3.0, MISSING, 7.0, 4.0, MISSING, MISSING],
+        );
+        // Strongest echo censored: the next strongest with a velocity wins
+        assert_eq!(decimated(&sweep, VELOCITY, 2)[0], 3.0);
+        // A gate without echo strength loses to one with
+        assert_eq!(decimated(&sweep, VELOCITY, 2)[1], 4.0);
+        assert!(is_missing(decimated(&sweep, VELOCITY, 2)[2]));
+    }
+
+    #[test]
+    fn velocities_survive_without_decimation() {
+        let velocity = vec![24.5, -24.8, MISSING, 1.0];
+        let sweep = doppler_sweep(vec![1.0; 4], velocity.clone());
+        let kept = decimated(&sweep, VELOCITY, 1);
+        assert_eq!(kept[..2], velocity[..2]);
+        assert!(is_missing(kept[2]));
+        assert_eq!(kept[3], 1.0);
+    }
+
+    #[test]
+    fn tilt_choice_is_kept_until_changed() {
+        let mut subscription = Subscription::default();
+        assert!(subscription.wants_tilt(0) && subscription.wants_tilt(4));
+
+        subscription.merge(&Subscription {
+            tilts: Some(vec![1, 3]),
+            ..Subscription::default()
+        });
+        assert!(!subscription.wants_tilt(0) && subscription.wants_tilt(3));
+
+        // Other keys leave the tilts alone, `tilts=all` resets them
+        subscription.merge(&Subscription {
+            rate_hz: Some(2.0),
+            ..Subscription::default()
+        });
+        assert!(!subscription.wants_tilt(0));
+        subscription.merge(&Subscription {
+            tilts: Some(Vec::new()),
+            ..Subscription::default()
+        });
+        assert!(subscription.wants_tilt(0));
+    }
+}
@@ -1,29 +1,192 @@
-use crate::radar_simulator::{extract_client_portion, RadarSimulator, RadarSweep};
-use std::collections::HashMap;
+use crate::command::{Ack, Command, CommandError, CommandLineCodec};
+use crate::compression::{Compression, CompressionCounters, CompressionReport};
+use crate::config::RadarConfig;
+use crate::delta::{DeltaEncoder, DeltaSweep};
+use crate::fault::{FaultAction, FaultConfig, FaultInjector, FrameDelay};
+use crate::protocol::{
+    encode_compressed_payload, encode_payload, Message, MessageType, RadarCodec, ServerStatus,
+    StreamHeader, HEADER_BYTES,
+};
+use crate::quantize::{QuantizedSweep, SampleFormat};
+use crate::radar_simulator::{RadarSimulator, RadarSweep, Radial, DEFAULT_ROTATION_RPM};
+use crate::scenario::Scenario;
+use crate::sector::{SectorPlan, SectorSpan};
+use crate::subscription::{StreamMode, Subscription};
+use crate::writer::{ClientWriter, WriterConfig};
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::io;
 use std::sync::{
-    atomic::{AtomicUsize, Ordering},
+    atomic::{AtomicU64, AtomicUsize, Ordering},
     Arc,
 };
-use std::time::Duration;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::time::{Duration, Instant};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::spawn;
 use tokio::sync::Mutex;
 use tokio::time::interval;
+use tokio_util::codec::{Encoder, FramedRead};
+
+// Client connection manager: each connection's socket is owned by its writer task
+pub type ClientConnections = Arc<Mutex<HashMap<usize, ClientWriter>>>;
+pub type ClientSessions = Arc<Mutex<HashMap<usize, ClientSession>>>; // Command state per client
+
+// A client's delta encoder, shared with the broadcaster while it encodes
+// outside the session lock
+pub type SharedDeltaEncoder = Arc<std::sync::Mutex<DeltaEncoder>>;
+
+// The one simulated radar behind both streams, so sweeps and radials show
+// the same targets. Only the antenna task moves time and targets forward.
+pub type SharedSimulator = Arc<std::sync::Mutex<RadarSimulator>>;
+
+// How often the antenna position is advanced in radial streaming mode
+const RADIAL_TICK: Duration = Duration::from_millis(20);
+
+// What a client asked for through its commands
+#[derive(Debug, Clone, Default)]
+pub struct ClientSession {
+    pub port: u16,                         // Listener port the client connected to
+    pub streaming: bool,                   // Set by SUBSCRIBE, cleared by UNSUBSCRIBE
+    pub subscription: Subscription,        // Window, range, decimation and rate
+    pub sector: Option<usize>, // Port's sector or SELECT_SECTOR choice, `None` means any free sector
+    pub last_sent: Option<Instant>, // When this client last received a sweep
+    pub faults: Option<FaultInjector>, // Injected latency/loss, test harnesses only
+    pub compression: Compression, // Set by COMPRESS, applies to sweep frames
+    pub delta: Option<SharedDeltaEncoder>, // Delta stream state when `SUBSCRIBE delta=N` is on
+}
+
+impl ClientSession {
+    pub fn new(port: u16, sector: Option<usize>) -> Self {
+        Self {
+            port,
+            sector,
+            ..Self::default()
+        }
+    }
+
+    // Whether enough time has passed since the last sweep for the requested rate
+    pub fn is_due(&self, now: Instant) -> bool {
+        match (self.subscription.rate_hz, self.last_sent) {
+            (Some(rate_hz), Some(last_sent)) => {
+                now.duration_since(last_sent).as_secs_f64() >= 1.0 / rate_hz
+            }
+            _ => true,
+        }
+    }
+}
 
-// Client connection manager
-use tokio::net::tcp::OwnedWriteHalf;
-pub type ClientConnections = Arc<Mutex<HashMap<usize, OwnedWriteHalf>>>;
-pub type ReadyClients = Arc<Mutex<HashMap<usize, bool>>>; // Track which clients are ready for data
+// Counters shared by the listeners and the broadcaster
+#[derive(Debug, Default)]
+pub struct ServerStats {
+    pub last_sequence_id: AtomicU64,
+    pub compression: [CompressionCounters; 3], // Indexed by `Compression as usize`
+}
+
+impl ServerStats {
+    pub fn compression_counters(&self, compression: Compression) -> &CompressionCounters {
+        &self.compression[compression as usize]
+    }
+
+    // Reports for every codec that has encoded at least one sweep
+    pub fn compression_reports(&self) -> Vec<CompressionReport> {
+        Compression::ALL
+            .iter()
+            .map(|&compression| self.compression_counters(compression).report(compression))
+            .filter(|report| report.frames > 0)
+            .collect()
+    }
+}
+
+// How sector clients are grouped before data is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupPolicy {
+    // Every subscribed client is served on its own, several may share a sector
+    #[default]
+    Independent,
+    // Nothing is sent until each sector has exactly one client, so the
+    // portions of a sweep can be merged back together downstream
+    Synchronized,
+}
+
+// Which sector clients a broadcast tick serves under a group policy
+struct SectorGroup {
+    group_policy: GroupPolicy,
+    sector_count: usize,
+    complete: bool, // Every sector had a client on the previous tick
+}
+
+// Outcome of one broadcast tick for the sector clients
+#[derive(Debug)]
+struct GroupTick {
+    sector_clients: Vec<(usize, usize)>, // (client_id, sector_index) served this tick
+    covered_sectors: usize,
+    restart_sequence: bool, // The synchronized group has just become complete
+}
+
+impl SectorGroup {
+    fn new(group_policy: GroupPolicy, sector_count: usize) -> Self {
+        Self {
+            group_policy,
+            sector_count,
+            complete: false,
+        }
+    }
+
+    fn tick(
+        &mut self,
+        sector_ids: &[usize],
+        sessions_map: &HashMap<usize, ClientSession>,
+    ) -> GroupTick {
+        let sector_clients = assign_sectors(
+            self.group_policy,
+            sector_ids,
+            sessions_map,
+            self.sector_count,
+        );
+        let covered_sectors = sector_clients
+            .iter()
+            .map(|&(_, sector)| sector)
+            .collect::<HashSet<usize>>()
+            .len();
+        let complete = covered_sectors == self.sector_count;
+
+        // Reset sequence counter when the group becomes complete for synchronization
+        let restart_sequence =
+            self.group_policy == GroupPolicy::Synchronized && complete && !self.complete;
+        self.complete = complete;
+
+        // A synchronized group only gets sectors once every sector has a client
+        // for proper merging
+        let serve = match self.group_policy {
+            GroupPolicy::Independent => true,
+            GroupPolicy::Synchronized => complete,
+        };
+        GroupTick {
+            sector_clients: if serve { sector_clients } else { Vec::new() },
+            covered_sectors,
+            restart_sequence,
+        }
+    }
+}
 
 pub struct RadarTcpServer {
     pub ports: Vec<u16>,
     pub data_rate_hz: f64,
+    pub sector_plan: SectorPlan,
+    pub port_sectors: HashMap<u16, usize>, // Listener port -> sector index
+    pub group_policy: GroupPolicy,
+    pub port_faults: HashMap<u16, FaultConfig>, // Listener port -> injected faults
+    pub writer_config: WriterConfig,            // Per-client queue size and overflow policy
+    pub rotation_rpm: f64,                      // Antenna speed for `mode=radial` clients
+    pub radar_config: RadarConfig,              // Geometry, sent in every stream header
+    pub scenario: Scenario,                     // Targets, weather and noise to simulate
     pub client_counter: Arc<AtomicUsize>,
     pub clients: ClientConnections,
-    pub ready_clients: ReadyClients,
+    pub sessions: ClientSessions,
+    pub stats: Arc<ServerStats>, // Shared counters reported by GET_STATUS
 }
 
 impl RadarTcpServer {
@@ -31,42 +194,185 @@ impl RadarTcpServer {
         Self {
             ports,
             data_rate_hz,
+            sector_plan: SectorPlan::default(),
+            port_sectors: HashMap::new(),
+            group_policy: GroupPolicy::default(),
+            port_faults: HashMap::new(),
+            writer_config: WriterConfig::default(),
+            rotation_rpm: DEFAULT_ROTATION_RPM,
+            radar_config: RadarConfig::default(),
+            scenario: Scenario::default(),
             client_counter: Arc::new(AtomicUsize::new(0)),
             clients: Arc::new(Mutex::new(HashMap::new())),
-            ready_clients: Arc::new(Mutex::new(HashMap::new())),
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            stats: Arc::new(ServerStats::default()),
+        }
+    }
+
+    pub fn with_sector_plan(mut self, sector_plan: SectorPlan) -> Self {
+        self.sector_plan = sector_plan;
+        self
+    }
+
+    pub fn with_group_policy(mut self, group_policy: GroupPolicy) -> Self {
+        self.group_policy = group_policy;
+        self
+    }
+
+    // Every connection on `port` gets its own injector with this configuration
+    pub fn with_port_faults(mut self, port: u16, faults: FaultConfig) -> Self {
+        self.port_faults.insert(port, faults);
+        self
+    }
+
+    pub fn with_writer_config(mut self, writer_config: WriterConfig) -> Self {
+        self.writer_config = writer_config;
+        self
+    }
+
+    pub fn with_rotation_rpm(mut self, rotation_rpm: f64) -> Self {
+        self.rotation_rpm = rotation_rpm;
+        self
+    }
+
+    // Also resets the sector plan to the two-way split with the config's
+    // overlap; call `with_sector_plan` afterwards for another layout
+    pub fn with_radar_config(mut self, radar_config: RadarConfig) -> Self {
+        self.radar_config = radar_config;
+        self.sector_plan = radar_config.sector_plan();
+        self
+    }
+
+    pub fn with_scenario(mut self, scenario: Scenario) -> Self {
+        self.scenario = scenario;
+        self
+    }
+
+    // Simulator both broadcasters share, starting from the scenario's initial state
+    fn simulator(&self) -> RadarSimulator {
+        RadarSimulator::from_scenario(&self.scenario)
+            .with_config(self.radar_config)
+            .with_rotation_rpm(self.rotation_rpm)
+    }
+
+    // Clients connecting on `port` always receive `sector`
+    pub fn with_port_sector(mut self, port: u16, sector: usize) -> Self {
+        self.port_sectors.insert(port, sector);
+        self
+    }
+
+    // Sector for clients on `port`: the explicit mapping, otherwise the
+    // port's position in `ports` when the plan has that many sectors
+    pub fn sector_for_port(&self, port: u16) -> Option<usize> {
+        if let Some(&sector) = self.port_sectors.get(&port) {
+            return Some(sector);
         }
+        self.ports
+            .iter()
+            .position(|&p| p == port)
+            .filter(|&index| index < self.sector_plan.len())
     }
 
     pub async fn start(&self) -> io::Result<()> {
         let mut tasks = vec![];
 
+        self.radar_config
+            .validate()
+            .and_then(|_| self.scenario.validate())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+        let stream_header = StreamHeader {
+            radar: self.radar_config,
+            data_rate_hz: self.data_rate_hz,
+            rotation_rpm: self.rotation_rpm,
+            units: self.simulator().sample_unit(),
+            doppler: self.scenario.doppler,
+            scan: self.scenario.scan.clone(),
+        };
+
+        for (&port, &sector) in &self.port_sectors {
+            if sector >= self.sector_plan.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Port {} is mapped to sector {} but the plan has {} sectors",
+                        port,
+                        sector,
+                        self.sector_plan.len()
+                    ),
+                ));
+            }
+        }
+
+        for (&port, faults) in &self.port_faults {
+            faults.validate().map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid faults for port {}: {}", port, e),
+                )
+            })?;
+        }
+
         // Start servers on each port
         for port in &self.ports {
             let counter = Arc::clone(&self.client_counter);
             let clients_clone = Arc::clone(&self.clients);
-            let ready_clients_clone = Arc::clone(&self.ready_clients);
+            let sessions_clone = Arc::clone(&self.sessions);
+            let stats_clone = Arc::clone(&self.stats);
             let port = *port;
-            let task = spawn(start_server_on_port(
+            let settings = PortSettings {
                 port,
+                sector: self.sector_for_port(port),
+                faults: self.port_faults.get(&port).cloned(),
+                writer_config: self.writer_config,
+                stream_header: stream_header.clone(),
+            };
+            let task = spawn(start_server_on_port(
+                settings,
                 counter,
                 clients_clone,
-                ready_clients_clone,
+                sessions_clone,
+                stats_clone,
+                self.sector_plan.len(),
             ));
             tasks.push(task);
         }
 
+        let simulator: SharedSimulator = Arc::new(std::sync::Mutex::new(self.simulator()));
+
         // Start radar data broadcaster
         let clients_clone = Arc::clone(&self.clients);
-        let ready_clients_clone = Arc::clone(&self.ready_clients);
+        let sessions_clone = Arc::clone(&self.sessions);
+        let stats_clone = Arc::clone(&self.stats);
+        let simulator_clone = Arc::clone(&simulator);
         let data_rate = self.data_rate_hz;
+        let sector_plan = self.sector_plan.clone();
+        let group_policy = self.group_policy;
         let _broadcaster_task = spawn(async move {
-            radar_data_broadcaster(clients_clone, ready_clients_clone, data_rate).await;
+            radar_data_broadcaster(
+                clients_clone,
+                sessions_clone,
+                stats_clone,
+                simulator_clone,
+                data_rate,
+                sector_plan,
+                group_policy,
+            )
+            .await;
             Ok::<(), io::Error>(())
         });
 
+        // Start the radial streamer for clients following the antenna
+        let _radial_task = spawn(radial_broadcaster(
+            Arc::clone(&self.clients),
+            Arc::clone(&self.sessions),
+            Arc::clone(&self.stats),
+            simulator,
+            self.sector_plan.clone(),
+        ));
+
         println!("All servers started successfully!");
         println!("Connect clients to ports: {:?}", self.ports);
-        println!("Radar data will be streamed after clients send 'SEND_DATA' command");
+        println!("Radar data will be streamed after clients send 'SUBSCRIBE' command");
 
         // Wait for all tasks
         for task in tasks {
@@ -79,14 +385,30 @@ impl RadarTcpServer {
     }
 }
 
-async fn start_server_on_port(
+// Settings every connection on one listener port starts with
+#[derive(Debug, Clone)]
+struct PortSettings {
     port: u16,
+    sector: Option<usize>,
+    faults: Option<FaultConfig>,
+    writer_config: WriterConfig,
+    stream_header: StreamHeader, // Sent to each client as soon as it connects
+}
+
+async fn start_server_on_port(
+    settings: PortSettings,
     client_counter: Arc<AtomicUsize>,
     clients: ClientConnections,
-    ready_clients: ReadyClients,
+    sessions: ClientSessions,
+    stats: Arc<ServerStats>,
+    sector_count: usize,
 ) -> io::Result<()> {
+    let port = settings.port;
     let listener = TcpListener::bind(format!("127.0.0.1:{}", port)).await?;
-    println!("TCP Server listening on port {}", port);
+    match settings.sector {
+        Some(sector) => println!("TCP Server listening on port {} (sector {})", port, sector),
+        None => println!("TCP Server listening on port {}", port),
+    }
 
     loop {
         match listener.accept().await {
@@ -97,24 +419,38 @@ async fn start_server_on_port(
                     addr, port, client_id
                 );
 
-                // Initialize client as not ready
+                // Initialize client as not streaming
                 {
-                    let mut ready_map = ready_clients.lock().await;
-                    ready_map.insert(client_id, false);
+                    let mut sessions_map = sessions.lock().await;
+                    let faults = settings.faults.clone().map(|config| {
+                        FaultInjector::new(config, client_id as u64)
+                            .expect("fault configs are validated when the server starts")
+                    });
+                    sessions_map.insert(
+                        client_id,
+                        ClientSession {
+                            faults,
+                            ..ClientSession::new(port, settings.sector)
+                        },
+                    );
                 }
 
                 // Spawn a task to handle this client's commands
                 let clients_clone = Arc::clone(&clients);
-                let ready_clients_clone = Arc::clone(&ready_clients);
+                let sessions_clone = Arc::clone(&sessions);
+                let stats_clone = Arc::clone(&stats);
                 spawn(handle_client_connection(
                     client_id,
                     socket,
+                    settings.clone(),
                     clients_clone,
-                    ready_clients_clone,
+                    sessions_clone,
+                    stats_clone,
+                    sector_count,
                 ));
 
                 println!(
-                    "Client {} connected. Waiting for 'SEND_DATA' command...",
+                    "Client {} connected. Waiting for 'SUBSCRIBE' command...",
                     client_id
                 );
             }
@@ -128,226 +464,1152 @@ async fn start_server_on_port(
 async fn handle_client_connection(
     client_id: usize,
     socket: TcpStream,
+    settings: PortSettings,
     clients: ClientConnections,
-    ready_clients: ReadyClients,
+    sessions: ClientSessions,
+    stats: Arc<ServerStats>,
+    sector_count: usize,
 ) {
     // Split the socket to handle commands and data streaming concurrently
-    let (mut reader, mut writer) = socket.into_split();
-    let mut buffer = [0; 1024];
+    let (reader, writer) = socket.into_split();
+    let mut lines = FramedRead::new(reader, CommandLineCodec::new());
 
-    // Store the writer half immediately for data streaming
+    // Hand the writer half to its own task and store the queue for data streaming
     {
         let mut clients_map = clients.lock().await;
-        clients_map.insert(client_id, writer);
+        clients_map.insert(
+            client_id,
+            ClientWriter::spawn(client_id, writer, settings.writer_config),
+        );
+    }
+
+    // Tell the client how the radar is set up before anything else
+    let tilt_count = settings.stream_header.scan.tilt_count();
+    let header = Message::StreamHeader(settings.stream_header);
+    if let Err(e) = send_message(&clients, client_id, &header).await {
+        eprintln!(
+            "Failed to send stream header to client {}: {}",
+            client_id, e
+        );
     }
 
-    // Continue reading commands from the reader half
+    // Continue reading newline-delimited commands from the reader half
     loop {
-        match reader.read(&mut buffer).await {
-            Ok(0) => {
+        let line = match lines.next().await {
+            Some(Ok(line)) => line,
+            Some(Err(e)) => {
+                eprintln!("Error reading from client {}: {}", client_id, e);
+                break;
+            }
+            None => {
                 // Connection closed
                 println!("Client {} disconnected", client_id);
-
-                // Remove from both maps
-                {
-                    let mut clients_map = clients.lock().await;
-                    clients_map.remove(&client_id);
-                }
-                {
-                    let mut ready_map = ready_clients.lock().await;
-                    ready_map.remove(&client_id);
-                }
                 break;
             }
-            Ok(n) => {
-                let message = String::from_utf8_lossy(&buffer[..n]).trim().to_string();
-                println!("Received from client {}: '{}'", client_id, message);
+        };
 
-                if message == "SEND_DATA" {
-                    println!("Client {} requested data streaming", client_id);
+        let parsed = match line {
+            Ok(line) if line.trim().is_empty() => continue,
+            Ok(line) => {
+                println!("Received from client {}: '{}'", client_id, line.trim());
+                line.parse::<Command>()
+            }
+            Err(e) => Err(e), // Too long to be a command
+        };
 
-                    // Mark client as ready for data streaming
-                    {
-                        let mut ready_map = ready_clients.lock().await;
-                        ready_map.insert(client_id, true);
-                    }
+        let response = match parsed {
+            Ok(command) => {
+                execute_command(
+                    client_id,
+                    command,
+                    &clients,
+                    &sessions,
+                    &stats,
+                    sector_count,
+                    tilt_count,
+                )
+                .await
+            }
+            Err(e) => {
+                println!("Invalid command from client {}: {}", client_id, e);
+                Message::Error(e)
+            }
+        };
 
-                    println!("Client {} is now ready for data streaming", client_id);
-                    // Continue listening for more commands (don't break!)
-                } else if message == "STOP" {
-                    println!("Client {} requested to stop data streaming", client_id);
+        if let Err(e) = send_message(&clients, client_id, &response).await {
+            eprintln!("Failed to send response to client {}: {}", client_id, e);
+        }
+    }
 
-                    // Mark client as not ready
-                    {
-                        let mut ready_map = ready_clients.lock().await;
-                        ready_map.insert(client_id, false);
-                    }
+    // Remove from both maps, letting the writer flush what is queued
+    {
+        let mut clients_map = clients.lock().await;
+        if let Some(writer) = clients_map.remove(&client_id) {
+            writer.close();
+        }
+    }
+    {
+        let mut sessions_map = sessions.lock().await;
+        sessions_map.remove(&client_id);
+    }
+}
 
-                    println!("Client {} stopped receiving data streaming", client_id);
-                    // Continue listening for more commands
-                } else {
-                    println!("Unknown command from client {}: '{}'", client_id, message);
-
-                    // Optionally, you could send an error response back to the client
-                    let error_response = format!("Unknown command: '{}'\n", message);
-                    let mut clients_map = clients.lock().await;
-                    if let Some(writer) = clients_map.get_mut(&client_id) {
-                        if let Err(e) = writer.write_all(error_response.as_bytes()).await {
-                            eprintln!(
-                                "Failed to send error response to client {}: {}",
-                                client_id, e
-                            );
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("Error reading from client {}: {}", client_id, e);
+async fn execute_command(
+    client_id: usize,
+    command: Command,
+    clients: &ClientConnections,
+    sessions: &ClientSessions,
+    stats: &ServerStats,
+    sector_count: usize,
+    tilt_count: usize,
+) -> Message {
+    if let Command::GetStatus = command {
+        let (connected_clients, dropped_frames) = {
+            let clients_map = clients.lock().await;
+            let dropped_frames = clients_map.get(&client_id).map_or(0, |writer| {
+                writer.stats().dropped_frames.load(Ordering::SeqCst)
+            });
+            (clients_map.len(), dropped_frames)
+        };
+        let streaming_clients = sessions
+            .lock()
+            .await
+            .values()
+            .filter(|session| session.streaming)
+            .count();
+        return Message::Status(ServerStatus {
+            connected_clients,
+            streaming_clients,
+            last_sequence_id: stats.last_sequence_id.load(Ordering::SeqCst),
+            dropped_frames,
+            compression: stats.compression_reports(),
+        });
+    }
 
-                // Remove from both maps on error
-                {
-                    let mut clients_map = clients.lock().await;
-                    clients_map.remove(&client_id);
-                }
-                {
-                    let mut ready_map = ready_clients.lock().await;
-                    ready_map.remove(&client_id);
-                }
-                break;
+    let mut sessions_map = sessions.lock().await;
+    let Some(session) = sessions_map.get_mut(&client_id) else {
+        // Disconnect cleanup already ran; a fresh session here would never be removed
+        return Message::Error(CommandError::Rejected(format!(
+            "Client {} has no session",
+            client_id
+        )));
+    };
+
+    let ack = match command {
+        Command::Subscribe(update) => {
+            if let Some(&tilt) = update
+                .tilts
+                .iter()
+                .flatten()
+                .find(|&&tilt| tilt >= tilt_count)
+            {
+                return Message::Error(CommandError::Rejected(format!(
+                    "Tilt {} does not exist (scan has {} tilts)",
+                    tilt, tilt_count
+                )));
+            }
+            session.subscription.merge(&update);
+            session.streaming = true;
+            // A new subscription starts over with a keyframe
+            session.delta = session
+                .subscription
+                .delta_keyframe_interval()
+                .map(|interval| Arc::new(std::sync::Mutex::new(DeltaEncoder::new(interval))));
+            println!(
+                "Client {} is now ready for data streaming ({:?})",
+                client_id, session.subscription
+            );
+            Ack::Subscribed(session.subscription.clone())
+        }
+        Command::Unsubscribe => {
+            session.streaming = false;
+            println!("Client {} stopped receiving data streaming", client_id);
+            Ack::Unsubscribed
+        }
+        Command::SetRate(hz) => {
+            session.subscription.rate_hz = Some(hz);
+            println!("Client {} limited to {}Hz", client_id, hz);
+            Ack::RateSet(hz)
+        }
+        Command::SelectSector(index) => {
+            if index >= sector_count {
+                return Message::Error(CommandError::Rejected(format!(
+                    "Sector {} does not exist (plan has {} sectors)",
+                    index, sector_count
+                )));
             }
+            session.sector = Some(index);
+            session.subscription.azimuth = None; // Back to plan sectors
+            println!("Client {} selected sector {}", client_id, index);
+            Ack::SectorSelected(index)
+        }
+        Command::Compress(compression) => {
+            session.compression = compression;
+            println!(
+                "Client {} switched to {} compression",
+                client_id, compression
+            );
+            Ack::CompressionSet(compression)
         }
+        Command::Resync => {
+            let Some(delta) = session.delta.as_ref() else {
+                return Message::Error(CommandError::Rejected(
+                    "Delta streaming is not enabled".to_string(),
+                ));
+            };
+            delta.lock().unwrap().request_keyframe();
+            println!("Client {} requested a keyframe", client_id);
+            Ack::ResyncScheduled
+        }
+        Command::Ping(token) => Ack::Pong(token),
+        Command::GetStatus => unreachable!("handled above"),
+    };
+
+    Message::Ack(ack)
+}
+
+async fn send_message(
+    clients: &ClientConnections,
+    client_id: usize,
+    message: &Message,
+) -> io::Result<()> {
+    let mut frame = BytesMut::new();
+    RadarCodec::new().encode(message, &mut frame)?;
+
+    let clients_map = clients.lock().await;
+    match clients_map.get(&client_id) {
+        Some(writer) => Ok(writer.send_control(frame.freeze())?),
+        None => Ok(()),
     }
 }
 
 pub async fn radar_data_broadcaster(
     clients: ClientConnections,
-    ready_clients: ReadyClients,
+    sessions: ClientSessions,
+    stats: Arc<ServerStats>,
+    simulator: SharedSimulator,
     data_rate_hz: f64,
+    sector_plan: SectorPlan,
+    group_policy: GroupPolicy,
 ) {
-    let mut radar_sim = RadarSimulator::new();
     let mut interval = interval(Duration::from_millis((1000.0 / data_rate_hz) as u64));
     let mut last_ready_count = 0;
+    let sector_count = sector_plan.len();
+    let mut sector_group = SectorGroup::new(group_policy, sector_count);
 
     println!("Starting radar data broadcast at {}Hz", data_rate_hz);
-    println!("Real-world approach: ONE radar sweep split between clients");
-    println!("Waiting for both clients to connect and send 'SEND_DATA' command...");
+    println!(
+        "Real-world approach: ONE radar sweep split between {} sectors",
+        sector_count
+    );
+    match group_policy {
+        GroupPolicy::Independent => {
+            println!("Streaming to every client as soon as it sends 'SUBSCRIBE'...")
+        }
+        GroupPolicy::Synchronized => println!(
+            "Waiting for {} clients to connect and send 'SUBSCRIBE' command...",
+            sector_count
+        ),
+    }
 
     loop {
         interval.tick().await;
 
-        let clients_map = clients.lock().await;
-        let ready_map = ready_clients.lock().await;
+        // Decide who is served from a snapshot of the sessions; the locks are
+        // released before the sweep is generated and encoded, so commands,
+        // connects and disconnects are not held up behind it
+        let now = Instant::now();
+        let jobs = {
+            let clients_map = clients.lock().await;
+            let sessions_map = sessions.lock().await;
 
-        // Count ready clients
-        let current_ready_count = ready_map.values().filter(|&&ready| ready).count();
+            // Ready clients in client id order, split by how they subscribed
+            let mut ready_ids: Vec<usize> = sessions_map
+                .iter()
+                .filter(|(client_id, session)| {
+                    session.streaming
+                        && session.subscription.stream_mode() == StreamMode::Sweep
+                        && clients_map.contains_key(client_id)
+                })
+                .map(|(&client_id, _)| client_id)
+                .collect();
+            ready_ids.sort_unstable();
+            let (window_ids, sector_ids): (Vec<usize>, Vec<usize>) = ready_ids
+                .into_iter()
+                .partition(|client_id| sessions_map[client_id].subscription.is_custom_window());
 
-        // Check if ready client count changed
-        if current_ready_count != last_ready_count {
-            println!(
-                "Ready client count changed: {} -> {}",
-                last_ready_count, current_ready_count
-            );
+            // Count clients waiting for a plan sector
+            let current_ready_count = sector_ids.len();
+
+            // Check if ready client count changed
+            if current_ready_count != last_ready_count {
+                println!(
+                    "Ready client count changed: {} -> {}",
+                    last_ready_count, current_ready_count
+                );
+                last_ready_count = current_ready_count;
+            }
+
+            let tick = sector_group.tick(&sector_ids, &sessions_map);
+            if tick.restart_sequence {
+                simulator.lock().unwrap().reset_sequence();
+                println!("🔄 All clients ready! Resetting sequence counter for synchronization.");
+            }
+
+            // Clients with their own azimuth window are served regardless of the group
+            if tick.sector_clients.is_empty() && window_ids.is_empty() {
+                if group_policy == GroupPolicy::Synchronized {
+                    println!(
+                        "⏳ Waiting for all clients to be ready... ({}/{} sectors covered)",
+                        tick.covered_sectors, sector_count
+                    );
+                }
+                continue;
+            }
+
+            let mut assignments: Vec<(usize, Option<usize>)> = tick
+                .sector_clients
+                .into_iter()
+                .map(|(client_id, sector_index)| (client_id, Some(sector_index)))
+                .chain(window_ids.iter().map(|&client_id| (client_id, None)))
+                .collect();
+            assignments.sort_unstable();
+
+            assignments
+                .into_iter()
+                .filter_map(|(client_id, sector_index)| {
+                    let session = &sessions_map[&client_id];
+                    session.is_due(now).then(|| SweepJob {
+                        client_id,
+                        sector_index,
+                        subscription: session.subscription.clone(),
+                        compression: session.compression,
+                        delta: session.delta.clone(),
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Generate ONE complete radar sweep (this is what real radar produces)
+        let (complete_sweep, target_count) = {
+            let mut radar_sim = simulator.lock().unwrap();
+            (radar_sim.generate_complete_sweep(), radar_sim.targets.len())
+        };
+        stats
+            .last_sequence_id
+            .store(complete_sweep.sequence_id, Ordering::SeqCst);
+
+        // Tailor one sweep per due client from the SAME complete sweep
+        let encoded = encode_sweep_jobs(jobs, &complete_sweep, &sector_plan, &stats);
+
+        // Lock again only to update the sessions and queue the frames
+        let mut clients_map = clients.lock().await;
+        let mut sessions_map = sessions.lock().await;
+
+        let mut deliveries: Vec<(usize, u16, SharedFrame, FrameDelay)> = Vec::new(); // (client_id, port, frame, delay)
+        for (job, frame) in encoded {
+            let client_id = job.client_id;
+            let Some(session) = sessions_map.get_mut(&client_id) else {
+                continue; // Disconnected while the sweep was encoded
+            };
+            // Unsubscribed or subscribed afresh meanwhile: this frame is stale, and a
+            // delta made with the replaced encoder would not decode on the client
+            let same_delta_stream = match (&session.delta, &job.delta) {
+                (Some(current), Some(used)) => Arc::ptr_eq(current, used),
+                (None, None) => true,
+                _ => false,
+            };
+            if !session.streaming || !same_delta_stream {
+                continue;
+            }
+            session.last_sent = Some(now);
+
+            // Injected network faults, if enabled for this connection
+            let action = match session.faults.as_mut() {
+                Some(faults) => faults.next_action(frame.bytes.len()),
+                None => FaultAction::Deliver(FrameDelay::default()),
+            };
 
-            // Reset sequence counter when both clients are ready for synchronization
-            if current_ready_count == 2 && last_ready_count < 2 {
-                radar_sim.reset_sequence();
-                println!("🔄 Both clients ready! Resetting sequence counter for synchronization.");
+            match action {
+                FaultAction::Deliver(delay) => {
+                    deliveries.push((client_id, session.port, frame, delay))
+                }
+                FaultAction::Drop => println!(
+                    "💥 Dropped sweep {} for Client {} (fault injection)",
+                    complete_sweep.sequence_id, client_id
+                ),
             }
+        }
+
+        drop(sessions_map); // Release the lock early
 
-            last_ready_count = current_ready_count;
+        // Queue data for mapped ready clients; writer tasks do the actual sending
+        let mut disconnected_clients = Vec::new();
+        for (client_id, port, frame, delay) in deliveries {
+            let Some(writer) = clients_map.get(&client_id) else {
+                continue; // Already gone
+            };
+            if !deliver_sweep(writer, client_id, port, &frame, delay, target_count) {
+                disconnected_clients.push(client_id);
+            }
         }
 
-        // Only broadcast when we have both clients ready for proper merging
-        if current_ready_count < 2 {
-            println!(
-                "⏳ Waiting for both clients to be ready... ({}/2 ready)",
-                current_ready_count
-            );
+        // Remove disconnected clients from both maps
+        if !disconnected_clients.is_empty() {
+            let mut sessions_map = sessions.lock().await;
+            for client_id in disconnected_clients {
+                if let Some(writer) = clients_map.remove(&client_id) {
+                    writer.close();
+                }
+                sessions_map.remove(&client_id);
+                println!("Removed disconnected client {}", client_id);
+            }
+        }
+    }
+}
+
+// What one due client gets this tick, copied out of its session so the
+// sweep can be tailored without holding the session lock
+struct SweepJob {
+    client_id: usize,
+    sector_index: Option<usize>, // Plan sector, `None` for a custom azimuth window
+    subscription: Subscription,
+    compression: Compression,
+    delta: Option<SharedDeltaEncoder>,
+}
+
+// An encoded sweep frame. Cloning only bumps a reference count, so every
+// subscriber of a sector queues the same buffer.
+#[derive(Debug, Clone)]
+struct SharedFrame {
+    bytes: Bytes,
+    sequence_id: u64,
+    azimuth_start: f32,
+    azimuth_end: f32,
+}
+
+impl SharedFrame {
+    fn encode(
+        sweep: &RadarSweep,
+        format: SampleFormat,
+        compression: Compression,
+        stats: &ServerStats,
+    ) -> io::Result<Self> {
+        let frame = Self {
+            bytes: Bytes::new(),
+            sequence_id: sweep.sequence_id,
+            azimuth_start: sweep.azimuth_start,
+            azimuth_end: sweep.azimuth_end,
+        };
+        match QuantizedSweep::encode(sweep, format) {
+            Some(quantized) => {
+                frame.with_payload(MessageType::QuantizedSweep, &quantized, compression, stats)
+            }
+            None => frame.with_payload(MessageType::Sweep, sweep, compression, stats),
+        }
+    }
+
+    fn encode_delta(
+        delta: &DeltaSweep,
+        compression: Compression,
+        stats: &ServerStats,
+    ) -> io::Result<Self> {
+        let frame = Self {
+            bytes: Bytes::new(),
+            sequence_id: delta.sequence_id,
+            azimuth_start: delta.azimuth_start,
+            azimuth_end: delta.azimuth_end,
+        };
+        frame.with_payload(MessageType::DeltaSweep, delta, compression, stats)
+    }
+
+    fn encode_radial(
+        radial: &Radial,
+        beam_width: f32,
+        compression: Compression,
+        stats: &ServerStats,
+    ) -> io::Result<Self> {
+        let frame = Self {
+            bytes: Bytes::new(),
+            sequence_id: radial.sequence_id,
+            azimuth_start: radial.azimuth,
+            azimuth_end: radial.azimuth + beam_width,
+        };
+        frame.with_payload(MessageType::Radial, radial, compression, stats)
+    }
+
+    fn with_payload<T: Serialize>(
+        mut self,
+        message_type: MessageType,
+        payload: &T,
+        compression: Compression,
+        stats: &ServerStats,
+    ) -> io::Result<Self> {
+        let started = Instant::now();
+        let mut frame = BytesMut::new();
+        let raw_len = encode_compressed_payload(message_type, payload, compression, &mut frame)?;
+        stats.compression_counters(compression).record(
+            raw_len,
+            frame.len() - HEADER_BYTES,
+            started.elapsed(),
+        );
+
+        self.bytes = frame.freeze();
+        Ok(self)
+    }
+}
+
+// Encode the sweep of every job from the SAME complete sweep. Jobs with the
+// same effective window (a plan sector, or the same custom azimuth window),
+// shape and compression share one frame.
+fn encode_sweep_jobs(
+    jobs: Vec<SweepJob>,
+    complete_sweep: &RadarSweep,
+    sector_plan: &SectorPlan,
+    stats: &ServerStats,
+) -> Vec<(SweepJob, SharedFrame)> {
+    let mut encoded = Vec::new();
+    // (sector, shape, compression, frame): encoded once per window, shape and compression
+    let mut frames: Vec<(Option<usize>, Subscription, Compression, SharedFrame)> = Vec::new();
+    for job in jobs {
+        if !job.subscription.wants_tilt(complete_sweep.tilt) {
             continue;
         }
 
-        // Update target positions
-        radar_sim.update_targets(1.0 / data_rate_hz as f32);
-        radar_sim.current_time += (1000000f64 / data_rate_hz) as u64; // microseconds
+        // The rate limit and tilt choice do not change the data, so they are
+        // not part of the shape
+        let shape = Subscription {
+            rate_hz: None,
+            tilts: None,
+            ..job.subscription.clone()
+        };
 
-        // Generate ONE complete radar sweep (this is what real radar produces)
-        let complete_sweep = radar_sim.generate_complete_sweep();
+        let frame = if let Some(delta) = job.delta.as_ref() {
+            // Deltas depend on what this client already holds, so they are never shared
+            let Some(client_data) =
+                tailor_sweep(&shape, job.sector_index, complete_sweep, sector_plan)
+            else {
+                continue;
+            };
+            let format = shape.sample_format();
+            let delta_sweep = delta.lock().unwrap().encode(&client_data, format);
+            let frame = match delta_sweep {
+                Some(delta_sweep) => {
+                    SharedFrame::encode_delta(&delta_sweep, job.compression, stats)
+                }
+                None => SharedFrame::encode(&client_data, format, job.compression, stats),
+            };
+            match frame {
+                Ok(frame) => frame,
+                Err(e) => {
+                    eprintln!("Failed to encode sweep for client {}: {}", job.client_id, e);
+                    continue;
+                }
+            }
+        } else {
+            let cached = frames
+                .iter()
+                .find(|(sector, cached_shape, compression, _)| {
+                    *sector == job.sector_index
+                        && *cached_shape == shape
+                        && *compression == job.compression
+                })
+                .map(|(_, _, _, frame)| frame.clone());
 
-        let mut disconnected_clients = Vec::new();
-        let mut sent_count = 0;
+            match cached {
+                Some(frame) => frame,
+                None => {
+                    let Some(client_data) =
+                        tailor_sweep(&shape, job.sector_index, complete_sweep, sector_plan)
+                    else {
+                        continue;
+                    };
+                    let frame = match SharedFrame::encode(
+                        &client_data,
+                        shape.sample_format(),
+                        job.compression,
+                        stats,
+                    ) {
+                        Ok(frame) => frame,
+                        Err(e) => {
+                            eprintln!("Failed to encode sweep for client {}: {}", job.client_id, e);
+                            continue;
+                        }
+                    };
+                    frames.push((job.sector_index, shape, job.compression, frame.clone()));
+                    frame
+                }
+            }
+        };
+        encoded.push((job, frame));
+    }
+    encoded
+}
+
+// Cut a plan sector (or the custom window when `sector_index` is `None`) to
+// the subscription's shape. Not tied to one client, so the result can be shared
+fn tailor_sweep(
+    shape: &Subscription,
+    sector_index: Option<usize>,
+    complete_sweep: &RadarSweep,
+    sector_plan: &SectorPlan,
+) -> Option<RadarSweep> {
+    match sector_index {
+        Some(sector_index) => sector_plan
+            .extract(complete_sweep, sector_index)
+            .map(|sector_sweep| shape.apply(&sector_sweep)),
+        None => Some(shape.apply(complete_sweep)),
+    }
+}
 
-        // Map ready clients to specific ports for consistent assignment
-        let mut port_clients: HashMap<usize, usize> = HashMap::new(); // port_index -> client_id
+// Queue one tailored sweep, returning false if the client has to be disconnected
+fn deliver_sweep(
+    writer: &ClientWriter,
+    client_id: usize,
+    port: u16,
+    frame: &SharedFrame,
+    delay: FrameDelay,
+    target_count: usize,
+) -> bool {
+    let dropped_before = writer.stats().dropped_frames.load(Ordering::SeqCst);
 
-        for (&client_id, &is_ready) in ready_map.iter() {
-            if is_ready && sent_count < 2 && clients_map.contains_key(&client_id) {
-                port_clients.insert(sent_count, client_id);
-                sent_count += 1;
+    match writer.send_delayed(frame.bytes.clone(), delay) {
+        Ok(_) => {
+            let dropped = writer.stats().dropped_frames.load(Ordering::SeqCst);
+            if dropped > dropped_before {
+                println!(
+                    "⚠️ Client {} is falling behind, queue full ({} frames dropped so far)",
+                    client_id, dropped
+                );
             }
+            println!(
+                "[{}] Queued sweep {} for Client {} (Port {}) (Az: {:.1}°-{:.1}°, {} targets)",
+                chrono::Local::now().format("%H:%M:%S%.3f"),
+                frame.sequence_id,
+                client_id,
+                port,
+                frame.azimuth_start,
+                frame.azimuth_end,
+                target_count
+            );
+            true
+        }
+        Err(e) => {
+            eprintln!("Failed to send data to client {}: {}", client_id, e);
+            false
         }
+    }
+}
 
-        drop(ready_map); // Release the lock early
-        drop(clients_map); // Release the lock early
+// Streams radials to `mode=radial` clients as the antenna turns, one small
+// frame per radial. Clients see their window or sector, the whole circle
+// when they have neither.
+pub async fn radial_broadcaster(
+    clients: ClientConnections,
+    sessions: ClientSessions,
+    stats: Arc<ServerStats>,
+    simulator: SharedSimulator,
+    sector_plan: SectorPlan,
+) {
+    let radar_config = *simulator.lock().unwrap().config();
+    let azimuth_count = radar_config.azimuth_count() as u64;
+    let mut interval = interval(RADIAL_TICK);
+    let mut last_tick = Instant::now();
+    let mut rotation_frames = 0usize; // Radial frames queued during the current rotation
+
+    println!(
+        "Antenna turning at {} RPM for clients that send 'SUBSCRIBE mode=radial'",
+        simulator.lock().unwrap().rotation_rpm
+    );
+
+    loop {
+        interval.tick().await;
 
-        // Send data to mapped ready clients
-        for (port_index, &client_id) in port_clients.iter() {
-            let mut clients_map = clients.lock().await;
-            if let Some(stream) = clients_map.get_mut(&client_id) {
-                // Extract client's portion from the SAME complete sweep
+        // The antenna keeps turning whether or not anyone is listening, and
+        // moves the targets the sweep broadcaster samples too
+        let now = Instant::now();
+        let radials = simulator
+            .lock()
+            .unwrap()
+            .advance_antenna(now.duration_since(last_tick).as_secs_f32());
+        last_tick = now;
 
-                let client_data = extract_client_portion(&complete_sweep, *port_index);
+        // Snapshot of the radial clients; radials are encoded without the locks
+        let mut jobs: Vec<RadialJob> = {
+            let clients_map = clients.lock().await;
+            let sessions_map = sessions.lock().await;
+            sessions_map
+                .iter()
+                .filter(|(client_id, session)| {
+                    session.streaming
+                        && session.subscription.stream_mode() == StreamMode::Radial
+                        && clients_map.contains_key(client_id)
+                })
+                .map(|(&client_id, session)| RadialJob {
+                    client_id,
+                    span: session.sector.and_then(|sector| sector_plan.span(sector)),
+                    subscription: session.subscription.clone(),
+                    compression: session.compression,
+                })
+                .collect()
+        };
+        if jobs.is_empty() {
+            continue;
+        }
+        jobs.sort_unstable_by_key(|job| job.client_id);
+        let radials: Vec<(u64, Radial)> = {
+            let radar_sim = simulator.lock().unwrap();
+            radials
+                .map(|number| (number, radar_sim.generate_radial(number)))
+                .collect()
+        };
 
-                let port = if *port_index == 0 { 8080 } else { 8081 };
+        // Encode every radial of this tick for the clients that cover it
+        let mut radial_batches: Vec<RadialBatch> = Vec::new();
+        for (number, radial) in radials {
+            let mut radial_frames: Vec<(Subscription, Compression, SharedFrame)> = Vec::new(); // Encoded once per shape and compression
+            let mut client_frames = Vec::new();
+            for job in &jobs {
+                let covered = match (job.subscription.azimuth, job.span) {
+                    (Some(window), _) => window.contains(radial.azimuth),
+                    (None, Some(span)) => span.contains(radial.azimuth),
+                    (None, None) => true,
+                };
+                if !covered || !job.subscription.wants_tilt(radial.tilt) {
+                    continue;
+                }
 
-                match send_radar_data(stream, &client_data, port).await {
-                    Ok(_) => {
-                        println!(
-                            "[{}] Sent sweep {} to Client {} (Port {}) (Az: {:.1}°-{:.1}°, {} targets)",
-                            format!("{}", chrono::Local::now().format("%H:%M:%S%.3f")),
-                            complete_sweep.sequence_id,
-                            client_id,
-                            if *port_index == 0 { 8080 } else { 8081 },
-                            client_data.azimuth_start,
-                            client_data.azimuth_end,
-                            radar_sim.targets.len()
-                        );
+                // Only the range limit and decimation change a radial
+                let shape = Subscription {
+                    max_range_km: job.subscription.max_range_km,
+                    range_decimation: job.subscription.range_decimation,
+                    ..Subscription::default()
+                };
+                let cached = radial_frames
+                    .iter()
+                    .find(|(cached_shape, compression, _)| {
+                        *cached_shape == shape && *compression == job.compression
+                    })
+                    .map(|(_, _, frame)| frame.clone());
+                let frame = match cached {
+                    Some(frame) => frame,
+                    None => {
+                        let frame = match SharedFrame::encode_radial(
+                            &shape.apply_radial(&radial),
+                            radar_config.azimuth_resolution_deg,
+                            job.compression,
+                            &stats,
+                        ) {
+                            Ok(frame) => frame,
+                            Err(e) => {
+                                eprintln!(
+                                    "Failed to encode radial for client {}: {}",
+                                    job.client_id, e
+                                );
+                                continue;
+                            }
+                        };
+                        radial_frames.push((shape, job.compression, frame.clone()));
+                        frame
                     }
+                };
+                client_frames.push((job.client_id, frame));
+            }
+            radial_batches.push(RadialBatch {
+                number,
+                radial,
+                client_frames,
+            });
+        }
+
+        // Lock again only to apply faults and queue the frames
+        let mut clients_map = clients.lock().await;
+        let mut sessions_map = sessions.lock().await;
+
+        let mut disconnected_clients = HashSet::new();
+        for RadialBatch {
+            number,
+            radial,
+            client_frames,
+        } in radial_batches
+        {
+            if number % azimuth_count == 0 && rotation_frames > 0 {
+                println!(
+                    "[{}] 🔄 Rotation {} complete: {} radial frames queued for {} clients",
+                    chrono::Local::now().format("%H:%M:%S%.3f"),
+                    radial.sequence_id - 1,
+                    rotation_frames,
+                    jobs.len()
+                );
+                rotation_frames = 0;
+            }
+
+            for (client_id, frame) in client_frames {
+                if disconnected_clients.contains(&client_id) {
+                    continue;
+                }
+                let Some(session) = sessions_map.get_mut(&client_id) else {
+                    continue; // Disconnected while the radials were encoded
+                };
+                if !session.streaming || session.subscription.stream_mode() != StreamMode::Radial {
+                    continue;
+                }
+
+                // Injected network faults, if enabled for this connection
+                let delay = match session.faults.as_mut() {
+                    Some(faults) => match faults.next_action(frame.bytes.len()) {
+                        FaultAction::Deliver(delay) => delay,
+                        FaultAction::Drop => {
+                            println!(
+                                "💥 Dropped radial {:.1}° of rotation {} for Client {} (fault injection)",
+                                radial.azimuth, radial.sequence_id, client_id
+                            );
+                            continue;
+                        }
+                    },
+                    None => FrameDelay::default(),
+                };
+
+                let Some(writer) = clients_map.get(&client_id) else {
+                    continue;
+                };
+                match writer.send_delayed(frame.bytes, delay) {
+                    Ok(()) => rotation_frames += 1,
                     Err(e) => {
-                        eprintln!("Failed to send data to client {}: {}", client_id, e);
-                        disconnected_clients.push(client_id);
+                        eprintln!("Failed to send radial to client {}: {}", client_id, e);
+                        disconnected_clients.insert(client_id);
                     }
                 }
             }
         }
 
         // Remove disconnected clients from both maps
-        if !disconnected_clients.is_empty() {
-            let mut clients_map = clients.lock().await;
-            let mut ready_map = ready_clients.lock().await;
-            for client_id in disconnected_clients {
-                clients_map.remove(&client_id);
-                ready_map.remove(&client_id);
-                println!("Removed disconnected client {}", client_id);
+        for client_id in disconnected_clients {
+            if let Some(writer) = clients_map.remove(&client_id) {
+                writer.close();
             }
+            sessions_map.remove(&client_id);
+            println!("Removed disconnected client {}", client_id);
         }
     }
 }
 
-pub async fn send_radar_data(
-    stream: &mut OwnedWriteHalf,
+// A radial client's parameters, copied out of its session for one tick
+struct RadialJob {
+    client_id: usize,
+    span: Option<SectorSpan>, // Sector the client streams, when it has one
+    subscription: Subscription,
+    compression: Compression,
+}
+
+// One radial of a tick with the frame each covering client gets
+struct RadialBatch {
+    number: u64, // Radials since the antenna started turning
+    radial: Radial,
+    client_frames: Vec<(usize, SharedFrame)>, // (client_id, frame)
+}
+
+// Pair sector clients with plan sectors, returning (client_id, sector_index).
+// Port mappings and explicit selections come first; the remaining clients
+// take free sectors in client id order.
+fn assign_sectors(
+    group_policy: GroupPolicy,
+    sector_ids: &[usize],
+    sessions_map: &HashMap<usize, ClientSession>,
+    sector_count: usize,
+) -> Vec<(usize, usize)> {
+    let mut assignments = Vec::new();
+    let mut load = vec![0usize; sector_count]; // Clients per sector
+
+    for &client_id in sector_ids {
+        if let Some(sector) = sessions_map[&client_id].sector {
+            // A synchronized group has exactly one client per sector
+            if group_policy == GroupPolicy::Synchronized && load[sector] > 0 {
+                continue;
+            }
+            load[sector] += 1;
+            assignments.push((client_id, sector));
+        }
+    }
+
+    for &client_id in sector_ids {
+        if sessions_map[&client_id].sector.is_some() {
+            continue;
+        }
+        // Least loaded sector, lowest index on ties
+        let Some((sector, &count)) = load.iter().enumerate().min_by_key(|&(_, &count)| count)
+        else {
+            break;
+        };
+        if group_policy == GroupPolicy::Synchronized && count > 0 {
+            break;
+        }
+        load[sector] += 1;
+        assignments.push((client_id, sector));
+    }
+
+    assignments
+}
+
+// Encode a sweep and queue it on the client's writer, to be written after `delay`
+pub fn send_radar_data(
+    writer: &ClientWriter,
     radar_sweep: &RadarSweep,
-    port: u16,
+    delay: Duration,
 ) -> Result<(), Box<dyn Error>> {
-    let encoded_data = bincode::serialize(radar_sweep)?;
+    // Header-framed message, see `protocol::FrameHeader`
+    let mut frame = BytesMut::new();
+    encode_payload(MessageType::Sweep, radar_sweep, &mut frame)?;
+    writer.send_delayed(frame.freeze(), FrameDelay::latency(delay))?;
+
+    Ok(())
+}
 
-    // delay to send to port 8080
-    if port == 8080 {
-        tokio::time::sleep(Duration::from_millis(1000)).await;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subscription::AzimuthWindow;
+
+    fn server() -> RadarTcpServer {
+        RadarTcpServer::new(vec![8080, 8081], 1.0)
     }
-    // Send data size first, then the data
-    stream.write_u64(encoded_data.len() as u64).await?;
-    stream.write_all(&encoded_data).await?;
-    stream.flush().await?;
 
-    Ok(())
+    // Sessions as the listeners create them, with clients in id order
+    fn sessions_for(
+        server: &RadarTcpServer,
+        clients: &[(usize, u16)],
+    ) -> (Vec<usize>, HashMap<usize, ClientSession>) {
+        let sessions_map: HashMap<usize, ClientSession> = clients
+            .iter()
+            .map(|&(client_id, port)| {
+                let session = ClientSession::new(port, server.sector_for_port(port));
+                (client_id, session)
+            })
+            .collect();
+        let mut sector_ids: Vec<usize> = sessions_map.keys().copied().collect();
+        sector_ids.sort_unstable();
+        (sector_ids, sessions_map)
+    }
+
+    // sector_index -> client_id, for groups with at most one client per sector
+    fn sector_clients(
+        sector_ids: &[usize],
+        sessions_map: &HashMap<usize, ClientSession>,
+    ) -> HashMap<usize, usize> {
+        assign_sectors(GroupPolicy::Independent, sector_ids, sessions_map, 2)
+            .into_iter()
+            .map(|(client_id, sector)| (sector, client_id))
+            .collect()
+    }
+
+    #[test]
+    fn ports_map_to_their_position_unless_mapped_explicitly() {
+        let server = server();
+        assert_eq!(server.sector_for_port(8080), Some(0));
+        assert_eq!(server.sector_for_port(8081), Some(1));
+        assert_eq!(server.sector_for_port(9000), None);
+
+        let server = server.with_port_sector(8080, 1).with_port_sector(9000, 0);
+        assert_eq!(server.sector_for_port(8080), Some(1));
+        assert_eq!(server.sector_for_port(9000), Some(0));
+
+        // More ports than sectors: the extra ports take any free sector
+        let server = RadarTcpServer::new(vec![8080, 8081, 8082], 1.0);
+        assert_eq!(server.sector_for_port(8082), None);
+    }
+
+    #[test]
+    fn port_client_gets_its_sector_whatever_its_id() {
+        let server = server();
+        // The 8081 client connecting first, last, or with a large id
+        for clients in [
+            [(0, 8081), (1, 8080)],
+            [(0, 8080), (1, 8081)],
+            [(7, 8081), (3, 8080)],
+            [(3, 8081), (7, 8080)],
+        ] {
+            let (sector_ids, sessions_map) = sessions_for(&server, &clients);
+            let sector_clients = sector_clients(&sector_ids, &sessions_map);
+
+            let on_port = |port: u16| clients.iter().find(|c| c.1 == port).unwrap().0;
+            assert_eq!(sector_clients[&1], on_port(8081), "{:?}", clients);
+            assert_eq!(sector_clients[&0], on_port(8080), "{:?}", clients);
+        }
+    }
+
+    #[test]
+    fn unmapped_clients_fill_the_free_sectors() {
+        let server = server();
+        let (sector_ids, sessions_map) = sessions_for(&server, &[(0, 9000), (1, 8081)]);
+        let sector_clients = sector_clients(&sector_ids, &sessions_map);
+        assert_eq!(sector_clients, HashMap::from([(0, 0), (1, 1)]));
+    }
+
+    #[tokio::test]
+    async fn selected_sector_beats_the_port_mapping() {
+        let server = server();
+        let (sector_ids, sessions_map) = sessions_for(&server, &[(0, 8081), (1, 9000)]);
+        let sessions: ClientSessions = Arc::new(Mutex::new(sessions_map));
+        let clients: ClientConnections = Arc::new(Mutex::new(HashMap::new()));
+
+        let response = execute_command(
+            0,
+            Command::SelectSector(0),
+            &clients,
+            &sessions,
+            &ServerStats::default(),
+            2,
+            1,
+        )
+        .await;
+        assert!(matches!(response, Message::Ack(Ack::SectorSelected(0))));
+
+        let sessions_map = sessions.lock().await;
+        let sector_clients = sector_clients(&sector_ids, &sessions_map);
+        assert_eq!(sector_clients, HashMap::from([(0, 0), (1, 1)]));
+    }
+
+    #[tokio::test]
+    async fn start_rejects_a_port_mapped_to_a_missing_sector() {
+        let server = server().with_port_sector(8081, 2);
+        let error = server.start().await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("sector 2"));
+    }
+
+    #[test]
+    fn independent_lone_client_is_served_its_sector_at_once() {
+        let server = server();
+        let mut group = SectorGroup::new(GroupPolicy::Independent, 2);
+        let (sector_ids, sessions_map) = sessions_for(&server, &[(0, 8081)]);
+
+        let tick = group.tick(&sector_ids, &sessions_map);
+        assert_eq!(tick.sector_clients, vec![(0, 1)]);
+        assert_eq!(tick.covered_sectors, 1);
+        assert!(!tick.restart_sequence);
+    }
+
+    #[test]
+    fn independent_clients_share_a_sector() {
+        let server = server();
+        let mut group = SectorGroup::new(GroupPolicy::Independent, 2);
+        let (sector_ids, sessions_map) =
+            sessions_for(&server, &[(0, 8080), (1, 8080), (2, 8080), (3, 9000)]);
+
+        // The unmapped client goes to the least loaded sector
+        let tick = group.tick(&sector_ids, &sessions_map);
+        assert_eq!(tick.sector_clients, vec![(0, 0), (1, 0), (2, 0), (3, 1)]);
+    }
+
+    #[test]
+    fn synchronized_group_waits_for_every_sector() {
+        let server = server();
+        let mut group = SectorGroup::new(GroupPolicy::Synchronized, 2);
+
+        let (sector_ids, sessions_map) = sessions_for(&server, &[(0, 8080), (1, 8080)]);
+        let tick = group.tick(&sector_ids, &sessions_map);
+        assert!(tick.sector_clients.is_empty());
+        assert_eq!(tick.covered_sectors, 1);
+
+        let (sector_ids, sessions_map) = sessions_for(&server, &[(0, 8080), (1, 8080), (2, 8081)]);
+        let tick = group.tick(&sector_ids, &sessions_map);
+        assert_eq!(tick.sector_clients, vec![(0, 0), (2, 1)]); // One client per sector
+    }
+
+    #[test]
+    fn synchronized_sequence_restarts_when_the_group_completes() {
+        let server = server();
+        let mut group = SectorGroup::new(GroupPolicy::Synchronized, 2);
+        let (half, half_sessions) = sessions_for(&server, &[(0, 8080)]);
+        let (full, full_sessions) = sessions_for(&server, &[(0, 8080), (1, 8081)]);
+
+        let restarts: Vec<bool> = [
+            (&half, &half_sessions),
+            (&full, &full_sessions),
+            (&full, &full_sessions),
+            (&half, &half_sessions),
+            (&full, &full_sessions),
+        ]
+        .into_iter()
+        .map(|(sector_ids, sessions_map)| group.tick(sector_ids, sessions_map).restart_sequence)
+        .collect();
+        assert_eq!(restarts, vec![false, true, false, false, true]);
+
+        // An independent group never restarts the sequence
+        let mut group = SectorGroup::new(GroupPolicy::Independent, 2);
+        assert!(!group.tick(&half, &half_sessions).restart_sequence);
+        assert!(!group.tick(&full, &full_sessions).restart_sequence);
+    }
+
+    fn job(client_id: usize, sector_index: Option<usize>, subscription: Subscription) -> SweepJob {
+        SweepJob {
+            client_id,
+            sector_index,
+            subscription,
+            compression: Compression::None,
+            delta: None,
+        }
+    }
+
+    // Frames of `jobs` encoded from one complete sweep, in job order
+    fn encoded_frames(jobs: Vec<SweepJob>) -> Vec<Bytes> {
+        let complete_sweep = RadarSimulator::new().generate_complete_sweep();
+        let stats = ServerStats::default();
+        encode_sweep_jobs(jobs, &complete_sweep, &SectorPlan::default(), &stats)
+            .into_iter()
+            .map(|(_, frame)| frame.bytes)
+            .collect()
+    }
+
+    #[test]
+    fn subscribers_of_a_sector_share_one_frame() {
+        let decimated = Subscription {
+            range_decimation: Some(4),
+            ..Subscription::default()
+        };
+        let rate_limited = Subscription {
+            rate_hz: Some(0.5), // Not part of the shape
+            ..Subscription::default()
+        };
+        let frames = encoded_frames(vec![
+            job(0, Some(0), Subscription::default()),
+            job(1, Some(0), rate_limited),
+            job(2, Some(0), decimated.clone()),
+            job(3, Some(0), decimated),
+            job(4, Some(1), Subscription::default()),
+        ]);
+
+        assert_eq!(frames[0].as_ptr(), frames[1].as_ptr());
+        assert_eq!(frames[2].as_ptr(), frames[3].as_ptr());
+        assert_ne!(frames[0].as_ptr(), frames[2].as_ptr(), "other shape");
+        assert!(frames[2].len() < frames[0].len());
+        assert_ne!(frames[0].as_ptr(), frames[4].as_ptr(), "other sector");
+    }
+
+    #[test]
+    fn identical_custom_windows_share_one_frame() {
+        let window = |azimuth_start, azimuth_end| Subscription {
+            azimuth: Some(AzimuthWindow {
+                azimuth_start,
+                azimuth_end,
+            }),
+            ..Subscription::default()
+        };
+        let frames = encoded_frames(vec![
+            job(0, None, window(10.0, 50.0)),
+            job(1, None, window(10.0, 50.0)),
+            job(2, None, window(20.0, 60.0)),
+        ]);
+
+        assert_eq!(frames[0].as_ptr(), frames[1].as_ptr());
+        assert_ne!(frames[0].as_ptr(), frames[2].as_ptr());
+    }
+
+    #[test]
+    fn other_compression_gets_its_own_frame() {
+        let mut compressed = job(1, Some(0), Subscription::default());
+        compressed.compression = Compression::Lz4;
+        let frames = encoded_frames(vec![job(0, Some(0), Subscription::default()), compressed]);
+        assert_ne!(frames[0].as_ptr(), frames[1].as_ptr());
+    }
 }
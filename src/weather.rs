@@ -0,0 +1,295 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+// Echoes are uniform up to this share of the echo top, then fade out
+const PROFILE_TAPER: f32 = 0.6;
+
+// Shape and life cycle of a precipitation cell. The echo is an ellipse
+// centred on the target: strongest at the core, fading to nothing at the
+// edge, broken up by internal texture that drifts with the cell. In height
+// it reaches from the ground up to its echo top.
+//
+//   cell = { length_km = 12.0, width_km = 5.0, orientation_deg = 30.0, top_km = 12.0,
+//            growth_s = 30.0, mature_s = 60.0, decay_s = 45.0 }
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WeatherCell {
+    #[serde(default = "default_length_km")]
+    pub length_km: f32, // Extent along the major axis at maturity
+    #[serde(default = "default_width_km")]
+    pub width_km: f32, // Extent across the major axis at maturity
+    #[serde(default)]
+    pub orientation_deg: f32, // Direction of the major axis (degrees from north)
+    #[serde(default = "default_texture")]
+    pub texture: f32, // 0 = smooth, 1 = strongly cellular interior
+    #[serde(default = "default_top_km")]
+    pub top_km: f32, // Echo top at maturity, height above the radar
+    #[serde(default)]
+    pub growth_s: f32, // Time to build up to full strength
+    #[serde(default)]
+    pub mature_s: Option<f32>, // Time at full strength, `None` means forever
+    #[serde(default)]
+    pub decay_s: f32, // Time to dissipate after maturity
+}
+
+impl Default for WeatherCell {
+    fn default() -> Self {
+        Self {
+            length_km: default_length_km(),
+            width_km: default_width_km(),
+            orientation_deg: 0.0,
+            texture: default_texture(),
+            top_km: default_top_km(),
+            growth_s: 0.0,
+            mature_s: None,
+            decay_s: 0.0,
+        }
+    }
+}
+
+impl WeatherCell {
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let valid_size = |km: f32| km.is_finite() && km > 0.0;
+        if !valid_size(self.length_km) || !valid_size(self.width_km) {
+            return Err(format!(
+                "Invalid cell size {} x {} km",
+                self.length_km, self.width_km
+            )
+            .into());
+        }
+        if !self.orientation_deg.is_finite() {
+            return Err(format!("Invalid cell orientation {}°", self.orientation_deg).into());
+        }
+        if !valid_size(self.top_km) {
+            return Err(format!("Invalid echo top {} km", self.top_km).into());
+        }
+        if !(0.0..=1.0).contains(&self.texture) {
+            return Err(format!("Cell texture {} is outside 0..1", self.texture).into());
+        }
+        let durations = [self.growth_s, self.mature_s.unwrap_or(0.0), self.decay_s];
+        if durations.iter().any(|s| !s.is_finite() || *s < 0.0) {
+            return Err("Invalid cell life cycle".into());
+        }
+        Ok(())
+    }
+
+    // Strength between 0 and 1 at `age_s` seconds after the cell appeared:
+    // rising while it grows, 1 while mature, falling to 0 as it decays
+    pub fn strength(&self, age_s: f32) -> f32 {
+        if age_s < 0.0 {
+            return 0.0;
+        }
+        if age_s < self.growth_s {
+            return age_s / self.growth_s;
+        }
+        let Some(mature_s) = self.mature_s else {
+            return 1.0;
+        };
+        let decay_age = age_s - self.growth_s - mature_s;
+        if decay_age < 0.0 {
+            1.0
+        } else if decay_age < self.decay_s {
+            1.0 - decay_age / self.decay_s
+        } else {
+            0.0 // Dissipated
+        }
+    }
+
+    // Half-axes (km) at `strength`: young and dying cells are smaller
+    pub fn half_axes_km(&self, strength: f32) -> (f32, f32) {
+        let scale = 0.5 + 0.5 * strength;
+        (self.length_km * scale / 2.0, self.width_km * scale / 2.0)
+    }
+
+    // Share of the echo at `height_km` between 0 and 1: full strength low
+    // down, fading to nothing at the echo top, which young and dying cells
+    // do not reach
+    pub fn vertical_profile(&self, height_km: f32, strength: f32) -> f32 {
+        let top_km = self.top_km * (0.5 + 0.5 * strength);
+        let taper_km = top_km * PROFILE_TAPER;
+        if height_km <= taper_km {
+            1.0
+        } else if height_km < top_km {
+            (top_km - height_km) / (top_km - taper_km)
+        } else {
+            0.0
+        }
+    }
+}
+
+fn default_length_km() -> f32 {
+    6.0
+}
+
+fn default_width_km() -> f32 {
+    4.0
+}
+
+fn default_texture() -> f32 {
+    0.4
+}
+
+fn default_top_km() -> f32 {
+    10.0
+}
+
+// Steering wind that carries every weather cell along
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Wind {
+    pub speed_kmh: f32,
+    pub from_deg: f32, // Direction the wind blows from, as in weather reports
+}
+
+impl Wind {
+    // Velocity the wind gives a cell, as [east, north] in km/h
+    pub fn velocity_kmh(&self) -> [f32; 2] {
+        let (sin, cos) = (self.from_deg + 180.0).to_radians().sin_cos();
+        [self.speed_kmh * sin, self.speed_kmh * cos]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn life_cycle() -> WeatherCell {
+        WeatherCell {
+            growth_s: 30.0,
+            mature_s: Some(60.0),
+            decay_s: 45.0,
+            ..WeatherCell::default()
+        }
+    }
+
+    #[test]
+    fn strength_follows_the_life_cycle() {
+        let cell = life_cycle();
+        let expected = [
+            (-1.0, 0.0),   // Not there yet
+            (0.0, 0.0),    // Appears
+            (15.0, 0.5),   // Half grown
+            (30.0, 1.0),   // Mature
+            (89.9, 1.0),   // Still mature
+            (90.0, 1.0),   // Starts to decay
+            (112.5, 0.5),  // Half gone
+            (135.0, 0.0),  // Dissipated
+            (1000.0, 0.0), // And stays gone
+        ];
+        for (age_s, strength) in expected {
+            assert!(
+                close(cell.strength(age_s), strength),
+                "strength {} at {} s, expected {}",
+                cell.strength(age_s),
+                age_s,
+                strength
+            );
+        }
+    }
+
+    #[test]
+    fn cells_without_a_life_cycle_stay_at_full_strength() {
+        let cell = WeatherCell::default();
+        assert_eq!(cell.strength(0.0), 1.0);
+        assert_eq!(cell.strength(1.0e6), 1.0);
+
+        // No growth and no decay: full strength for exactly the mature time
+        let brief = WeatherCell {
+            mature_s: Some(10.0),
+            ..WeatherCell::default()
+        };
+        assert_eq!(brief.strength(9.9), 1.0);
+        assert_eq!(brief.strength(10.0), 0.0);
+    }
+
+    #[test]
+    fn young_cells_are_smaller() {
+        let cell = WeatherCell {
+            length_km: 12.0,
+            width_km: 5.0,
+            ..WeatherCell::default()
+        };
+        assert_eq!(cell.half_axes_km(1.0), (6.0, 2.5));
+        assert_eq!(cell.half_axes_km(0.0), (3.0, 1.25));
+        assert_eq!(cell.half_axes_km(0.5), (4.5, 1.875));
+    }
+
+    #[test]
+    fn echo_fades_out_towards_the_top() {
+        let cell = WeatherCell::default(); // 10 km top
+        assert_eq!(cell.vertical_profile(0.0, 1.0), 1.0);
+        assert_eq!(cell.vertical_profile(6.0, 1.0), 1.0); // Full strength up to 60% of the top
+        assert!(close(cell.vertical_profile(8.0, 1.0), 0.5));
+        assert_eq!(cell.vertical_profile(10.0, 1.0), 0.0);
+        assert_eq!(cell.vertical_profile(15.0, 1.0), 0.0);
+
+        // A cell just forming reaches half as high
+        assert_eq!(cell.vertical_profile(3.0, 0.0), 1.0);
+        assert!(close(cell.vertical_profile(4.0, 0.0), 0.5));
+        assert_eq!(cell.vertical_profile(5.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn invalid_cells_are_rejected() {
+        assert!(life_cycle().validate().is_ok());
+
+        let invalid = [
+            WeatherCell {
+                length_km: 0.0,
+                ..life_cycle()
+            },
+            WeatherCell {
+                width_km: f32::INFINITY,
+                ..life_cycle()
+            },
+            WeatherCell {
+                orientation_deg: f32::NAN,
+                ..life_cycle()
+            },
+            WeatherCell {
+                top_km: 0.0,
+                ..life_cycle()
+            },
+            WeatherCell {
+                texture: 1.5,
+                ..life_cycle()
+            },
+            WeatherCell {
+                growth_s: -1.0,
+                ..life_cycle()
+            },
+            WeatherCell {
+                mature_s: Some(f32::NAN),
+                ..life_cycle()
+            },
+        ];
+        for cell in invalid {
+            assert!(cell.validate().is_err(), "{:?} accepted", cell);
+        }
+    }
+
+    #[test]
+    fn wind_blows_from_its_direction() {
+        // A westerly carries cells east
+        let [east, north] = Wind {
+            speed_kmh: 36.0,
+            from_deg: 270.0,
+        }
+        .velocity_kmh();
+        assert!(close(east, 36.0) && close(north, 0.0));
+
+        // A northerly carries them south
+        let [east, north] = Wind {
+            speed_kmh: 20.0,
+            from_deg: 0.0,
+        }
+        .velocity_kmh();
+        assert!(close(east, 0.0) && close(north, -20.0));
+
+        assert_eq!(Wind::default().velocity_kmh(), [0.0, 0.0]);
+    }
+}
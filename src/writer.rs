@@ -0,0 +1,409 @@
+use crate::fault::FrameDelay;
+use bytes::Bytes;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::spawn;
+use tokio::sync::Notify;
+use tokio::time::{sleep_until, Instant};
+
+// What to do when a client's queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    // Discard the oldest queued frame to make room (freshest data wins)
+    #[default]
+    DropOldest,
+    // Discard the frame being queued
+    DropNewest,
+    // Close the connection of a consumer that cannot keep up
+    Disconnect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriterConfig {
+    pub queue_capacity: usize, // Frames waiting to be written per client
+    pub overflow_policy: OverflowPolicy,
+}
+
+impl Default for WriterConfig {
+    fn default() -> Self {
+        Self {
+            queue_capacity: 8,
+            overflow_policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+// Frame counters for one client
+#[derive(Debug, Default)]
+pub struct WriterStats {
+    pub sent_frames: AtomicU64,
+    pub dropped_frames: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueError {
+    Closed,     // Writer task has stopped, the client is gone
+    Overflowed, // Queue full under `OverflowPolicy::Disconnect`
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueueError::Closed => write!(f, "client writer is closed"),
+            QueueError::Overflowed => write!(f, "client queue overflowed, disconnecting"),
+        }
+    }
+}
+
+impl std::error::Error for QueueError {}
+
+impl From<QueueError> for io::Error {
+    fn from(e: QueueError) -> Self {
+        io::Error::new(io::ErrorKind::BrokenPipe, e)
+    }
+}
+
+// Encoded frame plus when it may be written. The deadline is fixed when the
+// frame is queued, so latency does not add up across queued frames.
+#[derive(Debug, Clone)]
+struct OutgoingFrame {
+    bytes: Bytes,
+    deadline: Option<Instant>, // None: write as soon as possible
+    transmission: Duration,    // Link time, serialized with the frames around it
+}
+
+#[derive(Debug, Default)]
+struct Frames {
+    control: VecDeque<Bytes>, // Replies and headers: written first, never dropped
+    data: VecDeque<OutgoingFrame>, // Sweeps and radials, bounded by `queue_capacity`
+}
+
+#[derive(Debug, Default)]
+struct FrameQueue {
+    frames: Mutex<Frames>,
+    notify: Notify,
+    closed: AtomicBool,
+}
+
+// Handle to a connection's dedicated writer task. Queuing never blocks,
+// so one slow client cannot stall the broadcaster or other clients.
+#[derive(Debug, Clone)]
+pub struct ClientWriter {
+    config: WriterConfig,
+    queue: Arc<FrameQueue>,
+    stats: Arc<WriterStats>,
+}
+
+impl ClientWriter {
+    pub fn spawn<W>(client_id: usize, stream: W, config: WriterConfig) -> Self
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let writer = Self {
+            config,
+            queue: Arc::new(FrameQueue::default()),
+            stats: Arc::new(WriterStats::default()),
+        };
+
+        spawn(run_writer(
+            client_id,
+            stream,
+            Arc::clone(&writer.queue),
+            Arc::clone(&writer.stats),
+        ));
+
+        writer
+    }
+
+    pub fn stats(&self) -> &WriterStats {
+        &self.stats
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.queue.closed.load(Ordering::SeqCst)
+    }
+
+    pub fn send(&self, bytes: Bytes) -> Result<(), QueueError> {
+        self.send_delayed(bytes, FrameDelay::default())
+    }
+
+    // Queue a frame to be written after `delay` (used for fault injection)
+    pub fn send_delayed(&self, bytes: Bytes, delay: FrameDelay) -> Result<(), QueueError> {
+        if self.is_closed() {
+            return Err(QueueError::Closed);
+        }
+
+        let mut frames = self.queue.frames.lock().unwrap();
+        if frames.data.len() >= self.config.queue_capacity.max(1) {
+            match self.config.overflow_policy {
+                OverflowPolicy::DropOldest => {
+                    frames.data.pop_front();
+                    self.stats.dropped_frames.fetch_add(1, Ordering::SeqCst);
+                }
+                OverflowPolicy::DropNewest => {
+                    self.stats.dropped_frames.fetch_add(1, Ordering::SeqCst);
+                    return Ok(());
+                }
+                OverflowPolicy::Disconnect => {
+                    self.stats
+                        .dropped_frames
+                        .fetch_add(frames.data.len() as u64 + 1, Ordering::SeqCst);
+                    frames.data.clear();
+                    drop(frames);
+                    self.close();
+                    return Err(QueueError::Overflowed);
+                }
+            }
+        }
+
+        let deadline = (!delay.latency.is_zero()).then(|| Instant::now() + delay.latency);
+        frames.data.push_back(OutgoingFrame {
+            bytes,
+            deadline,
+            transmission: delay.transmission,
+        });
+        drop(frames);
+        self.queue.notify.notify_one();
+        Ok(())
+    }
+
+    // Queue a command reply or stream header ahead of any data frames. These
+    // are small and rare, so they bypass the capacity and overflow policy.
+    pub fn send_control(&self, bytes: Bytes) -> Result<(), QueueError> {
+        if self.is_closed() {
+            return Err(QueueError::Closed);
+        }
+
+        self.queue.frames.lock().unwrap().control.push_back(bytes);
+        self.queue.notify.notify_one();
+        Ok(())
+    }
+
+    // Stop the writer task once the queue drains
+    pub fn close(&self) {
+        self.queue.closed.store(true, Ordering::SeqCst);
+        self.queue.notify.notify_one();
+    }
+}
+
+async fn run_writer<W: AsyncWrite + Unpin>(
+    client_id: usize,
+    mut stream: W,
+    queue: Arc<FrameQueue>,
+    stats: Arc<WriterStats>,
+) {
+    let mut link_free = Instant::now(); // When the simulated link finishes the previous frame
+
+    loop {
+        let next = {
+            let mut frames = queue.frames.lock().unwrap();
+            match frames.control.pop_front() {
+                Some(bytes) => Some(OutgoingFrame {
+                    bytes,
+                    deadline: None,
+                    transmission: Duration::ZERO,
+                }),
+                None => frames.data.pop_front(),
+            }
+        };
+        let frame = match next {
+            Some(frame) => frame,
+            None if queue.closed.load(Ordering::SeqCst) => break,
+            None => {
+                queue.notify.notified().await;
+                continue;
+            }
+        };
+
+        if let Some(deadline) = frame.deadline {
+            sleep_until(deadline).await;
+        }
+        if !frame.transmission.is_zero() {
+            link_free = link_free.max(Instant::now()) + frame.transmission;
+            sleep_until(link_free).await;
+        }
+
+        let result = match stream.write_all(&frame.bytes).await {
+            Ok(()) => stream.flush().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to write to client {}: {}", client_id, e);
+            queue.closed.store(true, Ordering::SeqCst);
+            break;
+        }
+        stats.sent_frames.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let _ = stream.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{duplex, AsyncReadExt, DuplexStream};
+
+    fn spawn_writer(
+        queue_capacity: usize,
+        overflow_policy: OverflowPolicy,
+    ) -> (ClientWriter, DuplexStream) {
+        let (client, server) = duplex(4096);
+        let config = WriterConfig {
+            queue_capacity,
+            overflow_policy,
+        };
+        (ClientWriter::spawn(0, server, config), client)
+    }
+
+    fn frame(id: u8) -> Bytes {
+        Bytes::from(vec![id; 4])
+    }
+
+    // Everything the writer sends until it shuts the stream down
+    async fn received(mut client: DuplexStream) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        client.read_to_end(&mut bytes).await.unwrap();
+        bytes
+    }
+
+    fn frames(ids: &[u8]) -> Vec<u8> {
+        ids.iter().flat_map(|&id| frame(id)).collect()
+    }
+
+    fn dropped(writer: &ClientWriter) -> u64 {
+        writer.stats().dropped_frames.load(Ordering::SeqCst)
+    }
+
+    // The writer task only runs once the test yields, so the queue fills up first
+
+    #[tokio::test]
+    async fn drop_oldest_keeps_the_newest_frames() {
+        let (writer, client) = spawn_writer(2, OverflowPolicy::DropOldest);
+        for id in 1..=5 {
+            assert_eq!(writer.send(frame(id)), Ok(()));
+        }
+        assert_eq!(dropped(&writer), 3);
+
+        writer.close();
+        assert_eq!(received(client).await, frames(&[4, 5]));
+        assert_eq!(writer.stats().sent_frames.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn drop_newest_keeps_the_queued_frames() {
+        let (writer, client) = spawn_writer(2, OverflowPolicy::DropNewest);
+        for id in 1..=5 {
+            assert_eq!(writer.send(frame(id)), Ok(()));
+        }
+        assert_eq!(dropped(&writer), 3);
+
+        writer.close();
+        assert_eq!(received(client).await, frames(&[1, 2]));
+    }
+
+    #[tokio::test]
+    async fn disconnect_closes_a_client_that_falls_behind() {
+        let (writer, client) = spawn_writer(2, OverflowPolicy::Disconnect);
+        assert_eq!(writer.send(frame(1)), Ok(()));
+        assert_eq!(writer.send(frame(2)), Ok(()));
+        assert_eq!(writer.send(frame(3)), Err(QueueError::Overflowed));
+
+        // Both queued frames and the rejected one count as dropped
+        assert_eq!(dropped(&writer), 3);
+        assert!(writer.is_closed());
+        assert_eq!(writer.send(frame(4)), Err(QueueError::Closed));
+        assert!(received(client).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn closed_writer_rejects_frames_but_flushes_the_queue() {
+        let (writer, client) = spawn_writer(8, OverflowPolicy::DropOldest);
+        assert!(!writer.is_closed());
+        assert_eq!(writer.send(frame(1)), Ok(()));
+
+        writer.close();
+        assert!(writer.is_closed());
+        assert_eq!(writer.send(frame(2)), Err(QueueError::Closed));
+        assert_eq!(writer.send_control(frame(3)), Err(QueueError::Closed));
+        assert_eq!(received(client).await, frames(&[1]));
+    }
+
+    #[tokio::test]
+    async fn control_frames_skip_the_overflow_policy() {
+        let (writer, client) = spawn_writer(1, OverflowPolicy::DropOldest);
+        assert_eq!(writer.send(frame(1)), Ok(()));
+        assert_eq!(writer.send(frame(2)), Ok(()));
+        assert_eq!(writer.send_control(frame(10)), Ok(()));
+        assert_eq!(writer.send_control(frame(11)), Ok(()));
+        assert_eq!(dropped(&writer), 1);
+
+        // Replies go out ahead of the data still waiting
+        writer.close();
+        assert_eq!(received(client).await, frames(&[10, 11, 2]));
+
+        let (writer, _client) = spawn_writer(1, OverflowPolicy::Disconnect);
+        assert_eq!(writer.send(frame(1)), Ok(()));
+        for id in 10..20 {
+            assert_eq!(writer.send_control(frame(id)), Ok(()));
+        }
+        assert!(!writer.is_closed());
+        assert_eq!(dropped(&writer), 0);
+    }
+
+    #[tokio::test]
+    async fn each_delayed_frame_keeps_its_own_deadline() {
+        let (writer, mut client) = spawn_writer(8, OverflowPolicy::DropOldest);
+        let latency = Duration::from_millis(100);
+        let start = Instant::now();
+        writer
+            .send_delayed(frame(1), FrameDelay::latency(latency))
+            .unwrap();
+        writer.send(frame(2)).unwrap();
+        writer
+            .send_delayed(frame(3), FrameDelay::latency(latency))
+            .unwrap();
+
+        // All three are due 100 ms after queuing, not 100 ms after each other
+        let mut bytes = [0u8; 12];
+        client.read_exact(&mut bytes).await.unwrap();
+        let elapsed = start.elapsed();
+        assert_eq!(bytes.to_vec(), frames(&[1, 2, 3]));
+        assert!(elapsed >= latency, "{:?}", elapsed);
+        assert!(elapsed < latency * 2, "{:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn transmission_time_queues_up_like_a_link() {
+        let (writer, mut client) = spawn_writer(8, OverflowPolicy::DropOldest);
+        let transmission = Duration::from_millis(40);
+        let start = Instant::now();
+        for id in 1..=3 {
+            let delay = FrameDelay {
+                latency: Duration::ZERO,
+                transmission,
+            };
+            writer.send_delayed(frame(id), delay).unwrap();
+        }
+
+        let mut arrivals = Vec::new();
+        for id in 1..=3 {
+            let mut bytes = [0u8; 4];
+            client.read_exact(&mut bytes).await.unwrap();
+            assert_eq!(bytes.to_vec(), frames(&[id]));
+            arrivals.push(start.elapsed());
+        }
+        for (index, arrival) in arrivals.iter().enumerate() {
+            let due = transmission * (index as u32 + 1);
+            assert!(
+                *arrival >= due && *arrival < due + transmission,
+                "{:?}",
+                arrivals
+            );
+        }
+    }
+}
@@ -1,20 +1,16 @@
-use std::io::{self, Write};
 use std::time::Duration;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
-use tokio::time::sleep;
+use rust_tcp_server::protocol::RadarClient;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🔗 Connecting to radar server on port 8080...");
 
-    let mut stream = TcpStream::connect("127.0.0.1:8080").await?;
+    let mut client = RadarClient::connect("127.0.0.1", 8080).await?;
     println!("✅ Connected!");
 
     // Send SEND_DATA command to start receiving data
     println!("\n📡 Sending SEND_DATA command...");
-    stream.write_all(b"SEND_DATA").await?;
-    stream.flush().await?;
+    client.start_streaming().await?;
     println!("✅ SEND_DATA command sent!");
 
     // Read a few radar sweeps
@@ -23,7 +19,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut sweep_count = 0;
 
     while start_time.elapsed() < Duration::from_secs(5) {
-        match tokio::time::timeout(Duration::from_millis(500), read_radar_sweep(&mut stream)).await
+        match tokio::time::timeout(Duration::from_millis(500), read_radar_sweep(&mut client)).await
         {
             Ok(Ok(sequence_id)) => {
                 sweep_count += 1;
@@ -43,8 +39,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     println!("\n🛑 Sending STOP command...");
-    stream.write_all(b"STOP").await?;
-    stream.flush().await?;
+    client.stop_streaming().await?;
     println!("✅ STOP command sent!");
 
     // Wait a bit and verify no more data is received
@@ -53,7 +48,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut data_received_after_stop = false;
 
     while stop_time.elapsed() < Duration::from_secs(3) {
-        match tokio::time::timeout(Duration::from_millis(100), read_radar_sweep(&mut stream)).await
+        match tokio::time::timeout(Duration::from_millis(100), read_radar_sweep(&mut client)).await
         {
             Ok(Ok(sequence_id)) => {
                 println!("⚠️  Still received sweep #{} after STOP!", sequence_id);
@@ -72,8 +67,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Test resuming with SEND_DATA again
     println!("\n🔄 Testing resume with SEND_DATA command...");
-    stream.write_all(b"SEND_DATA").await?;
-    stream.flush().await?;
+    client.start_streaming().await?;
     println!("✅ SEND_DATA command sent again!");
 
     // Read a few more sweeps to verify resumption
@@ -82,7 +76,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut resumed_sweep_count = 0;
 
     while resume_time.elapsed() < Duration::from_secs(2) {
-        match tokio::time::timeout(Duration::from_millis(500), read_radar_sweep(&mut stream)).await
+        match tokio::time::timeout(Duration::from_millis(500), read_radar_sweep(&mut client)).await
         {
             Ok(Ok(sequence_id)) => {
                 resumed_sweep_count += 1;
@@ -114,16 +108,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-async fn read_radar_sweep(stream: &mut TcpStream) -> Result<u64, Box<dyn std::error::Error>> {
-    // Read data length first
-    let data_len = stream.read_u64().await? as usize;
-
-    // Read the serialized data
-    let mut buffer = vec![0u8; data_len];
-    stream.read_exact(&mut buffer).await?;
-
-    // Deserialize to get sequence_id
-    let radar_sweep: rust_tcp_server::RadarSweep = bincode::deserialize(&buffer)?;
+async fn read_radar_sweep(client: &mut RadarClient) -> Result<u64, Box<dyn std::error::Error>> {
+    let radar_sweep = client
+        .next_sweep()
+        .await?
+        .ok_or("Connection closed by server")?;
 
     Ok(radar_sweep.sequence_id)
 }
@@ -1,52 +1,57 @@
-use serde::{Deserialize, Serialize};
+use rust_tcp_server::protocol::RadarClient;
+use rust_tcp_server::VolumeAssembler;
 use std::error::Error;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct RadarSweep {
-    timestamp: u64,
-    sequence_id: u64,
-    azimuth_start: f32,
-    azimuth_end: f32,
-    range_bins: Vec<f32>,
-    data: Vec<Vec<f32>>,
-    overlap_region: Vec<Vec<f32>>,
-    client_id: usize,
-}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     // Connect to the server
-    let mut stream = TcpStream::connect("127.0.0.1:8080").await?;
+    let mut client = RadarClient::connect("127.0.0.1", 8080).await?;
     println!("Connected to radar server on port 8080");
 
-    // Receive radar data continuously
-    loop {
-        // Read the size of the incoming data
-        let data_size = stream.read_u64().await?;
-
-        // Read the serialized data
-        let mut buffer = vec![0u8; data_size as usize];
-        stream.read_exact(&mut buffer).await?;
-
-        // Deserialize the radar sweep
-        let radar_sweep: RadarSweep = bincode::deserialize(&buffer)?;
-
+    // Receive radar data continuously, collecting the tilts of each volume scan
+    let mut volumes: Option<VolumeAssembler> = None;
+    while let Some(radar_sweep) = client.next_sweep().await? {
         println!(
-            "Received sweep {} from server: Az {:.1}°-{:.1}°, {} azimuth bins, {} range bins, overlap: {} bins",
+            "Received sweep {} from server: volume {} tilt {} ({:.1}°), Az {:.1}°-{:.1}°, {} azimuth bins, {} range bins, overlap: {} bins",
             radar_sweep.sequence_id,
+            radar_sweep.volume_id,
+            radar_sweep.tilt,
+            radar_sweep.elevation_deg,
             radar_sweep.azimuth_start,
             radar_sweep.azimuth_end,
-            radar_sweep.data.len(),
-            radar_sweep.data.get(0).map_or(0, |row| row.len()),
-            radar_sweep.overlap_region.len()
+            radar_sweep.azimuth_count(),
+            radar_sweep.range_count(),
+            radar_sweep.overlap_azimuth_count()
         );
+        println!("  Fields: {}", radar_sweep.field_names().join(", "));
 
         // Print some sample data values
-        if !radar_sweep.data.is_empty() && !radar_sweep.data[0].is_empty() {
-            let sample_intensity = radar_sweep.data[0][0];
-            println!("  Sample intensity at [0,0]: {:.6}", sample_intensity);
+        for field in &radar_sweep.fields {
+            if let Some(sample) = field.data.get(0, 0) {
+                println!(
+                    "  Sample {} at [0,0]: {:.6} {}",
+                    field.name, sample, field.units
+                );
+            }
+        }
+
+        // The scan strategy arrives in the stream header, before any sweep
+        let assembler = volumes.get_or_insert_with(|| {
+            let scan = client
+                .stream_header()
+                .map(|header| header.scan.clone())
+                .unwrap_or_default();
+            VolumeAssembler::new(&scan)
+        });
+        if let Some(volume) = assembler.push(radar_sweep) {
+            println!(
+                "🧊 Volume {} complete: {} tilts at {:?}°",
+                volume.volume_id,
+                volume.sweeps.len(),
+                volume.elevations_deg()
+            );
         }
     }
+    println!("Server closed the connection");
+    Ok(())
 }